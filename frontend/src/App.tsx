@@ -25,6 +25,7 @@ import {
 } from '@mui/icons-material';
 import { appDataDir, join } from '@tauri-apps/api/path';
 import { readTextFile, exists } from '@tauri-apps/plugin-fs';
+import { describeError } from "./errors";
 import "./App.css";
 
 const CONFIG_FILE_NAME = 'audio_devices.json';
@@ -35,15 +36,22 @@ interface AudioDevice {
   is_default: boolean;
 }
 
+// 后端 auto-hide-tick 事件的内容
+interface AutoHideStatus {
+  active: boolean;
+  remaining: number;
+  paused: boolean;
+}
+
 function App() {
   const [currentAudioDevice, setCurrentAudioDevice] = useState<AudioDevice | null>(null);
   const [configuredDevices, setConfiguredDevices] = useState<string[]>([]);
   const [availableDevices, setAvailableDevices] = useState<AudioDevice[]>([]);
+  const [deviceError, setDeviceError] = useState<string | null>(null);
   const containerRef = useRef<HTMLDivElement>(null);
   const [isDragging, setIsDragging] = useState(false);
   const [isPinned, setIsPinned] = useState(false);
   const [countdown, setCountdown] = useState<number | null>(null);
-  const [countdownTimer, setCountdownTimer] = useState<number | null>(null);
   const [darkMode, setDarkMode] = useState(false);
 
   // 创建主题
@@ -80,6 +88,9 @@ function App() {
 
     // 添加键盘事件监听器，用于打开开发者工具
     const handleKeyDown = (event: KeyboardEvent) => {
+      // 任何按键都算用户交互，取消自动隐藏
+      cancelAutoHide();
+
       if (event.key === 'F12' || (event.ctrlKey && event.shiftKey && event.key === 'I')) {
         event.preventDefault();
         // 在开发模式下打开开发者工具
@@ -113,11 +124,10 @@ function App() {
         }
       });
 
-      // 监听测试倒计时事件
-      const unlistenTest = await listen<{delay: number}>('test-countdown', (event) => {
-        console.log('Test countdown triggered:', event.payload);
+      // 自动隐藏倒计时由后端驱动，这里只显示剩余秒数
+      const unlistenAutoHide = await listen<AutoHideStatus>('auto-hide-tick', (event) => {
         if (mounted) {
-          handleWindowClosed(['测试窗口']);
+          setCountdown(event.payload.active ? event.payload.remaining : null);
         }
       });
 
@@ -136,33 +146,31 @@ function App() {
         }
       }, 5000);
 
-      // 每2秒检查窗口变化
-      const windowInterval = setInterval(async () => {
+      // 后端监听规则触发"显示切换器"，窗口已由后端置顶显示并开始倒计时
+      // payload 是触发规则的窗口标题或进程名
+      const unlistenMonitor = await listen<string>('monitor-triggered', (event) => {
+        console.log('Monitor rule triggered by:', event.payload);
         if (mounted) {
-          try {
-            const closedWindows = await invoke<string[]>('check_window_changes');
-            if (closedWindows.length > 0) {
-              console.log('Detected closed windows:', closedWindows);
-              // 有监听的窗口关闭了，置顶显示
-              await handleWindowClosed(closedWindows);
-            }
-          } catch (error) {
-            console.error('Failed to check window changes:', error);
-          }
+          loadCurrentAudioDevice();
+        }
+      });
+
+      // 后端规则切换了音频设备
+      const unlistenDeviceChanged = await listen('audio-device-changed', () => {
+        if (mounted) {
+          loadCurrentAudioDevice();
         }
-      }, 2000);
+      });
 
       return () => {
         mounted = false;
         clearInterval(deviceInterval);
-        clearInterval(windowInterval);
-        if (countdownTimer) {
-          clearInterval(countdownTimer);
-        }
         unlisten();
         unlistenPin();
-        unlistenTest();
+        unlistenAutoHide();
         unlistenDarkMode();
+        unlistenMonitor();
+        unlistenDeviceChanged();
       };
     };
 
@@ -323,6 +331,9 @@ function App() {
 
   // 拖动功能
   const handleMouseDown = async (e: React.MouseEvent) => {
+    // 点击切换器算用户交互，取消自动隐藏
+    cancelAutoHide();
+
     // 避免在可交互元素上拖动
     const target = e.target as HTMLElement;
     if (target.closest('button') ||
@@ -350,13 +361,6 @@ function App() {
     try {
       console.log('Attempting to unpin window...');
 
-      // 清理倒计时
-      if (countdownTimer) {
-        clearInterval(countdownTimer);
-        setCountdownTimer(null);
-      }
-      setCountdown(null);
-
       const result = await invoke('set_window_pinned', { pinned: false });
       console.log('Backend response:', result);
 
@@ -367,59 +371,21 @@ function App() {
     }
   };
 
-  // 处理监听的窗口关闭事件
-  const handleWindowClosed = async (closedWindows: string[]) => {
+  // 取消自动隐藏倒计时（后端没有倒计时时会忽略）
+  const cancelAutoHide = async () => {
     try {
-      console.log('Handling window closed event for:', closedWindows);
-
-      // 置顶窗口
-      const result = await invoke('set_window_pinned', { pinned: true });
-      console.log('Window pinned due to monitored window closure:', result);
-
-      setIsPinned(true);
-
-      // 显示窗口（如果被隐藏了）
-      const window = getCurrentWebviewWindow();
-      await window.show();
-      await window.setFocus();
-
-      // 获取自动隐藏延迟
-      const delay = await invoke<number>('get_auto_hide_delay');
-      console.log(`Will auto-unpin after ${delay} seconds`);
-
-      // 开始倒计时
-      setCountdown(delay);
-
-      // 设置倒计时定时器
-      const timer = setInterval(() => {
-        setCountdown(prev => {
-          if (prev === null || prev <= 1) {
-            // 倒计时结束，自动取消置顶
-            clearInterval(timer);
-            setCountdownTimer(null);
-            setCountdown(null);
-
-            // 异步取消置顶
-            (async () => {
-              try {
-                await invoke('set_window_pinned', { pinned: false });
-                setIsPinned(false);
-                console.log('Auto-unpinned window after countdown');
-              } catch (error) {
-                console.error('Failed to auto-unpin window:', error);
-              }
-            })();
-
-            return null;
-          }
-          return prev - 1;
-        });
-      }, 1000);
-
-      setCountdownTimer(timer);
+      await invoke('cancel_auto_hide');
+    } catch (error) {
+      console.error('Failed to cancel auto hide:', error);
+    }
+  };
 
+  // 鼠标悬停时暂停倒计时，离开后继续
+  const setAutoHideHover = async (hovered: boolean) => {
+    try {
+      await invoke('set_auto_hide_hover', { hovered });
     } catch (error) {
-      console.error('Failed to handle window closed event:', error);
+      console.error('Failed to update auto hide hover:', error);
     }
   };
 
@@ -430,6 +396,7 @@ function App() {
     } catch (error) {
       console.error('Failed to get current audio device:', error);
       setCurrentAudioDevice(null);
+      setDeviceError(describeError(error));
     }
   };
 
@@ -462,9 +429,11 @@ function App() {
     try {
       const devices = await invoke('get_audio_output_devices');
       setAvailableDevices(devices as AudioDevice[]);
+      setDeviceError(null);
     } catch (error) {
       console.error('Failed to get available devices:', error);
       setAvailableDevices([]);
+      setDeviceError(describeError(error));
     }
   };
 
@@ -473,11 +442,13 @@ function App() {
       const device = availableDevices.find(d => d.name === deviceName);
       if (device) {
         await invoke('set_audio_device', { deviceId: device.id });
+        setDeviceError(null);
         await loadCurrentAudioDevice();
         console.log(`Switched to device: ${deviceName}`);
       }
     } catch (error) {
       console.error('Failed to switch audio device:', error);
+      setDeviceError(describeError(error));
     }
   };
 
@@ -503,6 +474,8 @@ function App() {
       <Paper
       ref={containerRef}
       onMouseDown={handleMouseDown}
+      onMouseEnter={() => setAutoHideHover(true)}
+      onMouseLeave={() => setAutoHideHover(false)}
       elevation={8}
       sx={{
         borderRadius: 3,
@@ -536,11 +509,11 @@ function App() {
               音频输出
             </Typography>
           </Box>
-          {isPinned && (
-            <Tooltip title={countdown ? `${countdown}秒后自动取消置顶，点击立即取消` : "取消置顶"}>
+          {(isPinned || countdown !== null) && (
+            <Tooltip title={countdown !== null ? `${countdown}秒后自动隐藏，点击保持显示` : "取消置顶"}>
               <IconButton
                 size="small"
-                onClick={unpinWindow}
+                onClick={countdown !== null ? cancelAutoHide : unpinWindow}
                 color="primary"
                 sx={{
                   position: 'relative',
@@ -551,7 +524,7 @@ function App() {
                 }}
               >
                 <PanToolIcon fontSize="small" />
-                {countdown && (
+                {countdown !== null && (
                   <Box
                     sx={{
                       position: 'absolute',
@@ -589,6 +562,11 @@ function App() {
           <Typography variant="body1" fontWeight="medium">
             {currentAudioDevice ? currentAudioDevice.name : '未知设备'}
           </Typography>
+          {deviceError && (
+            <Typography variant="caption" color="error">
+              {deviceError}
+            </Typography>
+          )}
         </Box>
 
         <Divider />
@@ -0,0 +1,40 @@
+// 后端命令返回的错误，对应 src-tauri/core/src/error.rs 中的 AppError
+export type ErrorCode =
+  | 'device_not_found'
+  | 'device_inactive'
+  | 'backend_unavailable'
+  | 'config_io'
+  | 'permission'
+  | 'invalid_input'
+  | 'not_found'
+  | 'internal';
+
+export interface AppError {
+  code: ErrorCode;
+  message: string;
+  detail: string | null;
+}
+
+// 每种错误码给用户的处理建议
+const ERROR_HINTS: Record<ErrorCode, string> = {
+  device_not_found: '设备不存在，可能已被移除，请刷新设备列表',
+  device_inactive: '设备已被禁用或未插入，请在系统声音设置中启用或重新连接',
+  backend_unavailable: '无法访问系统音频服务，请确认音频服务正在运行',
+  config_io: '无法读写配置文件，请检查应用数据目录',
+  permission: '没有权限，请检查系统权限设置',
+  invalid_input: '输入无效，请检查后重试',
+  not_found: '要操作的对象不存在',
+  internal: '内部错误，请查看日志',
+};
+
+export const isAppError = (error: unknown): error is AppError =>
+  typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+
+// 给用户看的错误说明：处理建议加上后端的原始说明
+export const describeError = (error: unknown): string => {
+  if (isAppError(error)) {
+    const hint = ERROR_HINTS[error.code];
+    return hint ? `${hint}（${error.message}）` : error.message;
+  }
+  return String(error);
+};
@@ -17,11 +17,13 @@ import SettingsIcon from '@mui/icons-material/Settings';
 import InfoIcon from '@mui/icons-material/Info';
 import FolderOpenIcon from '@mui/icons-material/FolderOpen';
 import DescriptionIcon from '@mui/icons-material/Description';
+import RuleIcon from '@mui/icons-material/Rule';
 import { invoke } from '@tauri-apps/api/core';
 import { emit } from '@tauri-apps/api/event';
 import { appDataDir, join } from '@tauri-apps/api/path';
 import { readTextFile, writeTextFile, exists } from '@tauri-apps/plugin-fs';
 
+import { describeError } from './errors';
 import './App.css'; // 可以复用主应用的 CSS，或者创建新的 CSS 文件
 
 const CONFIG_FILE_NAME = 'audio_devices.json';
@@ -32,18 +34,279 @@ interface AudioDevice {
   is_default: boolean;
 }
 
+type MatcherType = 'exact_title' | 'substring' | 'glob' | 'regex' | 'process' | 'class';
+
+type TriggerEvent = 'opened' | 'closed' | 'focused';
+
+type MonitorAction =
+  | { type: 'show_switcher' }
+  | { type: 'switch_to'; device: string }
+  | { type: 'revert' };
+
+interface MonitorRule {
+  matcher: { type: MatcherType; value: string };
+  event: TriggerEvent;
+  action: MonitorAction;
+}
+
+const EVENT_LABELS: Record<TriggerEvent, string> = {
+  opened: '打开时',
+  closed: '关闭时',
+  focused: '获得焦点时',
+};
+
+type ProcessTrigger = 'started' | 'exited';
+
+interface ProcessRule {
+  process: string;
+  event: ProcessTrigger;
+  action: MonitorAction;
+}
+
+const PROCESS_EVENT_LABELS: Record<ProcessTrigger, string> = {
+  started: '启动时',
+  exited: '退出时',
+};
+
+type Day = 'mon' | 'tue' | 'wed' | 'thu' | 'fri' | 'sat' | 'sun';
+
+type ScheduleTrigger =
+  | { type: 'weekly'; days: Day[]; start: string; end: string }
+  | { type: 'cron'; expression: string };
+
+interface ScheduleRule {
+  name: string;
+  trigger: ScheduleTrigger;
+  action: MonitorAction;
+  end_action: MonitorAction | null;
+  missed: 'catch_up' | 'skip';
+}
+
+const DAY_LABELS: Record<Day, string> = {
+  mon: '一',
+  tue: '二',
+  wed: '三',
+  thu: '四',
+  fri: '五',
+  sat: '六',
+  sun: '日',
+};
+
+const describeSchedule = (trigger: ScheduleTrigger) => {
+  if (trigger.type === 'cron') {
+    return `cron ${trigger.expression}`;
+  }
+  const days = trigger.days.length === 0
+    ? '每天'
+    : '周' + trigger.days.map(day => DAY_LABELS[day]).join('');
+  return `${days} ${trigger.start}-${trigger.end}`;
+};
+
+// 规则引擎的规则，触发器/条件/动作的结构见后端 rules.rs
+interface EngineRule {
+  id: string;
+  name: string;
+  enabled: boolean;
+  trigger: { type: string; [key: string]: unknown };
+  conditions: { type: string; [key: string]: unknown }[];
+  actions: { type: string; [key: string]: unknown }[];
+}
+
+interface RuleFiring {
+  rule_id: string;
+  rule_name: string;
+  trigger: string;
+  skipped_by: string | null;
+  actions: { action: string; error: string | null }[];
+  dry_run: boolean;
+  timestamp: number;
+}
+
+type HistoryKind = 'window' | 'process' | 'device' | 'rule';
+
+interface HistoryEvent {
+  timestamp: number;
+  kind: HistoryKind;
+  summary: string;
+  detail: unknown;
+}
+
+const HISTORY_KIND_LABELS: Record<HistoryKind, string> = {
+  window: '窗口',
+  process: '进程',
+  device: '设备',
+  rule: '规则',
+};
+
+const RULE_TEMPLATE = `{
+  "name": "连接耳机时切换并调低音量",
+  "trigger": { "type": "device", "device": "Headset", "event": "connected" },
+  "conditions": [{ "type": "time_of_day", "start": "09:00", "end": "18:00" }],
+  "actions": [
+    { "type": "switch_device", "device": "Headset" },
+    { "type": "set_volume", "level": 40 },
+    { "type": "notify", "title": "已切换到耳机" }
+  ]
+}`;
+
+interface FocusSwitchConfig {
+  enabled: boolean;
+  mappings: { process: string; device: string }[];
+  fallback_device: string | null;
+  settle_ms: number;
+}
+
+// 本地 HTTP 接口，token 为空时保存会重新生成
+interface HttpApiConfig {
+  enabled: boolean;
+  port: number;
+  token: string;
+}
+
+// MQTT 桥接，topic_prefix 为空时使用 audiocat/<主机名>
+interface MqttConfig {
+  enabled: boolean;
+  host: string;
+  port: number;
+  username: string;
+  password: string;
+  topic_prefix: string;
+  discovery: boolean;
+  discovery_prefix: string;
+}
+
+// OSC 服务器，address 是监听的 IP 地址，allowed_clients 是允许控制的其他设备的 IP（本机总是允许）
+interface OscConfig {
+  enabled: boolean;
+  address: string;
+  port: number;
+  allowed_clients: string[];
+}
+
+const describeAction = (action: MonitorAction) => {
+  switch (action.type) {
+    case 'show_switcher':
+      return '显示切换器';
+    case 'switch_to':
+      return `切换到 ${action.device}`;
+    case 'revert':
+      return '恢复之前的设备';
+  }
+};
+
+// get_running_windows 返回的运行窗口，同一应用中标题相同的窗口已合并
+interface RunningWindow {
+  title: string;
+  process_name: string;
+  process_path: string;
+  pids: number[];
+  class: string;
+  count: number;
+  icon: string | null; // PNG data URL
+}
+
+interface PickerOption {
+  value: string;
+  label: string;
+  detail: string;
+  icon: string | null;
+}
+
+// 从运行窗口生成选择框的选项：按标题、进程名或类名去重
+const pickerOptions = (windows: RunningWindow[], type: MatcherType): PickerOption[] => {
+  const options: PickerOption[] = [];
+  for (const window of windows) {
+    let option: PickerOption;
+    if (type === 'process') {
+      if (!window.process_name) continue;
+      option = { value: window.process_name, label: window.process_name, detail: window.process_path, icon: window.icon };
+    } else if (type === 'class') {
+      if (!window.class) continue;
+      option = { value: window.class, label: window.class, detail: window.process_name, icon: window.icon };
+    } else {
+      const count = window.count > 1 ? ` ×${window.count}` : '';
+      option = { value: window.title, label: window.title, detail: `${window.process_name || '未知应用'}${count}`, icon: window.icon };
+    }
+    if (!options.some(existing => existing.value === option.value)) {
+      options.push(option);
+    }
+  }
+  return options;
+};
+
+const PICKER_MATCHERS: MatcherType[] = ['exact_title', 'process', 'class'];
+
+const MATCHER_LABELS: Record<MatcherType, string> = {
+  exact_title: '完整标题',
+  substring: '标题包含',
+  glob: '通配符',
+  regex: '正则表达式',
+  process: '进程名',
+  class: '窗口类名',
+};
+
 function PreferenceApp() {
 
   const [configuredAudioDevices, setConfiguredAudioDevices] = useState<string[]>([]);
   const [availableAudioDevices, setAvailableAudioDevices] = useState<AudioDevice[]>([]);
+  const [deviceLoadError, setDeviceLoadError] = useState<string | null>(null);
   const [selectedDeviceToAdd, setSelectedDeviceToAdd] = useState('');
 
   // 窗口监听相关状态
-  const [monitoredWindows, setMonitoredWindows] = useState<string[]>([]);
-  const [availableWindows, setAvailableWindows] = useState<string[]>([]);
+  const [monitoredWindows, setMonitoredWindows] = useState<MonitorRule[]>([]);
+  const [availableWindows, setAvailableWindows] = useState<RunningWindow[]>([]);
   const [selectedWindowToAdd, setSelectedWindowToAdd] = useState('');
+  const [selectedMatcherType, setSelectedMatcherType] = useState<MatcherType>('exact_title');
+  const [selectedEvent, setSelectedEvent] = useState<TriggerEvent>('closed');
+  const [selectedActionType, setSelectedActionType] = useState<MonitorAction['type']>('show_switcher');
+  const [selectedActionDevice, setSelectedActionDevice] = useState('');
+  const [focusSwitch, setFocusSwitch] = useState<FocusSwitchConfig>({
+    enabled: false, mappings: [], fallback_device: null, settle_ms: 800
+  });
+  const [focusProcessToAdd, setFocusProcessToAdd] = useState('');
+  const [focusDeviceToAdd, setFocusDeviceToAdd] = useState('');
+  const [processRules, setProcessRules] = useState<ProcessRule[]>([]);
+  const [processToAdd, setProcessToAdd] = useState('');
+  const [processEventToAdd, setProcessEventToAdd] = useState<ProcessTrigger>('started');
+  const [processActionType, setProcessActionType] = useState<MonitorAction['type']>('switch_to');
+  const [processActionDevice, setProcessActionDevice] = useState('');
+  const [scheduleRules, setScheduleRules] = useState<ScheduleRule[]>([]);
+  const [scheduleType, setScheduleType] = useState<ScheduleTrigger['type']>('weekly');
+  const [scheduleDays, setScheduleDays] = useState<Day[]>(['mon', 'tue', 'wed', 'thu', 'fri']);
+  const [scheduleStart, setScheduleStart] = useState('09:00');
+  const [scheduleEnd, setScheduleEnd] = useState('18:00');
+  const [scheduleCron, setScheduleCron] = useState('');
+  const [scheduleDevice, setScheduleDevice] = useState('');
+  const [scheduleEndDevice, setScheduleEndDevice] = useState('');
+  const [engineRules, setEngineRules] = useState<EngineRule[]>([]);
+  const [ruleDraft, setRuleDraft] = useState(RULE_TEMPLATE);
+  const [ruleMessage, setRuleMessage] = useState<{ severity: 'success' | 'error' | 'info'; text: string } | null>(null);
+  const [ruleLog, setRuleLog] = useState<RuleFiring[]>([]);
+  const [eventHistory, setEventHistory] = useState<HistoryEvent[]>([]);
+  const [historyKind, setHistoryKind] = useState<HistoryKind | 'all'>('all');
+  const [historyText, setHistoryText] = useState('');
+  const [logLevel, setLogLevel] = useState('info');
+  const [logTail, setLogTail] = useState<string[]>([]);
   const [autoHideDelay, setAutoHideDelay] = useState<number>(5);
   const [darkMode, setDarkMode] = useState(false);
+  const [httpApi, setHttpApi] = useState<HttpApiConfig>({ enabled: false, port: 17380, token: '' });
+  const [httpApiPort, setHttpApiPort] = useState('17380');
+  const [httpApiError, setHttpApiError] = useState<string | null>(null);
+  const [mqtt, setMqtt] = useState<MqttConfig>({
+    enabled: false,
+    host: 'localhost',
+    port: 1883,
+    username: '',
+    password: '',
+    topic_prefix: '',
+    discovery: true,
+    discovery_prefix: 'homeassistant'
+  });
+  const [mqttStatus, setMqttStatus] = useState<{ severity: 'success' | 'error'; message: string } | null>(null);
+  const [osc, setOsc] = useState<OscConfig>({ enabled: false, address: '127.0.0.1', port: 9000, allowed_clients: [] });
+  // 允许的客户端按逗号分隔编辑，保存时再拆分，输入过程中不会吞掉结尾的逗号
+  const [oscClients, setOscClients] = useState('');
+  const [oscStatus, setOscStatus] = useState<{ severity: 'success' | 'error'; message: string } | null>(null);
   const [activeTab, setActiveTab] = useState('general');
 
   useEffect(() => {
@@ -53,6 +316,15 @@ function PreferenceApp() {
     loadAvailableWindows();
     loadAutoHideDelay();
     loadDarkMode();
+    loadHttpApi();
+    loadMqtt();
+    loadOsc();
+    loadFocusSwitch();
+    loadProcessRules();
+    loadScheduleRules();
+    loadEngineRules();
+    loadEventHistory();
+    loadLogs();
 
     // 监听深色模式变化
     const setupDarkModeListener = async () => {
@@ -99,16 +371,18 @@ function PreferenceApp() {
     try {
       const devices = await invoke('get_audio_output_devices');
       setAvailableAudioDevices(devices as AudioDevice[]);
+      setDeviceLoadError(null);
       console.log('Available devices:', devices);
     } catch (error) {
       console.error('Failed to get available audio devices:', error);
+      setDeviceLoadError(describeError(error));
     }
   };
 
   const loadMonitoredWindows = async () => {
     try {
       const windows = await invoke('get_monitored_windows');
-      setMonitoredWindows(windows as string[]);
+      setMonitoredWindows(windows as MonitorRule[]);
       console.log('Monitored windows:', windows);
     } catch (error) {
       console.error('Failed to get monitored windows:', error);
@@ -118,7 +392,7 @@ function PreferenceApp() {
   const loadAvailableWindows = async () => {
     try {
       const windows = await invoke('get_running_windows');
-      setAvailableWindows(windows as string[]);
+      setAvailableWindows(windows as RunningWindow[]);
       console.log('Available windows:', windows);
     } catch (error) {
       console.error('Failed to get available windows:', error);
@@ -145,6 +419,251 @@ function PreferenceApp() {
     }
   };
 
+  const loadHttpApi = async () => {
+    try {
+      const config = await invoke<HttpApiConfig>('get_http_api_config');
+      setHttpApi(config);
+      setHttpApiPort(String(config.port));
+    } catch (error) {
+      console.error('Failed to load HTTP API config:', error);
+    }
+  };
+
+  const saveHttpApi = async (config: HttpApiConfig) => {
+    try {
+      const saved = await invoke<HttpApiConfig>('set_http_api_config', { config });
+      setHttpApi(saved);
+      setHttpApiPort(String(saved.port));
+      setHttpApiError(null);
+    } catch (error) {
+      setHttpApiPort(String(httpApi.port));
+      setHttpApiError(describeError(error));
+    }
+  };
+
+  const handleHttpApiPortChange = () => {
+    const port = parseInt(httpApiPort);
+    if (isNaN(port) || port < 1 || port > 65535) {
+      setHttpApiPort(String(httpApi.port));
+    } else if (port !== httpApi.port) {
+      saveHttpApi({ ...httpApi, port });
+    }
+  };
+
+  const loadMqtt = async () => {
+    try {
+      setMqtt(await invoke<MqttConfig>('get_mqtt_config'));
+    } catch (error) {
+      console.error('Failed to load MQTT config:', error);
+    }
+  };
+
+  const saveMqtt = async () => {
+    try {
+      await invoke('set_mqtt_config', { config: mqtt });
+      setMqttStatus({ severity: 'success', message: mqtt.enabled ? '已保存，正在连接' : '已保存' });
+    } catch (error) {
+      setMqttStatus({ severity: 'error', message: describeError(error) });
+    }
+  };
+
+  const loadOsc = async () => {
+    try {
+      const config = await invoke<OscConfig>('get_osc_config');
+      setOsc(config);
+      setOscClients(config.allowed_clients.join(', '));
+    } catch (error) {
+      console.error('Failed to load OSC config:', error);
+    }
+  };
+
+  const saveOsc = async () => {
+    try {
+      const allowed_clients = oscClients.split(/[,\s]+/).filter(client => client);
+      await invoke('set_osc_config', { config: { ...osc, allowed_clients } });
+      setOsc({ ...osc, allowed_clients });
+      setOscStatus({ severity: 'success', message: '已保存' });
+    } catch (error) {
+      setOscStatus({ severity: 'error', message: describeError(error) });
+    }
+  };
+
+  const loadFocusSwitch = async () => {
+    try {
+      const config = await invoke<FocusSwitchConfig>('get_focus_switch_config');
+      setFocusSwitch(config);
+    } catch (error) {
+      console.error('Failed to load focus switch config:', error);
+    }
+  };
+
+  const saveFocusSwitch = async (config: FocusSwitchConfig) => {
+    try {
+      await invoke('set_focus_switch_config', { config });
+      setFocusSwitch(config);
+    } catch (error) {
+      console.error('Failed to save focus switch config:', error);
+    }
+  };
+
+  const handleAddFocusMapping = () => {
+    const process = focusProcessToAdd.trim();
+    if (process && focusDeviceToAdd) {
+      const mappings = [
+        ...focusSwitch.mappings.filter(m => m.process.toLowerCase() !== process.toLowerCase()),
+        { process, device: focusDeviceToAdd }
+      ];
+      saveFocusSwitch({ ...focusSwitch, mappings });
+      setFocusProcessToAdd('');
+      setFocusDeviceToAdd('');
+    }
+  };
+
+  const loadProcessRules = async () => {
+    try {
+      const rules = await invoke<ProcessRule[]>('get_process_rules');
+      setProcessRules(rules);
+    } catch (error) {
+      console.error('Failed to load process rules:', error);
+    }
+  };
+
+  const saveProcessRules = async (rules: ProcessRule[]) => {
+    try {
+      await invoke('set_process_rules', { rules });
+      setProcessRules(rules);
+    } catch (error) {
+      console.error('Failed to save process rules:', error);
+    }
+  };
+
+  const handleAddProcessRule = () => {
+    const process = processToAdd.trim();
+    const action: MonitorAction = processActionType === 'switch_to'
+      ? { type: 'switch_to', device: processActionDevice }
+      : { type: processActionType };
+    if (!process || (action.type === 'switch_to' && !action.device)) {
+      return;
+    }
+    saveProcessRules([...processRules, { process, event: processEventToAdd, action }]);
+    setProcessToAdd('');
+  };
+
+  const loadScheduleRules = async () => {
+    try {
+      const rules = await invoke<ScheduleRule[]>('get_schedule_rules');
+      setScheduleRules(rules);
+    } catch (error) {
+      console.error('Failed to load schedule rules:', error);
+    }
+  };
+
+  const saveScheduleRules = async (rules: ScheduleRule[]) => {
+    try {
+      await invoke('set_schedule_rules', { rules });
+      setScheduleRules(rules);
+    } catch (error) {
+      console.error('Failed to save schedule rules:', error);
+    }
+  };
+
+  const handleAddScheduleRule = () => {
+    const trigger: ScheduleTrigger = scheduleType === 'cron'
+      ? { type: 'cron', expression: scheduleCron.trim() }
+      : { type: 'weekly', days: scheduleDays, start: scheduleStart, end: scheduleEnd };
+    // 没有选设备时显示切换器
+    const action: MonitorAction = scheduleDevice
+      ? { type: 'switch_to', device: scheduleDevice }
+      : { type: 'show_switcher' };
+    const end_action: MonitorAction | null = scheduleType === 'weekly' && scheduleEndDevice
+      ? { type: 'switch_to', device: scheduleEndDevice }
+      : null;
+    saveScheduleRules([...scheduleRules, { name: '', trigger, action, end_action, missed: 'catch_up' }]);
+    setScheduleCron('');
+  };
+
+  const toggleScheduleDay = (day: Day) => {
+    setScheduleDays(scheduleDays.includes(day)
+      ? scheduleDays.filter(d => d !== day)
+      : (Object.keys(DAY_LABELS) as Day[]).filter(d => d === day || scheduleDays.includes(d)));
+  };
+
+  const loadEngineRules = async () => {
+    try {
+      setEngineRules(await invoke<EngineRule[]>('get_rules'));
+      setRuleLog(await invoke<RuleFiring[]>('get_rule_log'));
+    } catch (error) {
+      console.error('Failed to load rules:', error);
+    }
+  };
+
+  // 事件历史：最近 200 条，按类型和文字过滤
+  const loadEventHistory = async (kind = historyKind, text = historyText) => {
+    try {
+      const filter = {
+        kinds: kind === 'all' ? [] : [kind],
+        text: text.trim() || null,
+        limit: 200,
+      };
+      setEventHistory(await invoke<HistoryEvent[]>('get_event_history', { filter }));
+    } catch (error) {
+      console.error('Failed to load event history:', error);
+    }
+  };
+
+  const clearEventHistory = async () => {
+    try {
+      await invoke('clear_event_history');
+      setEventHistory([]);
+    } catch (error) {
+      console.error('Failed to clear event history:', error);
+    }
+  };
+
+  const saveEngineRules = async (rules: EngineRule[]) => {
+    try {
+      await invoke('set_rules', { rules });
+      setEngineRules(rules);
+      return true;
+    } catch (error) {
+      console.error('Failed to save rules:', error);
+      setRuleMessage({ severity: 'error', text: describeError(error) });
+      return false;
+    }
+  };
+
+  const parseRuleDraft = (): EngineRule | null => {
+    try {
+      return { id: `rule-${Date.now()}`, name: '', enabled: true, conditions: [], ...JSON.parse(ruleDraft) };
+    } catch (error) {
+      setRuleMessage({ severity: 'error', text: `JSON 格式错误: ${error}` });
+      return null;
+    }
+  };
+
+  // 试运行：只检查条件并列出会执行的动作
+  const handleTestRule = async (rule: EngineRule | null) => {
+    if (!rule) {
+      return;
+    }
+    try {
+      const firing = await invoke<RuleFiring>('test_rule', { rule });
+      setRuleMessage(firing.skipped_by
+        ? { severity: 'info', text: `条件不满足，不会执行: ${firing.skipped_by}` }
+        : { severity: 'success', text: `将依次执行: ${firing.actions.map(a => a.action).join(' → ')}` });
+      setRuleLog(await invoke<RuleFiring[]>('get_rule_log'));
+    } catch (error) {
+      setRuleMessage({ severity: 'error', text: describeError(error) });
+    }
+  };
+
+  const handleAddRule = async () => {
+    const rule = parseRuleDraft();
+    if (rule && await saveEngineRules([...engineRules, rule])) {
+      setRuleMessage({ severity: 'success', text: '规则已添加' });
+    }
+  };
+
   const saveConfiguredAudioDevices = async (devices: string[]) => {
     try {
       const appDataDirPath = await appDataDir();
@@ -184,8 +703,20 @@ function PreferenceApp() {
 
 
   const handleAddWindow = async () => {
-    if (selectedWindowToAdd && monitoredWindows.length < 10 && !monitoredWindows.includes(selectedWindowToAdd)) {
-      const updatedWindows = [...monitoredWindows, selectedWindowToAdd];
+    const action: MonitorAction = selectedActionType === 'switch_to'
+      ? { type: 'switch_to', device: selectedActionDevice }
+      : { type: selectedActionType };
+    const newRule: MonitorRule = {
+      matcher: { type: selectedMatcherType, value: selectedWindowToAdd.trim() },
+      event: selectedEvent,
+      action
+    };
+    const isDuplicate = monitoredWindows.some(rule =>
+      JSON.stringify(rule) === JSON.stringify(newRule)
+    );
+    const actionReady = action.type !== 'switch_to' || action.device !== '';
+    if (newRule.matcher.value && actionReady && monitoredWindows.length < 10 && !isDuplicate) {
+      const updatedWindows = [...monitoredWindows, newRule];
       try {
         await invoke('set_monitored_windows', { windows: updatedWindows });
         setMonitoredWindows(updatedWindows);
@@ -220,15 +751,34 @@ function PreferenceApp() {
 
   const testCountdown = async () => {
     try {
-      // 模拟窗口关闭事件，触发倒计时
-      await invoke('set_window_pinned', { pinned: true });
-      await emit('test-countdown', { delay: autoHideDelay });
+      // 先保存当前延迟，再由后端像规则触发一样显示切换器并开始倒计时
+      await invoke('set_auto_hide_delay', { delay: autoHideDelay });
+      await invoke('test_auto_hide');
       console.log('Test countdown triggered');
     } catch (error) {
       console.error('Failed to test countdown:', error);
     }
   };
 
+  const loadLogs = async () => {
+    try {
+      setLogLevel(await invoke<string>('get_log_level'));
+      setLogTail(await invoke<string[]>('get_log_tail', { lines: 200 }));
+    } catch (error) {
+      console.error('Failed to load log:', error);
+    }
+  };
+
+  const handleLogLevelChange = async (level: string) => {
+    try {
+      await invoke('set_log_level', { level });
+      setLogLevel(level);
+      await loadLogs();
+    } catch (error) {
+      console.error('Failed to set log level:', error);
+    }
+  };
+
   const openConfigFolder = async () => {
     try {
       const result = await invoke<string>('open_config_folder');
@@ -254,6 +804,7 @@ function PreferenceApp() {
     { id: 'general', label: '通用设置', icon: <SettingsIcon /> },
     { id: 'audio', label: '音效设置', icon: <VolumeUpIcon /> },
     { id: 'window', label: '窗口监听', icon: <WindowIcon /> },
+    { id: 'rules', label: '规则', icon: <RuleIcon /> },
     { id: 'about', label: '关于', icon: <InfoIcon /> }
   ];
 
@@ -266,6 +817,8 @@ function PreferenceApp() {
         return renderAudioSettings();
       case 'window':
         return renderWindowSettings();
+      case 'rules':
+        return renderRuleSettings();
       case 'about':
         return renderAboutSettings();
       default:
@@ -298,6 +851,221 @@ function PreferenceApp() {
           </Box>
         </CardContent>
       </Card>
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Typography variant="subtitle1" sx={{ mb: 2, fontWeight: 500 }}>
+            本地 HTTP 接口
+          </Typography>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            在 127.0.0.1 上提供 REST 接口，供家庭自动化和宏工具调用。请求需要带 Authorization: Bearer &lt;token&gt;，接口文档见 /openapi.json
+          </Typography>
+          <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', mb: 2 }}>
+            <Typography variant="body2">
+              启用
+            </Typography>
+            <Switch
+              checked={httpApi.enabled}
+              onChange={(e) => saveHttpApi({ ...httpApi, enabled: e.target.checked })}
+            />
+          </Box>
+          <Stack spacing={2}>
+            <TextField
+              label="端口"
+              type="number"
+              value={httpApiPort}
+              onChange={(e) => setHttpApiPort(e.target.value)}
+              onBlur={handleHttpApiPortChange}
+              onKeyDown={(e) => {
+                if (e.key === 'Enter') {
+                  handleHttpApiPortChange();
+                }
+              }}
+              slotProps={{
+                htmlInput: {
+                  min: 1,
+                  max: 65535,
+                  step: 1
+                }
+              }}
+              size="small"
+              sx={{ width: 140 }}
+            />
+            <Box sx={{ display: 'flex', gap: 1 }}>
+              <TextField
+                label="Token"
+                value={httpApi.token}
+                slotProps={{ htmlInput: { readOnly: true } }}
+                size="small"
+                fullWidth
+              />
+              <Button
+                variant="outlined"
+                onClick={() => saveHttpApi({ ...httpApi, token: '' })}
+                disabled={!httpApi.enabled}
+              >
+                重新生成
+              </Button>
+            </Box>
+          </Stack>
+          {httpApiError && (
+            <Alert severity="error" sx={{ mt: 2 }}>
+              {httpApiError}
+            </Alert>
+          )}
+        </CardContent>
+      </Card>
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Typography variant="subtitle1" sx={{ mb: 2, fontWeight: 500 }}>
+            MQTT
+          </Typography>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            连接 MQTT broker，发布当前设备、设备列表和音量，接收切换设备和显示切换器的命令，可供 Home Assistant 自动发现
+          </Typography>
+          <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', mb: 2 }}>
+            <Typography variant="body2">
+              启用
+            </Typography>
+            <Switch
+              checked={mqtt.enabled}
+              onChange={(e) => setMqtt({ ...mqtt, enabled: e.target.checked })}
+            />
+          </Box>
+          <Stack spacing={2}>
+            <Box sx={{ display: 'flex', gap: 1 }}>
+              <TextField
+                label="服务器"
+                value={mqtt.host}
+                onChange={(e) => setMqtt({ ...mqtt, host: e.target.value })}
+                size="small"
+                fullWidth
+              />
+              <TextField
+                label="端口"
+                type="number"
+                value={mqtt.port}
+                onChange={(e) => setMqtt({ ...mqtt, port: parseInt(e.target.value) || 0 })}
+                slotProps={{
+                  htmlInput: {
+                    min: 1,
+                    max: 65535,
+                    step: 1
+                  }
+                }}
+                size="small"
+                sx={{ width: 140 }}
+              />
+            </Box>
+            <Box sx={{ display: 'flex', gap: 1 }}>
+              <TextField
+                label="用户名"
+                value={mqtt.username}
+                onChange={(e) => setMqtt({ ...mqtt, username: e.target.value })}
+                size="small"
+                fullWidth
+              />
+              <TextField
+                label="密码"
+                type="password"
+                value={mqtt.password}
+                onChange={(e) => setMqtt({ ...mqtt, password: e.target.value })}
+                size="small"
+                fullWidth
+              />
+            </Box>
+            <TextField
+              label="主题前缀"
+              placeholder="audiocat/<主机名>"
+              value={mqtt.topic_prefix}
+              onChange={(e) => setMqtt({ ...mqtt, topic_prefix: e.target.value })}
+              size="small"
+            />
+            <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between' }}>
+              <Typography variant="body2">
+                Home Assistant 自动发现
+              </Typography>
+              <Switch
+                checked={mqtt.discovery}
+                onChange={(e) => setMqtt({ ...mqtt, discovery: e.target.checked })}
+              />
+            </Box>
+            <Box>
+              <Button variant="contained" onClick={saveMqtt}>
+                保存
+              </Button>
+            </Box>
+          </Stack>
+          {mqttStatus && (
+            <Alert severity={mqttStatus.severity} sx={{ mt: 2 }}>
+              {mqttStatus.message}
+            </Alert>
+          )}
+        </CardContent>
+      </Card>
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Typography variant="subtitle1" sx={{ mb: 2, fontWeight: 500 }}>
+            OSC
+          </Typography>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            接收 TouchOSC、QLab 等控制器的 UDP 消息：/audiocat/device/set、/audiocat/device/index、/audiocat/show。发送 /audiocat/register 的客户端会在设备变化时收到反馈
+          </Typography>
+          <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', mb: 2 }}>
+            <Typography variant="body2">
+              启用
+            </Typography>
+            <Switch
+              checked={osc.enabled}
+              onChange={(e) => setOsc({ ...osc, enabled: e.target.checked })}
+            />
+          </Box>
+          <Stack spacing={2}>
+            <Box sx={{ display: 'flex', gap: 1 }}>
+              <TextField
+                label="监听地址"
+                value={osc.address}
+                onChange={(e) => setOsc({ ...osc, address: e.target.value })}
+                size="small"
+                fullWidth
+              />
+              <TextField
+                label="端口"
+                type="number"
+                value={osc.port}
+                onChange={(e) => setOsc({ ...osc, port: parseInt(e.target.value) || 0 })}
+                slotProps={{
+                  htmlInput: {
+                    min: 1,
+                    max: 65535,
+                    step: 1
+                  }
+                }}
+                size="small"
+                sx={{ width: 140 }}
+              />
+            </Box>
+            <TextField
+              label="允许的控制器 IP"
+              value={oscClients}
+              onChange={(e) => setOscClients(e.target.value)}
+              placeholder="192.168.1.20, 192.168.1.21"
+              helperText="监听地址为 0.0.0.0 等其他网卡时，只接受这些地址和本机发来的消息，用逗号分隔"
+              size="small"
+              fullWidth
+            />
+            <Box>
+              <Button variant="contained" onClick={saveOsc}>
+                保存
+              </Button>
+            </Box>
+          </Stack>
+          {oscStatus && (
+            <Alert severity={oscStatus.severity} sx={{ mt: 2 }}>
+              {oscStatus.message}
+            </Alert>
+          )}
+        </CardContent>
+      </Card>
     </Box>
   );
 
@@ -354,6 +1122,12 @@ function PreferenceApp() {
             </Stack>
           )}
 
+          {deviceLoadError && (
+            <Alert severity="error" sx={{ mb: 2 }}>
+              无法获取音频设备列表：{deviceLoadError}
+            </Alert>
+          )}
+
           {configuredAudioDevices.length < 4 && (
             <Box sx={{ display: 'flex', gap: 1 }}>
               <TextField
@@ -482,7 +1256,7 @@ function PreferenceApp() {
             </Alert>
           ) : (
             <Stack spacing={1} sx={{ mb: 2 }}>
-              {monitoredWindows.map((windowTitle, index) => (
+              {monitoredWindows.map((rule, index) => (
                 <Box
                   key={index}
                   sx={{
@@ -499,7 +1273,8 @@ function PreferenceApp() {
                   <Box sx={{ display: 'flex', alignItems: 'center', flex: 1 }}>
                     <MonitorIcon sx={{ mr: 1, fontSize: '1.2rem', color: 'text.secondary' }} />
                     <Typography variant="body2">
-                      {windowTitle}
+                      {MATCHER_LABELS[rule.matcher.type]}: {rule.matcher.value}
+                      {' — '}{EVENT_LABELS[rule.event]}{describeAction(rule.action)}
                     </Typography>
                   </Box>
                   <IconButton
@@ -519,20 +1294,104 @@ function PreferenceApp() {
               <TextField
                 select
                 size="small"
-                value={selectedWindowToAdd}
-                onChange={(e) => setSelectedWindowToAdd(e.target.value)}
-                placeholder="选择要监听的窗口"
-                sx={{ flex: 1 }}
-                disabled={availableWindows.length === 0}
+                value={selectedMatcherType}
+                onChange={(e) => {
+                  setSelectedMatcherType(e.target.value as MatcherType);
+                  setSelectedWindowToAdd('');
+                }}
+                sx={{ width: 130 }}
+              >
+                {(Object.keys(MATCHER_LABELS) as MatcherType[]).map((type) => (
+                  <MenuItem key={type} value={type}>
+                    {MATCHER_LABELS[type]}
+                  </MenuItem>
+                ))}
+              </TextField>
+              {PICKER_MATCHERS.includes(selectedMatcherType) ? (
+                <TextField
+                  select
+                  size="small"
+                  value={selectedWindowToAdd}
+                  onChange={(e) => setSelectedWindowToAdd(e.target.value)}
+                  placeholder="选择要监听的窗口"
+                  sx={{ flex: 1 }}
+                  disabled={availableWindows.length === 0}
+                >
+                  {pickerOptions(availableWindows, selectedMatcherType)
+                    .filter(option => !monitoredWindows.some(rule =>
+                      rule.matcher.type === selectedMatcherType && rule.matcher.value === option.value
+                    ))
+                    .map((option) => (
+                      <MenuItem key={option.value} value={option.value}>
+                        <Box sx={{ display: 'flex', alignItems: 'center', gap: 1, minWidth: 0 }}>
+                          {option.icon ? (
+                            <img src={option.icon} width={16} height={16} alt="" />
+                          ) : (
+                            <WindowIcon sx={{ fontSize: 16 }} color="disabled" />
+                          )}
+                          <Box sx={{ minWidth: 0 }}>
+                            <Typography variant="body2" noWrap>{option.label}</Typography>
+                            <Typography variant="caption" color="text.secondary" noWrap>
+                              {option.detail}
+                            </Typography>
+                          </Box>
+                        </Box>
+                      </MenuItem>
+                    ))}
+                </TextField>
+              ) : (
+                <TextField
+                  size="small"
+                  value={selectedWindowToAdd}
+                  onChange={(e) => setSelectedWindowToAdd(e.target.value)}
+                  placeholder={selectedMatcherType === 'process' ? '例如 obs64.exe' : '输入匹配内容'}
+                  sx={{ flex: 1 }}
+                />
+              )}
+            </Box>
+          )}
+
+          {monitoredWindows.length < 10 && (
+            <Box sx={{ display: 'flex', gap: 1, mt: 1 }}>
+              <TextField
+                select
+                size="small"
+                value={selectedEvent}
+                onChange={(e) => setSelectedEvent(e.target.value as TriggerEvent)}
+                sx={{ width: 130 }}
               >
-                {availableWindows
-                  .filter(window => !monitoredWindows.includes(window))
-                  .map((window) => (
-                    <MenuItem key={window} value={window}>
-                      {window}
+                {(Object.keys(EVENT_LABELS) as TriggerEvent[]).map((event) => (
+                  <MenuItem key={event} value={event}>
+                    {EVENT_LABELS[event]}
+                  </MenuItem>
+                ))}
+              </TextField>
+              <TextField
+                select
+                size="small"
+                value={selectedActionType}
+                onChange={(e) => setSelectedActionType(e.target.value as MonitorAction['type'])}
+                sx={{ width: 160 }}
+              >
+                <MenuItem value="show_switcher">显示切换器</MenuItem>
+                <MenuItem value="switch_to">切换到设备</MenuItem>
+                <MenuItem value="revert">恢复之前的设备</MenuItem>
+              </TextField>
+              {selectedActionType === 'switch_to' && (
+                <TextField
+                  select
+                  size="small"
+                  value={selectedActionDevice}
+                  onChange={(e) => setSelectedActionDevice(e.target.value)}
+                  sx={{ flex: 1 }}
+                >
+                  {availableAudioDevices.map((device) => (
+                    <MenuItem key={device.id} value={device.name}>
+                      {device.name}
                     </MenuItem>
                   ))}
-              </TextField>
+                </TextField>
+              )}
               <Button
                 variant="contained"
                 startIcon={<AddIcon />}
@@ -551,6 +1410,531 @@ function PreferenceApp() {
           )}
         </CardContent>
       </Card>
+
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', mb: 1 }}>
+            <Typography variant="subtitle1" sx={{ fontWeight: 500 }}>
+              焦点跟随
+            </Typography>
+            <Switch
+              checked={focusSwitch.enabled}
+              onChange={(e) => saveFocusSwitch({ ...focusSwitch, enabled: e.target.checked })}
+            />
+          </Box>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            切换到指定应用时自动使用对应的输出设备，其他应用使用默认设备
+          </Typography>
+
+          <Stack spacing={1} sx={{ mb: 2 }}>
+            {focusSwitch.mappings.map((mapping, index) => (
+              <Box
+                key={mapping.process}
+                sx={{
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'space-between',
+                  p: 1.5,
+                  border: '1px solid',
+                  borderColor: 'divider',
+                  borderRadius: 1
+                }}
+              >
+                <Typography variant="body2">
+                  {mapping.process} → {mapping.device}
+                </Typography>
+                <IconButton
+                  size="small"
+                  color="error"
+                  onClick={() => saveFocusSwitch({
+                    ...focusSwitch,
+                    mappings: focusSwitch.mappings.filter((_, i) => i !== index)
+                  })}
+                >
+                  <DeleteIcon fontSize="small" />
+                </IconButton>
+              </Box>
+            ))}
+          </Stack>
+
+          <Box sx={{ display: 'flex', gap: 1, mb: 2 }}>
+            <TextField
+              size="small"
+              value={focusProcessToAdd}
+              onChange={(e) => setFocusProcessToAdd(e.target.value)}
+              placeholder="进程名，例如 Ableton Live 11 Suite.exe"
+              sx={{ flex: 1 }}
+            />
+            <TextField
+              select
+              size="small"
+              value={focusDeviceToAdd}
+              onChange={(e) => setFocusDeviceToAdd(e.target.value)}
+              sx={{ width: 200 }}
+            >
+              {availableAudioDevices.map((device) => (
+                <MenuItem key={device.id} value={device.name}>
+                  {device.name}
+                </MenuItem>
+              ))}
+            </TextField>
+            <Button
+              variant="contained"
+              startIcon={<AddIcon />}
+              onClick={handleAddFocusMapping}
+              disabled={!focusProcessToAdd.trim() || !focusDeviceToAdd}
+              size="small"
+              sx={{ borderRadius: 1.5, textTransform: 'none' }}
+            >
+              添加
+            </Button>
+          </Box>
+
+          <Box sx={{ display: 'flex', gap: 2, alignItems: 'center' }}>
+            <TextField
+              select
+              size="small"
+              label="其他应用使用"
+              value={focusSwitch.fallback_device ?? ''}
+              onChange={(e) => saveFocusSwitch({
+                ...focusSwitch,
+                fallback_device: e.target.value === '' ? null : e.target.value
+              })}
+              sx={{ flex: 1 }}
+            >
+              <MenuItem value="">保持不变</MenuItem>
+              {availableAudioDevices.map((device) => (
+                <MenuItem key={device.id} value={device.name}>
+                  {device.name}
+                </MenuItem>
+              ))}
+            </TextField>
+            <TextField
+              type="number"
+              size="small"
+              label="稳定时间 (毫秒)"
+              value={focusSwitch.settle_ms}
+              onChange={(e) => {
+                const settle = Math.max(0, parseInt(e.target.value) || 0);
+                saveFocusSwitch({ ...focusSwitch, settle_ms: settle });
+              }}
+              sx={{ width: 150 }}
+            />
+          </Box>
+        </CardContent>
+      </Card>
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Typography variant="subtitle1" sx={{ mb: 1, fontWeight: 500 }}>
+            进程监听
+          </Typography>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            指定程序启动或退出时执行动作，适用于没有可见窗口的程序
+          </Typography>
+
+          <Stack spacing={1} sx={{ mb: 2 }}>
+            {processRules.map((rule, index) => (
+              <Box
+                key={`${rule.process}-${index}`}
+                sx={{
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'space-between',
+                  p: 1.5,
+                  border: '1px solid',
+                  borderColor: 'divider',
+                  borderRadius: 1
+                }}
+              >
+                <Typography variant="body2">
+                  {rule.process} {PROCESS_EVENT_LABELS[rule.event]} → {describeAction(rule.action)}
+                </Typography>
+                <IconButton
+                  size="small"
+                  color="error"
+                  onClick={() => saveProcessRules(processRules.filter((_, i) => i !== index))}
+                >
+                  <DeleteIcon fontSize="small" />
+                </IconButton>
+              </Box>
+            ))}
+          </Stack>
+
+          <Box sx={{ display: 'flex', gap: 1, flexWrap: 'wrap' }}>
+            <TextField
+              size="small"
+              value={processToAdd}
+              onChange={(e) => setProcessToAdd(e.target.value)}
+              placeholder="进程名，例如 obs64.exe"
+              sx={{ flex: 1, minWidth: 180 }}
+            />
+            <TextField
+              select
+              size="small"
+              value={processEventToAdd}
+              onChange={(e) => setProcessEventToAdd(e.target.value as ProcessTrigger)}
+              sx={{ width: 110 }}
+            >
+              {(Object.keys(PROCESS_EVENT_LABELS) as ProcessTrigger[]).map((event) => (
+                <MenuItem key={event} value={event}>
+                  {PROCESS_EVENT_LABELS[event]}
+                </MenuItem>
+              ))}
+            </TextField>
+            <TextField
+              select
+              size="small"
+              value={processActionType}
+              onChange={(e) => setProcessActionType(e.target.value as MonitorAction['type'])}
+              sx={{ width: 150 }}
+            >
+              <MenuItem value="show_switcher">显示切换器</MenuItem>
+              <MenuItem value="switch_to">切换设备</MenuItem>
+              <MenuItem value="revert">恢复之前的设备</MenuItem>
+            </TextField>
+            {processActionType === 'switch_to' && (
+              <TextField
+                select
+                size="small"
+                value={processActionDevice}
+                onChange={(e) => setProcessActionDevice(e.target.value)}
+                sx={{ width: 200 }}
+              >
+                {availableAudioDevices.map((device) => (
+                  <MenuItem key={device.id} value={device.name}>
+                    {device.name}
+                  </MenuItem>
+                ))}
+              </TextField>
+            )}
+            <Button
+              variant="contained"
+              startIcon={<AddIcon />}
+              onClick={handleAddProcessRule}
+              disabled={!processToAdd.trim() || (processActionType === 'switch_to' && !processActionDevice)}
+              size="small"
+              sx={{ borderRadius: 1.5, textTransform: 'none' }}
+            >
+              添加
+            </Button>
+          </Box>
+        </CardContent>
+      </Card>
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Typography variant="subtitle1" sx={{ mb: 1, fontWeight: 500 }}>
+            定时切换
+          </Typography>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            在指定时间段切换设备，结束时可以切换到另一个设备；电脑睡眠期间错过的时间点会在唤醒后补执行
+          </Typography>
+
+          <Stack spacing={1} sx={{ mb: 2 }}>
+            {scheduleRules.map((rule, index) => (
+              <Box
+                key={index}
+                sx={{
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'space-between',
+                  p: 1.5,
+                  border: '1px solid',
+                  borderColor: 'divider',
+                  borderRadius: 1
+                }}
+              >
+                <Typography variant="body2">
+                  {describeSchedule(rule.trigger)} → {describeAction(rule.action)}
+                  {rule.end_action && `，结束后${describeAction(rule.end_action)}`}
+                </Typography>
+                <IconButton
+                  size="small"
+                  color="error"
+                  onClick={() => saveScheduleRules(scheduleRules.filter((_, i) => i !== index))}
+                >
+                  <DeleteIcon fontSize="small" />
+                </IconButton>
+              </Box>
+            ))}
+          </Stack>
+
+          <Box sx={{ display: 'flex', gap: 1, flexWrap: 'wrap', alignItems: 'center', mb: 1 }}>
+            <TextField
+              select
+              size="small"
+              value={scheduleType}
+              onChange={(e) => setScheduleType(e.target.value as ScheduleTrigger['type'])}
+              sx={{ width: 120 }}
+            >
+              <MenuItem value="weekly">时间段</MenuItem>
+              <MenuItem value="cron">cron</MenuItem>
+            </TextField>
+            {scheduleType === 'weekly' ? (
+              <>
+                {(Object.keys(DAY_LABELS) as Day[]).map((day) => (
+                  <Button
+                    key={day}
+                    size="small"
+                    variant={scheduleDays.includes(day) ? 'contained' : 'outlined'}
+                    onClick={() => toggleScheduleDay(day)}
+                    sx={{ minWidth: 32, px: 0 }}
+                  >
+                    {DAY_LABELS[day]}
+                  </Button>
+                ))}
+                <TextField
+                  type="time"
+                  size="small"
+                  value={scheduleStart}
+                  onChange={(e) => setScheduleStart(e.target.value)}
+                  sx={{ width: 120 }}
+                />
+                <TextField
+                  type="time"
+                  size="small"
+                  value={scheduleEnd}
+                  onChange={(e) => setScheduleEnd(e.target.value)}
+                  sx={{ width: 120 }}
+                />
+              </>
+            ) : (
+              <TextField
+                size="small"
+                value={scheduleCron}
+                onChange={(e) => setScheduleCron(e.target.value)}
+                placeholder="分 时 日 月 星期，例如 0 9 * * 1-5"
+                sx={{ flex: 1, minWidth: 200 }}
+              />
+            )}
+          </Box>
+
+          <Box sx={{ display: 'flex', gap: 1 }}>
+            <TextField
+              select
+              size="small"
+              label="切换到"
+              value={scheduleDevice}
+              onChange={(e) => setScheduleDevice(e.target.value)}
+              sx={{ flex: 1 }}
+            >
+              <MenuItem value="">显示切换器</MenuItem>
+              {availableAudioDevices.map((device) => (
+                <MenuItem key={device.id} value={device.name}>
+                  {device.name}
+                </MenuItem>
+              ))}
+            </TextField>
+            {scheduleType === 'weekly' && (
+              <TextField
+                select
+                size="small"
+                label="结束后切换到"
+                value={scheduleEndDevice}
+                onChange={(e) => setScheduleEndDevice(e.target.value)}
+                sx={{ flex: 1 }}
+              >
+                <MenuItem value="">保持不变</MenuItem>
+                {availableAudioDevices.map((device) => (
+                  <MenuItem key={device.id} value={device.name}>
+                    {device.name}
+                  </MenuItem>
+                ))}
+              </TextField>
+            )}
+            <Button
+              variant="contained"
+              startIcon={<AddIcon />}
+              onClick={handleAddScheduleRule}
+              disabled={scheduleType === 'cron' ? !scheduleCron.trim() : scheduleStart === scheduleEnd}
+              size="small"
+              sx={{ borderRadius: 1.5, textTransform: 'none' }}
+            >
+              添加
+            </Button>
+          </Box>
+        </CardContent>
+      </Card>
+    </Box>
+  );
+
+  const renderRuleSettings = () => (
+    <Box>
+      <Typography variant="h6" sx={{ mb: 3, fontWeight: 600 }}>
+        规则
+      </Typography>
+
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Typography variant="subtitle1" sx={{ mb: 1, fontWeight: 500 }}>
+            自定义规则
+          </Typography>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            触发器：窗口、进程、设备连接/断开、定时、快捷键；条件：当前设备、时间段；动作按顺序执行
+          </Typography>
+
+          <Stack spacing={1} sx={{ mb: 2 }}>
+            {engineRules.map((rule, index) => (
+              <Box
+                key={rule.id || index}
+                sx={{
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'space-between',
+                  p: 1.5,
+                  border: '1px solid',
+                  borderColor: 'divider',
+                  borderRadius: 1
+                }}
+              >
+                <Typography variant="body2" sx={{ flex: 1 }}>
+                  {rule.name || rule.trigger.type} · {rule.actions.map(a => a.type).join(' → ')}
+                </Typography>
+                <Switch
+                  size="small"
+                  checked={rule.enabled}
+                  onChange={(e) => saveEngineRules(engineRules.map((r, i) =>
+                    i === index ? { ...r, enabled: e.target.checked } : r
+                  ))}
+                />
+                <IconButton size="small" onClick={() => handleTestRule(rule)}>
+                  <TestIcon fontSize="small" />
+                </IconButton>
+                <IconButton
+                  size="small"
+                  color="error"
+                  onClick={() => saveEngineRules(engineRules.filter((_, i) => i !== index))}
+                >
+                  <DeleteIcon fontSize="small" />
+                </IconButton>
+              </Box>
+            ))}
+          </Stack>
+
+          <TextField
+            multiline
+            minRows={8}
+            fullWidth
+            value={ruleDraft}
+            onChange={(e) => setRuleDraft(e.target.value)}
+            sx={{ mb: 1, '& textarea': { fontFamily: 'monospace', fontSize: '0.8rem' } }}
+          />
+          {ruleMessage && (
+            <Alert severity={ruleMessage.severity} sx={{ mb: 1 }} onClose={() => setRuleMessage(null)}>
+              {ruleMessage.text}
+            </Alert>
+          )}
+          <Box sx={{ display: 'flex', gap: 1 }}>
+            <Button
+              variant="outlined"
+              startIcon={<TestIcon />}
+              onClick={() => handleTestRule(parseRuleDraft())}
+              size="small"
+              sx={{ borderRadius: 1.5, textTransform: 'none' }}
+            >
+              试运行
+            </Button>
+            <Button
+              variant="contained"
+              startIcon={<AddIcon />}
+              onClick={handleAddRule}
+              size="small"
+              sx={{ borderRadius: 1.5, textTransform: 'none' }}
+            >
+              添加规则
+            </Button>
+          </Box>
+        </CardContent>
+      </Card>
+
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', mb: 1 }}>
+            <Typography variant="subtitle1" sx={{ fontWeight: 500 }}>
+              触发记录
+            </Typography>
+            <Button size="small" onClick={loadEngineRules} sx={{ textTransform: 'none' }}>
+              刷新
+            </Button>
+          </Box>
+          <Stack spacing={0.5}>
+            {ruleLog.length === 0 && (
+              <Typography variant="body2" color="text.secondary">暂无记录</Typography>
+            )}
+            {[...ruleLog].reverse().map((firing, index) => (
+              <Typography key={index} variant="body2" sx={{ fontSize: '0.8rem' }}>
+                {new Date(firing.timestamp * 1000).toLocaleString()} · {firing.rule_name}
+                {firing.dry_run && '（试运行）'} · {firing.trigger}
+                {firing.skipped_by
+                  ? ` · 跳过: ${firing.skipped_by}`
+                  : ` · ${firing.actions.map(a => a.error ? `${a.action} 失败: ${a.error}` : a.action).join(' → ')}`}
+              </Typography>
+            ))}
+          </Stack>
+        </CardContent>
+      </Card>
+
+      <Card sx={{ mb: 3 }}>
+        <CardContent>
+          <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', mb: 1 }}>
+            <Typography variant="subtitle1" sx={{ fontWeight: 500 }}>
+              事件历史
+            </Typography>
+            <Box>
+              <Button size="small" onClick={() => loadEventHistory()} sx={{ textTransform: 'none' }}>
+                刷新
+              </Button>
+              <Button size="small" color="error" onClick={clearEventHistory} sx={{ textTransform: 'none' }}>
+                清空
+              </Button>
+            </Box>
+          </Box>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            记录监听到的窗口、进程、设备变化和规则触发，用于排查规则为什么没有触发
+          </Typography>
+          <Box sx={{ display: 'flex', gap: 1, mb: 2 }}>
+            <TextField
+              select
+              size="small"
+              value={historyKind}
+              onChange={(e) => {
+                const kind = e.target.value as HistoryKind | 'all';
+                setHistoryKind(kind);
+                loadEventHistory(kind, historyText);
+              }}
+              sx={{ width: 120 }}
+            >
+              <MenuItem value="all">全部</MenuItem>
+              {(Object.keys(HISTORY_KIND_LABELS) as HistoryKind[]).map((kind) => (
+                <MenuItem key={kind} value={kind}>
+                  {HISTORY_KIND_LABELS[kind]}
+                </MenuItem>
+              ))}
+            </TextField>
+            <TextField
+              size="small"
+              value={historyText}
+              onChange={(e) => setHistoryText(e.target.value)}
+              onKeyDown={(e) => {
+                if (e.key === 'Enter') {
+                  loadEventHistory();
+                }
+              }}
+              placeholder="搜索，按回车过滤"
+              sx={{ flex: 1 }}
+            />
+          </Box>
+          <Stack spacing={0.5} sx={{ maxHeight: 300, overflowY: 'auto' }}>
+            {eventHistory.length === 0 && (
+              <Typography variant="body2" color="text.secondary">暂无记录</Typography>
+            )}
+            {[...eventHistory].reverse().map((event, index) => (
+              <Typography key={index} variant="body2" sx={{ fontSize: '0.8rem' }}>
+                {new Date(event.timestamp * 1000).toLocaleString()} · {HISTORY_KIND_LABELS[event.kind]} · {event.summary}
+              </Typography>
+            ))}
+          </Stack>
+        </CardContent>
+      </Card>
     </Box>
   );
 
@@ -649,6 +2033,54 @@ function PreferenceApp() {
           </Stack>
         </CardContent>
       </Card>
+
+      {/* 日志卡片 */}
+      <Card sx={{ mt: 3 }}>
+        <CardContent>
+          <Box sx={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', mb: 2 }}>
+            <Typography variant="subtitle1" sx={{ fontWeight: 500 }}>
+              日志
+            </Typography>
+            <Box sx={{ display: 'flex', alignItems: 'center', gap: 1 }}>
+              <TextField
+                select
+                size="small"
+                value={logLevel}
+                onChange={(e) => handleLogLevelChange(e.target.value)}
+                sx={{ width: 110 }}
+              >
+                {['error', 'warn', 'info', 'debug', 'trace'].map((level) => (
+                  <MenuItem key={level} value={level}>
+                    {level}
+                  </MenuItem>
+                ))}
+              </TextField>
+              <Button size="small" onClick={loadLogs} sx={{ textTransform: 'none' }}>
+                刷新
+              </Button>
+            </Box>
+          </Box>
+          <Typography variant="body2" color="text.secondary" sx={{ mb: 2 }}>
+            日志保存在配置文件夹的 logs 目录中，这里显示最近 200 行
+          </Typography>
+          <Box
+            component="pre"
+            sx={{
+              m: 0,
+              p: 1.5,
+              maxHeight: 300,
+              overflow: 'auto',
+              fontSize: '0.75rem',
+              borderRadius: 1.5,
+              bgcolor: darkMode ? 'rgba(255, 255, 255, 0.05)' : 'rgba(0, 0, 0, 0.04)',
+              whiteSpace: 'pre-wrap',
+              wordBreak: 'break-all',
+            }}
+          >
+            {logTail.length === 0 ? '暂无日志' : logTail.join('\n')}
+          </Box>
+        </CardContent>
+      </Card>
     </Box>
   );
 
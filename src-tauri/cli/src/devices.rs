@@ -0,0 +1,85 @@
+// 设备命令：通过核心库的音频工作线程列出和切换设备，和托盘应用走同一条代码路径。
+// 托盘应用正在运行时切换交给它，切换历史、通知和各个接口的事件推送才会更新。
+use crate::Reply;
+use crate::remote::Remote;
+use audiocat_core::audio::{AudioBackend, AudioClient, AudioDevice, SwitchOutcome, next_device};
+use audiocat_core::error::{AppError, AppResult, ErrorCode};
+use serde_json::{Value, json};
+
+pub fn list() -> AppResult<Reply> {
+    let devices = AudioClient::spawn().call_blocking(AudioBackend::devices)?;
+    let text = devices
+        .iter()
+        .map(|device| {
+            let marker = if device.is_default { "*" } else { " " };
+            format!("{} {}  ({})", marker, device.name, device.id)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(Reply::new(&devices, text))
+}
+
+pub fn current() -> AppResult<Reply> {
+    let device = AudioClient::spawn().call_blocking(AudioBackend::current_device)?;
+    let text = device.name.clone();
+    Ok(Reply::new(&device, text))
+}
+
+// target 可以是设备 id 或设备名称
+pub fn set(remote: Option<Remote>, target: &str) -> AppResult<Reply> {
+    if let Some(mut remote) = remote {
+        let devices = remote_devices(&mut remote)?;
+        return switch_remote(remote, &devices, target);
+    }
+
+    let audio = AudioClient::spawn();
+    let device = audio
+        .call_blocking(AudioBackend::devices)?
+        .into_iter()
+        .find(|device| device.matches(target))
+        .ok_or_else(|| AppError::device_not_found(target))?;
+    switch(&audio, device)
+}
+
+pub fn cycle(remote: Option<Remote>) -> AppResult<Reply> {
+    if let Some(mut remote) = remote {
+        let devices = remote_devices(&mut remote)?;
+        let device = next(&devices)?;
+        return switch_remote(remote, &devices, &device.id);
+    }
+
+    let audio = AudioClient::spawn();
+    let devices = audio.call_blocking(AudioBackend::devices)?;
+    let device = next(&devices)?.clone();
+    switch(&audio, device)
+}
+
+fn next(devices: &[AudioDevice]) -> AppResult<&AudioDevice> {
+    next_device(devices)
+        .ok_or_else(|| AppError::new(ErrorCode::DeviceNotFound, "No active audio output device"))
+}
+
+fn switch(audio: &AudioClient, device: AudioDevice) -> AppResult<Reply> {
+    let outcome = audio.call_blocking(move |backend| backend.set_default(&device.id))?;
+    Ok(reply(outcome))
+}
+
+fn remote_devices(remote: &mut Remote) -> AppResult<Vec<AudioDevice>> {
+    let devices = remote.call("devices.list", Value::Null)?;
+    serde_json::from_value(devices)
+        .map_err(|e| AppError::internal("Invalid device list from AudioCat").with_detail(e))
+}
+
+// 托盘应用只返回切换后的设备，切换前的设备从设备列表中取，输出和本地切换一致
+fn switch_remote(mut remote: Remote, devices: &[AudioDevice], target: &str) -> AppResult<Reply> {
+    let current = remote.call("devices.set", json!({ "device": target }))?;
+    let current = serde_json::from_value(current)
+        .map_err(|e| AppError::internal("Invalid device from AudioCat").with_detail(e))?;
+    let previous = devices.iter().find(|device| device.is_default).cloned();
+    Ok(reply(SwitchOutcome { previous, current }))
+}
+
+fn reply(outcome: SwitchOutcome) -> Reply {
+    let text = format!("成功切换到音频设备: {}", outcome.current.name);
+    Reply::new(&outcome, text)
+}
@@ -0,0 +1,147 @@
+// AudioCat 命令行工具：列出和切换输出设备，管理窗口监听规则和配置文件。
+// 和托盘应用使用同一个音频后端和同一个配置文件（audiocat-core），供脚本、快捷方式和 CI 调用。
+mod devices;
+mod remote;
+mod settings;
+
+use audiocat_core::config::{config_path, default_data_dir};
+use audiocat_core::error::{AppError, AppResult, ErrorCode};
+use clap::{Parser, Subcommand};
+use remote::Remote;
+use serde::Serialize;
+use settings::{ConfigCommand, MonitorCommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const EXIT_CODES: &str = "\
+退出码:
+  0  成功
+  1  内部错误
+  2  参数错误
+  3  设备不存在
+  4  设备已禁用或未插入
+  5  音频后端不可用
+  6  配置文件读写失败
+  7  没有权限
+  8  输入无效
+  9  监听规则等对象不存在";
+
+#[derive(Parser)]
+#[command(name = "audiocat-cli", version, about = "AudioCat 命令行工具", after_help = EXIT_CODES)]
+struct Cli {
+    /// 以 JSON 输出结果，错误以 JSON 输出到标准错误
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// 配置文件路径，默认使用托盘应用的配置文件
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 列出所有已启用的输出设备，* 标记默认设备
+    List,
+    /// 显示当前默认输出设备
+    Current,
+    /// 把指定设备设为默认输出设备
+    Set {
+        /// 设备 id 或设备名称
+        device: String,
+    },
+    /// 切换到列表中的下一个输出设备
+    Cycle,
+    /// 管理窗口监听规则
+    #[command(subcommand)]
+    Monitor(MonitorCommand),
+    /// 读取或修改配置项
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+// 命令的结果：--json 时输出 value，否则输出 text
+pub struct Reply {
+    value: serde_json::Value,
+    text: String,
+}
+
+impl Reply {
+    pub fn new(value: impl Serialize, text: impl Into<String>) -> Self {
+        Self {
+            value: serde_json::to_value(value).unwrap_or_default(),
+            text: text.into(),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(reply) => {
+            if cli.json {
+                println!("{}", reply.value);
+            } else if !reply.text.is_empty() {
+                println!("{}", reply.text);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            if cli.json {
+                eprintln!("{}", serde_json::to_value(&error).unwrap_or_default());
+            } else {
+                eprintln!("error: {}", error);
+            }
+            ExitCode::from(exit_code(error.code))
+        }
+    }
+}
+
+fn run(cli: &Cli) -> AppResult<Reply> {
+    match &cli.command {
+        Command::List => devices::list(),
+        Command::Current => devices::current(),
+        // 切换设备和配置文件无关，托盘应用运行时总是交给它
+        Command::Set { device } => devices::set(Remote::connect(), device),
+        Command::Cycle => devices::cycle(Remote::connect()),
+        Command::Monitor(command) => {
+            settings::monitor(&config_file(cli)?, running_app(cli), command)
+        }
+        Command::Config(command) => settings::config(&config_file(cli)?, running_app(cli), command),
+    }
+}
+
+// --config 指定的文件，否则是托盘应用数据目录下的配置文件
+fn config_file(cli: &Cli) -> AppResult<PathBuf> {
+    match &cli.config {
+        Some(path) => Ok(path.clone()),
+        None => default_data_dir()
+            .map(|dir| config_path(&dir))
+            .ok_or_else(|| AppError::config_io("Failed to find app data directory")),
+    }
+}
+
+// 使用托盘应用的配置文件时，连接正在运行的托盘应用；--config 指定的文件和它无关
+fn running_app(cli: &Cli) -> Option<Remote> {
+    match cli.config {
+        Some(_) => None,
+        None => Remote::connect(),
+    }
+}
+
+// 退出码和 EXIT_CODES 中的说明一致，2 由 clap 在参数错误时使用
+fn exit_code(code: ErrorCode) -> u8 {
+    match code {
+        ErrorCode::Internal => 1,
+        ErrorCode::DeviceNotFound => 3,
+        ErrorCode::DeviceInactive => 4,
+        ErrorCode::BackendUnavailable => 5,
+        ErrorCode::ConfigIo => 6,
+        ErrorCode::Permission => 7,
+        ErrorCode::InvalidInput => 8,
+        ErrorCode::NotFound => 9,
+    }
+}
@@ -0,0 +1,76 @@
+// 正在运行的托盘应用的本地控制接口（JSON-RPC 2.0，每行一个消息，见托盘应用的 rpc.rs）。
+// 修改配置的命令在托盘应用运行时交给它，由它保存并立即生效，不会被它之后保存的配置覆盖。
+use audiocat_core::error::{AppError, AppResult};
+use audiocat_core::local_socket;
+use interprocess::local_socket::{RecvHalf, SendHalf, prelude::*};
+use serde_json::{Value, json};
+use std::io::{self, BufRead, BufReader, Write};
+use std::time::Duration;
+
+// 和托盘应用 rpc.rs 中的 SOCKET_KIND 一致
+const SOCKET_KIND: &str = "rpc";
+// 修改 MQTT、OSC 等配置时托盘应用要重启对应的服务，留出足够的时间
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Remote {
+    reader: BufReader<RecvHalf>,
+    writer: SendHalf,
+    next_id: u64,
+}
+
+impl Remote {
+    // 托盘应用没有运行时返回 None
+    pub fn connect() -> Option<Self> {
+        let stream = local_socket::connect(SOCKET_KIND).ok()?;
+        stream.set_recv_timeout(Some(REPLY_TIMEOUT)).ok()?;
+        let (reader, writer) = stream.split();
+        Some(Self {
+            reader: BufReader::new(reader),
+            writer,
+            next_id: 1,
+        })
+    }
+
+    pub fn call(&mut self, method: &str, params: Value) -> AppResult<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        writeln!(self.writer, "{}", request).map_err(connection_error)?;
+
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line).map_err(connection_error)? == 0 {
+                return Err(connection_error(io::ErrorKind::UnexpectedEof.into()));
+            }
+            let reply: Value = serde_json::from_str(&line)
+                .map_err(|e| AppError::internal("Invalid reply from AudioCat").with_detail(e))?;
+            // 不是这次请求的回复（如事件通知）时继续读
+            if reply.get("id") != Some(&json!(id)) {
+                continue;
+            }
+            return match reply.get("error") {
+                Some(error) => Err(remote_error(error)),
+                None => Ok(reply.get("result").cloned().unwrap_or(Value::Null)),
+            };
+        }
+    }
+}
+
+fn connection_error(error: io::Error) -> AppError {
+    AppError::internal("Lost connection to AudioCat").with_detail(error)
+}
+
+// 托盘应用自身的错误在 data 中，原样还原；其他 JSON-RPC 错误当作内部错误
+fn remote_error(error: &Value) -> AppError {
+    if let Some(error) = error
+        .get("data")
+        .and_then(|data| serde_json::from_value(data.clone()).ok())
+    {
+        return error;
+    }
+    let message = error
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or("Unknown error");
+    AppError::internal(format!("AudioCat control API error: {}", message))
+}
@@ -0,0 +1,239 @@
+// 监听规则和配置项命令。托盘应用正在运行时，修改通过本地控制接口交给它，由它保存并立即生效；
+// 没有运行或用 --config 指定了其他文件时直接读写配置文件。读取总是读配置文件。
+use crate::Reply;
+use crate::remote::Remote;
+use audiocat_core::config::{WindowConfig, read_config, save_config};
+use audiocat_core::error::{AppError, AppResult};
+use audiocat_core::monitor::{
+    CompiledPattern, MonitorAction, MonitorRule, TriggerEvent, WindowMatcher,
+};
+use clap::{ArgGroup, Args, Subcommand, ValueEnum};
+use serde_json::{Value, json};
+use std::path::Path;
+
+#[derive(Subcommand)]
+pub enum MonitorCommand {
+    /// 列出窗口监听规则
+    List,
+    /// 添加一条窗口监听规则
+    Add(AddRule),
+    /// 按 list 中的序号删除一条窗口监听规则
+    Remove {
+        /// 规则序号，从 1 开始
+        index: usize,
+    },
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("matcher").required(true)))]
+pub struct AddRule {
+    /// 标题完全相等
+    #[arg(long, group = "matcher")]
+    title: Option<String>,
+    /// 标题包含子串
+    #[arg(long, group = "matcher")]
+    contains: Option<String>,
+    /// 标题匹配通配符，如 "* - Visual Studio Code"
+    #[arg(long, group = "matcher")]
+    glob: Option<String>,
+    /// 标题匹配正则表达式
+    #[arg(long, group = "matcher")]
+    regex: Option<String>,
+    /// 可执行文件名，如 obs64.exe，忽略大小写
+    #[arg(long, group = "matcher")]
+    process: Option<String>,
+    /// 窗口类名，忽略大小写
+    #[arg(long, group = "matcher")]
+    class: Option<String>,
+
+    /// 在什么窗口事件上触发
+    #[arg(long, value_enum, default_value_t = Event::Closed)]
+    on: Event,
+
+    /// 切换到指定设备（设备 id 或名称），默认显示切换器
+    #[arg(long, value_name = "DEVICE", conflicts_with = "revert")]
+    switch_to: Option<String>,
+    /// 恢复到之前的设备
+    #[arg(long)]
+    revert: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Event {
+    Opened,
+    Closed,
+    Focused,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// 输出整个配置或一个配置项
+    Get {
+        /// 配置项，如 auto_hide_delay、dark_mode、log_level
+        key: Option<String>,
+    },
+    /// 修改一个配置项，值按 JSON 解析，不是合法 JSON 时当作字符串
+    Set { key: String, value: String },
+}
+
+impl AddRule {
+    fn to_rule(&self) -> MonitorRule {
+        let matcher = if let Some(title) = &self.title {
+            WindowMatcher::ExactTitle(title.clone())
+        } else if let Some(needle) = &self.contains {
+            WindowMatcher::Substring(needle.clone())
+        } else if let Some(pattern) = &self.glob {
+            WindowMatcher::Glob(CompiledPattern::new(pattern.as_str()))
+        } else if let Some(pattern) = &self.regex {
+            WindowMatcher::Regex(CompiledPattern::new(pattern.as_str()))
+        } else if let Some(name) = &self.process {
+            WindowMatcher::Process(name.clone())
+        } else {
+            WindowMatcher::Class(self.class.clone().unwrap_or_default())
+        };
+        let event = match self.on {
+            Event::Opened => TriggerEvent::Opened,
+            Event::Closed => TriggerEvent::Closed,
+            Event::Focused => TriggerEvent::Focused,
+        };
+        let action = match (&self.switch_to, self.revert) {
+            (Some(device), _) => MonitorAction::SwitchTo(device.clone()),
+            (None, true) => MonitorAction::Revert,
+            (None, false) => MonitorAction::ShowSwitcher,
+        };
+        MonitorRule {
+            matcher,
+            event,
+            action,
+        }
+    }
+}
+
+pub fn monitor(path: &Path, remote: Option<Remote>, command: &MonitorCommand) -> AppResult<Reply> {
+    let mut rules = MonitorRules::load(path, remote)?;
+
+    match command {
+        MonitorCommand::List => {
+            let text = rules
+                .rules
+                .iter()
+                .enumerate()
+                .map(|(i, rule)| format!("{}: {}", i + 1, rule.describe()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Reply::new(&rules.rules, text))
+        }
+        MonitorCommand::Add(args) => {
+            let rule = args.to_rule();
+            rule.validate().map_err(AppError::invalid_input)?;
+            rules.rules.push(rule.clone());
+            rules.save(path)?;
+
+            let text = format!("已添加监听规则 {}: {}", rules.rules.len(), rule.describe());
+            Ok(Reply::new(&rule, text))
+        }
+        MonitorCommand::Remove { index } => {
+            if *index == 0 || *index > rules.rules.len() {
+                return Err(AppError::not_found(format!(
+                    "Monitor rule {} does not exist",
+                    index
+                )));
+            }
+            let rule = rules.rules.remove(index - 1);
+            rules.save(path)?;
+
+            let text = format!("已删除监听规则 {}: {}", index, rule.describe());
+            Ok(Reply::new(&rule, text))
+        }
+    }
+}
+
+// 托盘应用运行时是它内存中的规则，否则是配置文件中的规则
+struct MonitorRules {
+    rules: Vec<MonitorRule>,
+    remote: Option<Remote>,
+}
+
+impl MonitorRules {
+    fn load(path: &Path, mut remote: Option<Remote>) -> AppResult<Self> {
+        let rules = match &mut remote {
+            Some(remote) => {
+                let rules = remote.call("monitored_windows.get", Value::Null)?;
+                serde_json::from_value(rules).map_err(|e| {
+                    AppError::internal("Invalid monitor rules from AudioCat").with_detail(e)
+                })?
+            }
+            None => read_config(path)?.monitored_windows,
+        };
+        Ok(Self { rules, remote })
+    }
+
+    fn save(&mut self, path: &Path) -> AppResult<()> {
+        match &mut self.remote {
+            Some(remote) => {
+                let params = json!({ "windows": self.rules });
+                remote.call("monitored_windows.set", params).map(|_| ())
+            }
+            None => {
+                let mut config = read_config(path)?;
+                config.monitored_windows = self.rules.clone();
+                save_config(path, &config)
+            }
+        }
+    }
+}
+
+pub fn config(path: &Path, remote: Option<Remote>, command: &ConfigCommand) -> AppResult<Reply> {
+    let config = read_config(path)?;
+    let mut values = to_value(&config)?;
+
+    match command {
+        ConfigCommand::Get { key: None } => {
+            let text = serde_json::to_string_pretty(&values).unwrap_or_default();
+            Ok(Reply::new(&values, text))
+        }
+        ConfigCommand::Get { key: Some(key) } => {
+            let value = values.get(key).ok_or_else(|| unknown_key(key))?;
+            Ok(Reply::new(value, display(value)))
+        }
+        ConfigCommand::Set { key, value } => {
+            let slot = values.get_mut(key).ok_or_else(|| unknown_key(key))?;
+            // 这样 `config set log_level debug` 不需要给字符串加引号
+            *slot = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.clone()));
+
+            let value = match remote {
+                Some(mut remote) => {
+                    let params = json!({ "key": key, "value": slot.take() });
+                    remote.call("config.set", params)?
+                }
+                None => {
+                    let updated: WindowConfig = serde_json::from_value(values).map_err(|e| {
+                        AppError::invalid_input(format!("Invalid value for {}", key)).with_detail(e)
+                    })?;
+                    updated.validate().map_err(AppError::invalid_input)?;
+                    save_config(path, &updated)?;
+                    to_value(&updated)?[key.as_str()].take()
+                }
+            };
+            let text = format!("{} = {}", key, display(&value));
+            Ok(Reply::new(&value, text))
+        }
+    }
+}
+
+fn to_value(config: &WindowConfig) -> AppResult<Value> {
+    serde_json::to_value(config)
+        .map_err(|e| AppError::internal("Failed to serialize config").with_detail(e))
+}
+
+fn unknown_key(key: &str) -> AppError {
+    AppError::invalid_input(format!("Unknown config key: {}", key))
+}
+
+// 字符串直接输出，其他值输出为 JSON
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,293 @@
+// 命令行工具的退出码和 --json 输出：用临时目录中的配置文件运行编译好的 audiocat-cli。
+// XDG_RUNTIME_DIR 指向临时目录，测试时不会连上本机正在运行的托盘应用。
+use serde_json::{Value, json};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const USER: &str = "tester";
+
+struct Sandbox {
+    dir: TempDir,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        Self {
+            dir: TempDir::new().unwrap(),
+        }
+    }
+
+    fn config(&self) -> PathBuf {
+        self.dir.path().join("window_config.json")
+    }
+
+    fn write_config(&self, config: Value) {
+        fs::write(self.config(), config.to_string()).unwrap();
+    }
+
+    fn read_config(&self) -> Value {
+        serde_json::from_str(&fs::read_to_string(self.config()).unwrap()).unwrap()
+    }
+
+    // 不带 --config，使用托盘应用的配置文件
+    fn run_app(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_audiocat-cli"))
+            .args(args)
+            .env("XDG_RUNTIME_DIR", self.dir.path())
+            .env("USER", USER)
+            .env("USERNAME", USER)
+            .output()
+            .unwrap()
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        let config = self.config();
+        let mut all = vec!["--config", config.to_str().unwrap()];
+        all.extend_from_slice(args);
+        self.run_app(&all)
+    }
+}
+
+fn stdout_json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn stderr_json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stderr).unwrap()
+}
+
+fn base_config() -> Value {
+    json!({
+        "monitored_windows": ["Zoom Meeting"],
+        "auto_hide_delay": 7,
+        "main_window_position": null,
+        "preference_window_position": null,
+        "dark_mode": false
+    })
+}
+
+#[test]
+fn config_get_prints_values() {
+    let sandbox = Sandbox::new();
+    sandbox.write_config(base_config());
+
+    let output = sandbox.run(&["--json", "config", "get", "auto_hide_delay"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout_json(&output), json!(7));
+
+    let output = sandbox.run(&["config", "get", "dark_mode"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "false");
+
+    let output = sandbox.run(&["--json", "config", "get"]);
+    assert_eq!(
+        stdout_json(&output)["monitored_windows"],
+        json!([{
+            "matcher": { "type": "exact_title", "value": "Zoom Meeting" },
+            "event": "closed",
+            "action": { "type": "show_switcher" }
+        }])
+    );
+}
+
+#[test]
+fn config_set_saves_the_file() {
+    let sandbox = Sandbox::new();
+    sandbox.write_config(base_config());
+
+    let output = sandbox.run(&["--json", "config", "set", "auto_hide_delay", "10"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout_json(&output), json!(10));
+    assert_eq!(sandbox.read_config()["auto_hide_delay"], json!(10));
+    // 其他配置项保持不变
+    assert_eq!(sandbox.read_config()["dark_mode"], json!(false));
+
+    let output = sandbox.run(&["config", "set", "log_level", "debug"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "log_level = debug"
+    );
+}
+
+#[test]
+fn invalid_input_exits_with_8() {
+    let sandbox = Sandbox::new();
+    sandbox.write_config(base_config());
+
+    let output = sandbox.run(&["--json", "config", "set", "no_such_key", "1"]);
+    assert_eq!(output.status.code(), Some(8));
+    assert!(output.stdout.is_empty());
+    let error = stderr_json(&output);
+    assert_eq!(error["code"], "invalid_input");
+    assert_eq!(error["message"], "Unknown config key: no_such_key");
+
+    let output = sandbox.run(&["config", "set", "auto_hide_delay", "soon"]);
+    assert_eq!(output.status.code(), Some(8));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+    // 失败时不改动配置文件
+    assert_eq!(sandbox.read_config()["auto_hide_delay"], json!(7));
+}
+
+#[test]
+fn unreadable_config_exits_with_6() {
+    let sandbox = Sandbox::new();
+    fs::write(sandbox.config(), "{ not json").unwrap();
+
+    let output = sandbox.run(&["--json", "config", "set", "auto_hide_delay", "10"]);
+    assert_eq!(output.status.code(), Some(6));
+    assert_eq!(stderr_json(&output)["code"], "config_io");
+    // 不会用默认配置覆盖无法解析的文件
+    assert_eq!(fs::read_to_string(sandbox.config()).unwrap(), "{ not json");
+}
+
+#[test]
+fn monitor_rules_can_be_added_and_removed() {
+    let sandbox = Sandbox::new();
+    sandbox.write_config(base_config());
+
+    let output = sandbox.run(&[
+        "--json",
+        "monitor",
+        "add",
+        "--process",
+        "obs64.exe",
+        "--on",
+        "opened",
+        "--switch-to",
+        "Headphones",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let rule = json!({
+        "matcher": { "type": "process", "value": "obs64.exe" },
+        "event": "opened",
+        "action": { "type": "switch_to", "device": "Headphones" }
+    });
+    assert_eq!(stdout_json(&output), rule);
+
+    let output = sandbox.run(&["--json", "monitor", "list"]);
+    let rules = stdout_json(&output);
+    assert_eq!(rules.as_array().unwrap().len(), 2);
+    assert_eq!(rules[1], rule);
+
+    let output = sandbox.run(&["monitor", "remove", "1"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(sandbox.read_config()["monitored_windows"], json!([rule]));
+
+    let output = sandbox.run(&["--json", "monitor", "remove", "5"]);
+    assert_eq!(output.status.code(), Some(9));
+    assert_eq!(stderr_json(&output)["code"], "not_found");
+
+    let output = sandbox.run(&["--json", "monitor", "add", "--regex", "(unclosed"]);
+    assert_eq!(output.status.code(), Some(8));
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    let sandbox = Sandbox::new();
+    assert_eq!(sandbox.run(&["set"]).status.code(), Some(2));
+    assert_eq!(sandbox.run(&["monitor", "add"]).status.code(), Some(2));
+    assert_eq!(sandbox.run(&["no-such-command"]).status.code(), Some(2));
+}
+
+// 托盘应用没有运行时直接调用音频后端，Linux 上还没有音频后端
+#[cfg(target_os = "linux")]
+#[test]
+fn switching_without_the_app_reports_missing_backend() {
+    let sandbox = Sandbox::new();
+    let output = sandbox.run_app(&["--json", "set", "Headphones"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(stderr_json(&output)["code"], "backend_unavailable");
+}
+
+// 假的托盘应用：在本地控制接口上回复 devices.list 和 devices.set，返回收到的请求
+#[cfg(unix)]
+fn fake_app(sandbox: &Sandbox) -> std::thread::JoinHandle<Vec<Value>> {
+    use audiocat_core::config::APP_IDENTIFIER;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+
+    let socket = sandbox
+        .dir
+        .path()
+        .join(format!("{}.{}.rpc.sock", APP_IDENTIFIER, USER));
+    let listener = UnixListener::bind(socket).unwrap();
+
+    std::thread::spawn(move || {
+        let devices = json!([
+            { "id": "speakers", "name": "Speakers", "is_default": true },
+            { "id": "headphones", "name": "Headphones", "is_default": false },
+        ]);
+        let (stream, _) = listener.accept().unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut requests = Vec::new();
+
+        for line in BufReader::new(stream).lines() {
+            let request: Value = serde_json::from_str(&line.unwrap()).unwrap();
+            let result = match request["method"].as_str() {
+                Some("devices.list") => devices.clone(),
+                Some("devices.set") => json!({
+                    "id": "headphones",
+                    "name": "Headphones",
+                    "is_default": true
+                }),
+                _ => Value::Null,
+            };
+            // 先推送一条事件通知，命令行工具应该跳过它
+            writeln!(
+                writer,
+                r#"{{"jsonrpc":"2.0","method":"event","params":{{}}}}"#
+            )
+            .unwrap();
+            let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+            writeln!(writer, "{}", reply).unwrap();
+            requests.push(request);
+        }
+        requests
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn set_goes_through_the_running_app() {
+    let sandbox = Sandbox::new();
+    let app = fake_app(&sandbox);
+
+    let output = sandbox.run_app(&["--json", "set", "Headphones"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout_json(&output),
+        json!({
+            "previous": { "id": "speakers", "name": "Speakers", "is_default": true },
+            "current": { "id": "headphones", "name": "Headphones", "is_default": true },
+        })
+    );
+
+    let requests = app.join().unwrap();
+    let methods: Vec<&str> = requests
+        .iter()
+        .map(|request| request["method"].as_str().unwrap())
+        .collect();
+    assert_eq!(methods, ["devices.list", "devices.set"]);
+    assert_eq!(requests[1]["params"], json!({ "device": "Headphones" }));
+}
+
+#[cfg(unix)]
+#[test]
+fn cycle_goes_through_the_running_app() {
+    let sandbox = Sandbox::new();
+    let app = fake_app(&sandbox);
+
+    let output = sandbox.run_app(&["cycle"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "成功切换到音频设备: Headphones"
+    );
+
+    // 按设备 id 切换到列表中的下一个设备
+    let requests = app.join().unwrap();
+    assert_eq!(requests[1]["method"], "devices.set");
+    assert_eq!(requests[1]["params"], json!({ "device": "headphones" }));
+}
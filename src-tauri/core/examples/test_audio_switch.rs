@@ -0,0 +1,47 @@
+// 手动测试音频设备切换：列出输出设备，选择一个设为默认设备。
+// 直接使用核心库的音频后端（audiocat_core::audio），和托盘应用走同一条代码路径。
+use audiocat_core::audio::{AudioBackend, AudioClient};
+use std::io;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let audio = AudioClient::spawn();
+
+    // 列出所有设备
+    let devices = audio.call_blocking(AudioBackend::devices)?;
+    if devices.is_empty() {
+        println!("没有找到可用的音频设备");
+        return Ok(());
+    }
+
+    println!("找到 {} 个音频输出设备:", devices.len());
+    for (i, device) in devices.iter().enumerate() {
+        let status = if device.is_default {
+            " (当前默认)"
+        } else {
+            ""
+        };
+        println!("  {}: {}{}", i + 1, device.name, status);
+    }
+
+    // 让用户选择设备
+    println!("\n请选择要切换到的设备 (输入数字 1-{}):", devices.len());
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let choice: usize = match input.trim().parse::<usize>() {
+        Ok(n) if n > 0 && n <= devices.len() => n - 1,
+        _ => {
+            println!("无效的选择");
+            return Ok(());
+        }
+    };
+
+    // 设置为默认设备
+    let device_id = devices[choice].id.clone();
+    match audio.call_blocking(move |backend| backend.set_default(&device_id)) {
+        Ok(outcome) => println!("成功切换到音频设备: {}", outcome.current.name),
+        Err(e) => println!("操作失败: {}", e),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+// 音频后端：所有音频调用都在一个常驻的工作线程上执行。
+// 具体的后端由 Cargo 特性选择（Windows 上的 wasapi），没有可用后端时所有调用都返回
+// BackendUnavailable。命令通过通道把请求交给工作线程，异步命令 await 结果，不会阻塞 UI 线程。
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{Sender, channel};
+
+#[cfg(all(target_os = "windows", feature = "wasapi"))]
+mod wasapi;
+#[cfg(all(target_os = "windows", feature = "wasapi"))]
+pub use wasapi::AudioBackend;
+
+#[cfg(not(all(target_os = "windows", feature = "wasapi")))]
+mod unsupported;
+#[cfg(not(all(target_os = "windows", feature = "wasapi")))]
+pub use unsupported::AudioBackend;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioDevice {
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+}
+
+impl AudioDevice {
+    // 规则和命令行里的目标设备可以写设备 id 或设备名称
+    pub fn matches(&self, target: &str) -> bool {
+        self.id == target || self.name == target
+    }
+}
+
+// 默认设备在列表中的下一个设备，到末尾后回到第一个；没有默认设备时返回第一个
+pub fn next_device(devices: &[AudioDevice]) -> Option<&AudioDevice> {
+    let next = match devices.iter().position(|device| device.is_default) {
+        Some(index) => (index + 1) % devices.len(),
+        None => 0,
+    };
+    devices.get(next)
+}
+
+// 切换默认设备的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SwitchOutcome {
+    pub previous: Option<AudioDevice>, // 切换前的默认设备
+    pub current: AudioDevice,
+}
+
+type Job = Box<dyn FnOnce(&mut AudioBackend) + Send>;
+
+// 工作线程的句柄，可以在多个线程间克隆使用
+#[derive(Clone)]
+pub struct AudioClient {
+    jobs: Sender<Job>,
+}
+
+impl AudioClient {
+    // 启动音频工作线程，线程在所有句柄都被丢弃后退出
+    pub fn spawn() -> Self {
+        let (jobs, receiver) = channel::<Job>();
+
+        std::thread::spawn(move || {
+            let mut backend = AudioBackend::new();
+            for job in receiver {
+                job(&mut backend);
+            }
+        });
+
+        Self { jobs }
+    }
+
+    // 在工作线程上执行 f，供异步命令使用
+    pub async fn call<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut AudioBackend) -> AppResult<T> + Send + 'static,
+    ) -> AppResult<T> {
+        let (reply, result) = tokio::sync::oneshot::channel();
+        self.submit(Box::new(move |backend| {
+            let _ = reply.send(run_job(backend, f));
+        }))?;
+        result.await.map_err(|_| worker_stopped())?
+    }
+
+    // 在工作线程上执行 f 并等待结果，供监听线程、托盘菜单等同步代码使用；
+    // 不能在异步命令中调用
+    pub fn call_blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut AudioBackend) -> AppResult<T> + Send + 'static,
+    ) -> AppResult<T> {
+        let (reply, result) = channel();
+        self.submit(Box::new(move |backend| {
+            let _ = reply.send(run_job(backend, f));
+        }))?;
+        result.recv().map_err(|_| worker_stopped())?
+    }
+
+    fn submit(&self, job: Job) -> AppResult<()> {
+        self.jobs.send(job).map_err(|_| worker_stopped())
+    }
+}
+
+// 执行一个任务；任务 panic 时返回错误并重新创建后端，工作线程继续处理后面的任务
+fn run_job<T>(
+    backend: &mut AudioBackend,
+    f: impl FnOnce(&mut AudioBackend) -> AppResult<T>,
+) -> AppResult<T> {
+    match panic::catch_unwind(AssertUnwindSafe(|| f(backend))) {
+        Ok(result) => result,
+        Err(payload) => {
+            let detail = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            // panic 时后端可能处于不一致的状态，丢弃它
+            *backend = AudioBackend::new();
+            Err(AppError::internal("Audio backend call panicked").with_detail(detail))
+        }
+    }
+}
+
+fn worker_stopped() -> AppError {
+    AppError::backend("Audio worker thread stopped")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn worker_survives_a_panicking_job() {
+        let client = AudioClient::spawn();
+        let error = client
+            .call_blocking(|_| -> AppResult<()> { panic!("boom") })
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::Internal);
+        assert_eq!(error.detail.as_deref(), Some("boom"));
+
+        assert_eq!(client.call_blocking(|_| Ok(42)).unwrap(), 42);
+    }
+}
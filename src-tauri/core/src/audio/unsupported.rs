@@ -0,0 +1,40 @@
+// 没有启用当前平台的音频后端时使用：所有调用都返回 BackendUnavailable，
+// 这样规则、配置等不依赖音频的功能仍然可以在任何平台上编译和使用
+use super::{AudioDevice, SwitchOutcome};
+use crate::error::{AppError, AppResult};
+
+pub struct AudioBackend;
+
+impl AudioBackend {
+    pub(super) fn new() -> Self {
+        Self
+    }
+
+    pub fn devices(&mut self) -> AppResult<Vec<AudioDevice>> {
+        Err(unsupported())
+    }
+
+    pub fn current_device(&mut self) -> AppResult<AudioDevice> {
+        Err(unsupported())
+    }
+
+    pub fn set_default(&mut self, _device_id: &str) -> AppResult<SwitchOutcome> {
+        Err(unsupported())
+    }
+
+    pub fn volume(&mut self) -> AppResult<f32> {
+        Err(unsupported())
+    }
+
+    pub fn set_volume(&mut self, _level: f32) -> AppResult<()> {
+        Err(unsupported())
+    }
+
+    pub fn set_mute(&mut self, _muted: bool) -> AppResult<()> {
+        Err(unsupported())
+    }
+}
+
+fn unsupported() -> AppError {
+    AppError::backend("No audio backend is enabled for this platform")
+}
@@ -0,0 +1,199 @@
+// WASAPI 后端：工作线程初始化自己的 COM 套间并缓存设备枚举器和 PolicyConfig
+use super::{AudioDevice, SwitchOutcome};
+use crate::error::{AppError, AppResult, ErrorCode};
+use crate::logging::AUDIO;
+use com_policy_config::{IPolicyConfig, PolicyConfigClient};
+use log::{debug, info, warn};
+use windows::{
+    Win32::{
+        Devices::FunctionDiscovery::PKEY_Device_FriendlyName,
+        Foundation::{BOOL, ERROR_NOT_FOUND},
+        Media::Audio::{
+            DEVICE_STATE_ACTIVE, Endpoints::IAudioEndpointVolume, IMMDevice, IMMDeviceEnumerator,
+            MMDeviceEnumerator, eConsole, eRender,
+        },
+        System::Com::{
+            CLSCTX_ALL, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx, STGM_READ,
+        },
+    },
+    core::{HSTRING, PCWSTR},
+};
+
+// 工作线程缓存的 COM 接口
+struct Interfaces {
+    enumerator: IMMDeviceEnumerator,
+    policy_config: IPolicyConfig,
+}
+
+impl Interfaces {
+    fn create() -> AppResult<Self> {
+        unsafe {
+            Ok(Self {
+                enumerator: CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?,
+                policy_config: CoCreateInstance(&PolicyConfigClient, None, CLSCTX_ALL)?,
+            })
+        }
+    }
+}
+
+// 只在工作线程上使用，COM 接口不能跨线程
+pub struct AudioBackend {
+    interfaces: Option<Interfaces>,
+}
+
+impl AudioBackend {
+    pub(super) fn new() -> Self {
+        // 工作线程自己的 COM 套间，线程结束前一直有效
+        if let Err(e) = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) } {
+            warn!(target: AUDIO, "Failed to initialize COM on audio worker: {:?}", e);
+        }
+        Self { interfaces: None }
+    }
+
+    // 按需创建接口；后端不可用（如音频服务重启）时丢弃缓存，下次调用重新创建
+    fn with_interfaces<T>(&mut self, f: impl FnOnce(&Interfaces) -> AppResult<T>) -> AppResult<T> {
+        let interfaces = match self.interfaces.take() {
+            Some(interfaces) => interfaces,
+            None => Interfaces::create()?,
+        };
+        let result = f(&interfaces);
+        if !matches!(&result, Err(e) if e.code == ErrorCode::BackendUnavailable) {
+            self.interfaces = Some(interfaces);
+        }
+        result
+    }
+
+    // 所有已启用的输出设备
+    pub fn devices(&mut self) -> AppResult<Vec<AudioDevice>> {
+        debug!(target: AUDIO, "Listing audio output devices");
+        self.with_interfaces(|interfaces| unsafe {
+            let collection = interfaces
+                .enumerator
+                .EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+
+            // 没有任何输出设备时取不到默认设备，此时返回空列表
+            let default_id = match interfaces
+                .enumerator
+                .GetDefaultAudioEndpoint(eRender, eConsole)
+            {
+                Ok(device) => Some(device.GetId()?.to_string()?),
+                Err(_) => None,
+            };
+
+            let mut devices = Vec::new();
+            for i in 0..collection.GetCount()? {
+                let device = device_info(&collection.Item(i)?)?;
+                devices.push(AudioDevice {
+                    is_default: default_id.as_ref() == Some(&device.id),
+                    ..device
+                });
+            }
+            Ok(devices)
+        })
+    }
+
+    // 当前默认输出设备
+    pub fn current_device(&mut self) -> AppResult<AudioDevice> {
+        debug!(target: AUDIO, "Reading default audio device");
+        self.with_interfaces(|interfaces| unsafe {
+            let device = interfaces
+                .enumerator
+                .GetDefaultAudioEndpoint(eRender, eConsole)?;
+            Ok(AudioDevice {
+                is_default: true,
+                ..device_info(&device)?
+            })
+        })
+    }
+
+    // 把指定设备设为默认输出设备（Console 角色用于大多数应用程序）
+    pub fn set_default(&mut self, device_id: &str) -> AppResult<SwitchOutcome> {
+        let previous = self.current_device().ok();
+
+        let result = self.with_interfaces(|interfaces| unsafe {
+            let id = HSTRING::from(device_id);
+            let device = interfaces.enumerator.GetDevice(&id).map_err(|e| {
+                if e.code() == ERROR_NOT_FOUND.to_hresult() {
+                    AppError::device_not_found(device_id)
+                } else {
+                    AppError::from(e)
+                }
+            })?;
+            let current = device_info(&device)?;
+
+            if device.GetState()? != DEVICE_STATE_ACTIVE {
+                return Err(AppError::device_inactive(&current.name));
+            }
+
+            interfaces
+                .policy_config
+                .SetDefaultEndpoint(PCWSTR(id.as_ptr()), eConsole)?;
+            Ok(AudioDevice {
+                is_default: true,
+                ..current
+            })
+        });
+
+        match result {
+            Ok(current) => {
+                info!(target: AUDIO, "Switched default audio device to {}", current.name);
+                Ok(SwitchOutcome { previous, current })
+            }
+            Err(e) => {
+                warn!(target: AUDIO, "Failed to switch audio device: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    // 默认输出设备的主音量，取值 0.0-1.0
+    pub fn volume(&mut self) -> AppResult<f32> {
+        self.with_endpoint_volume(|volume| unsafe { volume.GetMasterVolumeLevelScalar() })
+    }
+
+    // 设置默认输出设备的主音量，level 取值 0.0-1.0
+    pub fn set_volume(&mut self, level: f32) -> AppResult<()> {
+        self.with_endpoint_volume(|volume| unsafe {
+            volume.SetMasterVolumeLevelScalar(level.clamp(0.0, 1.0), std::ptr::null())
+        })
+    }
+
+    // 设置默认输出设备的静音状态
+    pub fn set_mute(&mut self, muted: bool) -> AppResult<()> {
+        self.with_endpoint_volume(|volume| unsafe {
+            volume.SetMute(BOOL::from(muted), std::ptr::null())
+        })
+    }
+
+    fn with_endpoint_volume<T>(
+        &mut self,
+        f: impl FnOnce(&IAudioEndpointVolume) -> windows::core::Result<T>,
+    ) -> AppResult<T> {
+        self.with_interfaces(|interfaces| unsafe {
+            let device = interfaces
+                .enumerator
+                .GetDefaultAudioEndpoint(eRender, eConsole)?;
+            let volume: IAudioEndpointVolume = device.Activate(CLSCTX_ALL, None)?;
+            f(&volume).map_err(AppError::from)
+        })
+    }
+}
+
+// 读取设备 id 和名称，is_default 由调用方设置
+unsafe fn device_info(device: &IMMDevice) -> AppResult<AudioDevice> {
+    let id = device.GetId()?.to_string()?;
+    let property_store = device.OpenPropertyStore(STGM_READ)?;
+    let name_prop = property_store.GetValue(&PKEY_Device_FriendlyName)?;
+    let name = name_prop
+        .Anonymous
+        .Anonymous
+        .Anonymous
+        .pwszVal
+        .to_string()?;
+
+    Ok(AudioDevice {
+        id,
+        name,
+        is_default: false,
+    })
+}
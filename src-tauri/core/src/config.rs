@@ -0,0 +1,334 @@
+// 配置文件：应用数据目录下的 window_config.json，保存监听规则、窗口位置和各项设置。
+// 托盘应用和其他工具读写同一个文件，路径由调用方给出。
+use crate::error::{AppError, AppResult};
+use crate::focus_switch::FocusSwitchConfig;
+use crate::logging::{self, CONFIG};
+use crate::monitor::MonitorRule;
+use crate::process_monitor::ProcessRule;
+use crate::rules::Rule;
+use crate::schedule::ScheduleRule;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 托盘应用的标识，和 tauri.conf.json5 中的 identifier 一致，应用数据目录以它命名
+pub const APP_IDENTIFIER: &str = "com.audiocat.app";
+
+pub const CONFIG_FILE_NAME: &str = "window_config.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WindowPosition {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowConfig {
+    pub monitored_windows: Vec<MonitorRule>, // 旧版的字符串数组会被迁移为精确标题规则
+    pub auto_hide_delay: u64,
+    pub main_window_position: Option<WindowPosition>,
+    pub preference_window_position: Option<WindowPosition>,
+    pub dark_mode: bool,
+    #[serde(default)]
+    pub focus_switch: FocusSwitchConfig, // 焦点跟随切换设备
+    #[serde(default)]
+    pub process_rules: Vec<ProcessRule>, // 进程启动/退出规则
+    #[serde(default)]
+    pub schedule_rules: Vec<ScheduleRule>, // 定时规则
+    #[serde(default)]
+    pub rules: Vec<Rule>, // 规则引擎的规则
+    #[serde(default = "default_log_level")]
+    pub log_level: String, // 日志级别：off、error、warn、info、debug、trace
+    #[serde(default)]
+    pub http_api: HttpApiConfig, // 本地 HTTP 接口
+    #[serde(default)]
+    pub mqtt: MqttConfig, // MQTT 桥接
+    #[serde(default)]
+    pub osc: OscConfig, // OSC 服务器
+}
+
+// 本地 HTTP 接口，默认关闭；开启后只监听 127.0.0.1
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String, // 请求需要带 Authorization: Bearer <token>，开启时为空会自动生成
+}
+
+impl Default for HttpApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 17380,
+            token: String::new(),
+        }
+    }
+}
+
+// 配置会写入日志，不输出 token
+impl std::fmt::Debug for HttpApiConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpApiConfig")
+            .field("enabled", &self.enabled)
+            .field("port", &self.port)
+            .field("token", &if self.token.is_empty() { "" } else { "***" })
+            .finish()
+    }
+}
+
+impl HttpApiConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.port == 0 {
+            return Err("HTTP API port must not be 0".to_string());
+        }
+        Ok(())
+    }
+
+    // 开启时没有 token 则生成一个，返回是否生成了新的 token
+    pub fn ensure_token(&mut self) -> AppResult<bool> {
+        if !self.enabled || !self.token.is_empty() {
+            return Ok(false);
+        }
+        self.token = generate_token()?;
+        Ok(true)
+    }
+}
+
+// MQTT 桥接，默认关闭；开启后发布当前设备、设备列表和音量，并接收切换命令
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: String, // 为空时不认证
+    pub password: String,
+    pub topic_prefix: String, // 为空时使用 audiocat/<主机名>
+    pub discovery: bool,      // 发布 Home Assistant 自动发现配置
+    pub discovery_prefix: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: String::new(),
+            password: String::new(),
+            topic_prefix: String::new(),
+            discovery: true,
+            discovery_prefix: "homeassistant".to_string(),
+        }
+    }
+}
+
+// 配置会写入日志，不输出密码
+impl std::fmt::Debug for MqttConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttConfig")
+            .field("enabled", &self.enabled)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field(
+                "password",
+                &if self.password.is_empty() { "" } else { "***" },
+            )
+            .field("topic_prefix", &self.topic_prefix)
+            .field("discovery", &self.discovery)
+            .field("discovery_prefix", &self.discovery_prefix)
+            .finish()
+    }
+}
+
+impl MqttConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.host.trim().is_empty() {
+            return Err("MQTT host must not be empty".to_string());
+        }
+        if self.port == 0 {
+            return Err("MQTT port must not be 0".to_string());
+        }
+        // 前缀是发布和订阅的主题的一部分，不能带通配符
+        for prefix in [&self.topic_prefix, &self.discovery_prefix] {
+            if prefix.contains(['+', '#']) {
+                return Err(format!(
+                    "MQTT topic prefix must not contain wildcards: {}",
+                    prefix
+                ));
+            }
+        }
+        if self.discovery && self.discovery_prefix.trim().is_empty() {
+            return Err("MQTT discovery prefix must not be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+// OSC 服务器，默认关闭，只监听本机；OSC 没有认证，监听其他网卡时只接受 allowed_clients
+// 中的控制器（本机总是允许）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OscConfig {
+    pub enabled: bool,
+    pub address: String,              // 监听的 IP 地址
+    pub port: u16,                    // UDP 端口
+    pub allowed_clients: Vec<String>, // 允许控制的其他设备的 IP 地址
+}
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1".to_string(),
+            port: 9000,
+            allowed_clients: Vec::new(),
+        }
+    }
+}
+
+impl OscConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.address
+            .parse::<std::net::IpAddr>()
+            .map_err(|_| format!("Invalid OSC listen address: {}", self.address))?;
+        if self.port == 0 {
+            return Err("OSC port must not be 0".to_string());
+        }
+        for client in &self.allowed_clients {
+            client
+                .parse::<std::net::IpAddr>()
+                .map_err(|_| format!("Invalid OSC client address: {}", client))?;
+        }
+        Ok(())
+    }
+}
+
+// 32 字节随机数的十六进制
+pub fn generate_token() -> AppResult<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)
+        .map_err(|e| AppError::internal("Failed to generate token").with_detail(e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn default_log_level() -> String {
+    logging::DEFAULT_LEVEL.to_string()
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            monitored_windows: Vec::new(),
+            auto_hide_delay: 5, // 默认5秒
+            main_window_position: None,
+            preference_window_position: None,
+            dark_mode: false, // 默认浅色模式
+            focus_switch: FocusSwitchConfig::default(),
+            process_rules: Vec::new(),
+            schedule_rules: Vec::new(),
+            rules: Vec::new(),
+            log_level: default_log_level(),
+            http_api: HttpApiConfig::default(),
+            mqtt: MqttConfig::default(),
+            osc: OscConfig::default(),
+        }
+    }
+}
+
+impl WindowConfig {
+    // 检查各类规则和日志级别，直接修改配置文件的调用方在保存前调用
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.monitored_windows {
+            rule.validate()?;
+        }
+        for rule in &self.process_rules {
+            rule.validate()?;
+        }
+        for rule in &self.schedule_rules {
+            rule.validate()?;
+        }
+        for rule in &self.rules {
+            rule.validate()
+                .map_err(|e| format!("Invalid rule '{}': {}", rule.label(), e))?;
+        }
+        logging::parse_level(&self.log_level)?;
+        self.http_api.validate()?;
+        self.mqtt.validate()?;
+        self.osc.validate()?;
+        Ok(())
+    }
+}
+
+// 托盘应用的数据目录，和 Tauri 的 app_data_dir 规则相同：
+// Windows 为 %APPDATA%，macOS 为 ~/Library/Application Support，Linux 为 $XDG_DATA_HOME 或 ~/.local/share
+pub fn default_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| Path::new(dir).is_absolute())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+    }?;
+    Some(base.join(APP_IDENTIFIER))
+}
+
+// 数据目录下的配置文件路径
+pub fn config_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(CONFIG_FILE_NAME)
+}
+
+// 读取窗口配置：文件不存在时返回默认配置，读取或解析失败时返回错误。
+// 修改后要写回的调用方（如命令行）用它，避免把损坏的配置覆盖成默认配置
+pub fn read_config(config_path: &Path) -> AppResult<WindowConfig> {
+    if !config_path.exists() {
+        return Ok(WindowConfig::default());
+    }
+    let content = fs::read_to_string(config_path)
+        .map_err(|e| AppError::io("Failed to read config file", &e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::config_io("Failed to parse config file").with_detail(e))
+}
+
+// 加载窗口配置，文件不存在或无法解析时使用默认配置
+pub fn load_config(config_path: &Path) -> WindowConfig {
+    if config_path.exists() {
+        match read_config(config_path) {
+            Ok(config) => {
+                debug!(target: CONFIG, "Loaded window config: {:?}", config);
+                return config;
+            }
+            Err(e) => warn!(target: CONFIG, "{}", e),
+        }
+    }
+
+    info!(target: CONFIG, "Using default window config");
+    WindowConfig::default()
+}
+
+// 保存窗口配置
+pub fn save_config(config_path: &Path, config: &WindowConfig) -> AppResult<()> {
+    // 确保父目录存在
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create config directory", &e))?;
+    }
+
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| AppError::internal("Failed to serialize config").with_detail(e))?;
+
+    fs::write(config_path, content).map_err(|e| AppError::io("Failed to write config file", &e))?;
+
+    debug!(target: CONFIG, "Saved window config: {:?}", config);
+    Ok(())
+}
@@ -0,0 +1,145 @@
+// 命令和核心库返回的错误。code 是稳定的错误码，前端按它给出可操作的提示；
+// message 是给人看的说明，detail 是底层错误（HRESULT、io 错误等），没有时为 null。
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    DeviceNotFound,     // 设备不存在（已移除或 id 错误）
+    DeviceInactive,     // 设备存在但已禁用或未插入
+    BackendUnavailable, // 音频或窗口后端不可用，如 COM 调用失败、连不上 X server
+    ConfigIo,           // 配置、日志、事件历史等文件读写失败
+    Permission,         // 没有权限（访问被拒绝）
+    InvalidInput,       // 参数或规则不合法
+    NotFound,           // 窗口、切换记录等设备以外的对象不存在
+    Internal,           // 应用状态未初始化等内部错误
+}
+
+// 命令行工具从本地控制接口的错误回复中还原
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub detail: Option<String>,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            detail: None,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl fmt::Display) -> Self {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    pub fn device_not_found(device: &str) -> Self {
+        Self::new(
+            ErrorCode::DeviceNotFound,
+            format!("Audio device not found: {}", device),
+        )
+    }
+
+    pub fn device_inactive(device: &str) -> Self {
+        Self::new(
+            ErrorCode::DeviceInactive,
+            format!("Audio device is disabled or unplugged: {}", device),
+        )
+    }
+
+    pub fn backend(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::BackendUnavailable, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    // 托管状态取不到或锁已中毒
+    pub fn state() -> Self {
+        Self::internal("Failed to access app state")
+    }
+
+    // 文件读写错误：访问被拒绝归为 Permission，其余归为 ConfigIo
+    pub fn io(message: impl Into<String>, error: &std::io::Error) -> Self {
+        let code = match error.kind() {
+            std::io::ErrorKind::PermissionDenied => ErrorCode::Permission,
+            _ => ErrorCode::ConfigIo,
+        };
+        Self::new(code, message).with_detail(error)
+    }
+
+    // 各模块内部仍返回 String 错误的文件操作（事件历史、日志）
+    pub fn config_io(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ConfigIo, message)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {}", self.message, detail),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+// WASAPI / COM 调用的错误，按 HRESULT 归类，原始错误放在 detail 中
+#[cfg(all(target_os = "windows", feature = "wasapi"))]
+impl From<windows::core::Error> for AppError {
+    fn from(error: windows::core::Error) -> Self {
+        use windows::Win32::{
+            Foundation::{
+                CO_E_NOTINITIALIZED, E_ACCESSDENIED, ERROR_NOT_FOUND, REGDB_E_CLASSNOTREG,
+            },
+            Media::Audio::AUDCLNT_E_DEVICE_INVALIDATED,
+        };
+
+        let hresult = error.code();
+        let (code, message) = if hresult == E_ACCESSDENIED {
+            (
+                ErrorCode::Permission,
+                "Access to the audio device was denied",
+            )
+        } else if hresult == AUDCLNT_E_DEVICE_INVALIDATED {
+            (
+                ErrorCode::DeviceInactive,
+                "The audio device was disabled or unplugged",
+            )
+        } else if hresult == ERROR_NOT_FOUND.to_hresult() {
+            (ErrorCode::DeviceNotFound, "Audio device not found")
+        } else if hresult == CO_E_NOTINITIALIZED || hresult == REGDB_E_CLASSNOTREG {
+            (
+                ErrorCode::BackendUnavailable,
+                "Audio backend is not available",
+            )
+        } else {
+            (ErrorCode::BackendUnavailable, "Audio backend call failed")
+        };
+        Self::new(code, message).with_detail(format!("{:?}", error))
+    }
+}
+
+// 设备 id、名称等 UTF-16 字符串无法转换
+impl From<std::string::FromUtf16Error> for AppError {
+    fn from(error: std::string::FromUtf16Error) -> Self {
+        Self::backend("Failed to read audio device property").with_detail(error)
+    }
+}
@@ -0,0 +1,370 @@
+// 事件历史：记录观察到的窗口、进程、设备变化和规则触发，用于排查"规则为什么没有触发"。
+// 内存中保留最近的记录，同时逐行追加到应用数据目录下的 JSON Lines 文件，重启后仍可查看。
+use crate::logging::MONITOR;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 内存中最多保留的条数，超出后丢弃最早的记录
+const MAX_EVENTS: usize = 1000;
+// 文件超过这么多行时按内存中的记录重写，避免无限增长
+const MAX_FILE_LINES: usize = MAX_EVENTS * 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Window,  // 窗口打开、关闭、标题变化、获得焦点
+    Process, // 进程启动、退出
+    Device,  // 设备连接、断开、默认设备切换
+    Rule,    // 规则触发或因条件不满足而跳过
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    pub timestamp: u64, // Unix 时间戳（秒）
+    pub kind: EventKind,
+    pub summary: String, // 一行描述，如 "closed: Zoom Meeting (Zoom.exe)"
+    #[serde(default)]
+    pub detail: serde_json::Value, // 原始事件内容
+}
+
+// get_event_history 的过滤条件，所有字段都可以省略
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryFilter {
+    pub kinds: Vec<EventKind>, // 为空表示所有类型
+    pub since: Option<u64>,    // 不早于该时间戳
+    pub until: Option<u64>,    // 不晚于该时间戳
+    pub text: Option<String>,  // 描述中包含的文字（不区分大小写）
+    pub limit: Option<usize>,  // 只返回最近的若干条
+}
+
+impl HistoryFilter {
+    fn matches(&self, event: &HistoryEvent) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind) {
+            return false;
+        }
+        if self.since.is_some_and(|since| event.timestamp < since)
+            || self.until.is_some_and(|until| event.timestamp > until)
+        {
+            return false;
+        }
+        match &self.text {
+            Some(text) if !text.is_empty() => {
+                event.summary.to_lowercase().contains(&text.to_lowercase())
+            }
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EventHistory {
+    events: VecDeque<HistoryEvent>,
+    path: Option<PathBuf>, // None 时只保存在内存中
+    file_lines: usize,
+}
+
+pub type SharedEventHistory = Arc<Mutex<EventHistory>>;
+
+impl EventHistory {
+    // 从文件加载最近的记录，无法解析的行会被忽略
+    pub fn load(path: PathBuf) -> Self {
+        let mut history = EventHistory::default();
+
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                for line in content.lines() {
+                    history.file_lines += 1;
+                    if let Ok(event) = serde_json::from_str::<HistoryEvent>(line) {
+                        history.events.push_back(event);
+                        if history.events.len() > MAX_EVENTS {
+                            history.events.pop_front();
+                        }
+                    }
+                }
+            }
+            Err(e) if path.exists() => {
+                warn!(target: MONITOR, "Failed to read event history: {}", e)
+            }
+            Err(_) => {}
+        }
+
+        history.path = Some(path);
+        history
+    }
+
+    pub fn record(&mut self, kind: EventKind, summary: String, detail: serde_json::Value) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let event = HistoryEvent {
+            timestamp,
+            kind,
+            summary,
+            detail,
+        };
+
+        if let Err(e) = self.append_to_file(&event) {
+            warn!(target: MONITOR, "Failed to write event history: {}", e);
+        }
+
+        self.events.push_back(event);
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    // 按过滤条件查询，最早的在前
+    pub fn query(&self, filter: &HistoryFilter) -> Vec<HistoryEvent> {
+        let matched: Vec<&HistoryEvent> = self
+            .events
+            .iter()
+            .filter(|event| filter.matches(event))
+            .collect();
+        let skip = match filter.limit {
+            Some(limit) => matched.len().saturating_sub(limit),
+            None => 0,
+        };
+        matched.into_iter().skip(skip).cloned().collect()
+    }
+
+    // 清空内存中的记录并删除文件
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.events.clear();
+        self.file_lines = 0;
+        match &self.path {
+            Some(path) if path.exists() => fs::remove_file(path)
+                .map_err(|e| format!("Failed to remove event history file: {}", e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn append_to_file(&mut self, event: &HistoryEvent) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create history directory: {}", e))?;
+        }
+
+        let line = serde_json::to_string(event)
+            .map_err(|e| format!("Failed to serialize event: {}", e))?;
+
+        // 文件过大时用内存中的记录重写，再追加新的一条
+        if self.file_lines >= MAX_FILE_LINES {
+            let mut content = String::new();
+            for existing in &self.events {
+                if let Ok(existing) = serde_json::to_string(existing) {
+                    content.push_str(&existing);
+                    content.push('\n');
+                }
+            }
+            content.push_str(&line);
+            content.push('\n');
+            fs::write(path, content).map_err(|e| e.to_string())?;
+            self.file_lines = self.events.len() + 1;
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+        self.file_lines += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn event(timestamp: u64, kind: EventKind, summary: &str) -> HistoryEvent {
+        HistoryEvent {
+            timestamp,
+            kind,
+            summary: summary.to_string(),
+            detail: serde_json::Value::Null,
+        }
+    }
+
+    fn write_events(path: &PathBuf, events: &[HistoryEvent]) {
+        let lines: Vec<String> = events
+            .iter()
+            .map(|event| serde_json::to_string(event).unwrap())
+            .collect();
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    fn summaries(events: &[HistoryEvent]) -> Vec<&str> {
+        events.iter().map(|event| event.summary.as_str()).collect()
+    }
+
+    // 时间戳 100、200、300、400 的四条记录
+    fn sample(dir: &TempDir) -> EventHistory {
+        let path = dir.path().join("event_history.jsonl");
+        write_events(
+            &path,
+            &[
+                event(100, EventKind::Window, "opened: Zoom Meeting (Zoom.exe)"),
+                event(200, EventKind::Process, "started: obs64.exe"),
+                event(300, EventKind::Device, "default: Headphones"),
+                event(400, EventKind::Window, "closed: Zoom Meeting (Zoom.exe)"),
+            ],
+        );
+        EventHistory::load(path)
+    }
+
+    #[test]
+    fn load_skips_unreadable_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("event_history.jsonl");
+        let line = serde_json::to_string(&event(100, EventKind::Rule, "fired")).unwrap();
+        fs::write(&path, format!("{{ not json\n{}\n", line)).unwrap();
+
+        let history = EventHistory::load(path);
+        assert_eq!(
+            summaries(&history.query(&HistoryFilter::default())),
+            ["fired"]
+        );
+        assert_eq!(history.file_lines, 2);
+
+        let missing = EventHistory::load(dir.path().join("missing.jsonl"));
+        assert!(missing.query(&HistoryFilter::default()).is_empty());
+    }
+
+    #[test]
+    fn filter_by_kind_and_time() {
+        let dir = TempDir::new().unwrap();
+        let history = sample(&dir);
+
+        let filter = HistoryFilter {
+            kinds: vec![EventKind::Window, EventKind::Device],
+            ..HistoryFilter::default()
+        };
+        assert_eq!(history.query(&filter).len(), 3);
+
+        // since 和 until 都包含边界
+        let filter = HistoryFilter {
+            since: Some(200),
+            until: Some(300),
+            ..HistoryFilter::default()
+        };
+        assert_eq!(
+            summaries(&history.query(&filter)),
+            ["started: obs64.exe", "default: Headphones"]
+        );
+    }
+
+    #[test]
+    fn filter_by_text_and_limit() {
+        let dir = TempDir::new().unwrap();
+        let history = sample(&dir);
+
+        let filter = HistoryFilter {
+            text: Some("ZOOM".to_string()),
+            ..HistoryFilter::default()
+        };
+        assert_eq!(history.query(&filter).len(), 2);
+        let filter = HistoryFilter {
+            text: Some(String::new()),
+            ..HistoryFilter::default()
+        };
+        assert_eq!(history.query(&filter).len(), 4);
+
+        // limit 保留最近的记录，结果仍然按时间顺序
+        let filter = HistoryFilter {
+            limit: Some(2),
+            ..HistoryFilter::default()
+        };
+        assert_eq!(
+            summaries(&history.query(&filter)),
+            ["default: Headphones", "closed: Zoom Meeting (Zoom.exe)"]
+        );
+        let filter = HistoryFilter {
+            kinds: vec![EventKind::Window],
+            limit: Some(10),
+            ..HistoryFilter::default()
+        };
+        assert_eq!(history.query(&filter).len(), 2);
+    }
+
+    #[test]
+    fn filter_fields_are_optional() {
+        let filter: HistoryFilter =
+            serde_json::from_value(json!({ "kinds": ["rule"], "limit": 5 })).unwrap();
+        assert_eq!(filter.kinds, [EventKind::Rule]);
+        assert_eq!(filter.limit, Some(5));
+        assert_eq!(filter.since, None);
+    }
+
+    #[test]
+    fn record_appends_to_the_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history").join("event_history.jsonl");
+        let mut history = EventHistory::load(path.clone());
+
+        history.record(EventKind::Rule, "fired".to_string(), json!({ "rule": 1 }));
+        history.record(
+            EventKind::Device,
+            "removed".to_string(),
+            serde_json::Value::Null,
+        );
+
+        let reloaded = EventHistory::load(path);
+        let events = reloaded.query(&HistoryFilter::default());
+        assert_eq!(summaries(&events), ["fired", "removed"]);
+        assert_eq!(events[0].detail, json!({ "rule": 1 }));
+
+        history.clear().unwrap();
+        assert!(history.query(&HistoryFilter::default()).is_empty());
+        assert!(
+            !dir.path()
+                .join("history")
+                .join("event_history.jsonl")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn rewrites_the_file_when_it_grows_too_long() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("event_history.jsonl");
+        let events: Vec<HistoryEvent> = (0..MAX_FILE_LINES)
+            .map(|i| event(i as u64, EventKind::Window, &format!("event {}", i)))
+            .collect();
+        write_events(&path, &events);
+
+        let mut history = EventHistory::load(path.clone());
+        assert_eq!(history.query(&HistoryFilter::default()).len(), MAX_EVENTS);
+        history.record(
+            EventKind::Rule,
+            "latest".to_string(),
+            serde_json::Value::Null,
+        );
+
+        // 重写后只剩内存中的记录和新的一条
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), MAX_EVENTS + 1);
+        assert!(content.starts_with(&serde_json::to_string(&events[MAX_EVENTS]).unwrap()));
+        assert_eq!(history.file_lines, MAX_EVENTS + 1);
+
+        let reloaded = EventHistory::load(path);
+        let events = reloaded.query(&HistoryFilter::default());
+        assert_eq!(events.len(), MAX_EVENTS);
+        assert_eq!(events.last().unwrap().summary, "latest");
+    }
+}
@@ -0,0 +1,450 @@
+// 焦点跟随：根据前台应用自动切换输出设备
+// 例如聚焦 DAW 时切到声卡，聚焦其他应用时切回音箱。
+use crate::audio::AudioDevice;
+use crate::window_source::{SharedWindowSource, WindowInfo};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::{Duration, Instant};
+
+// 单个应用到设备的映射
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppDeviceMapping {
+    pub process: String, // 可执行文件名，如 "Ableton Live 11 Suite.exe"，忽略大小写
+    pub device: String,  // 设备 id 或设备名称
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FocusSwitchConfig {
+    pub enabled: bool,
+    pub mappings: Vec<AppDeviceMapping>,
+    pub fallback_device: Option<String>, // 聚焦未映射的应用时使用的设备，None 表示保持不变
+    pub settle_ms: u64,                  // 焦点需要稳定多久才切换，避免快速 alt-tab 时频繁切换
+}
+
+impl Default for FocusSwitchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mappings: Vec::new(),
+            fallback_device: None,
+            settle_ms: 800,
+        }
+    }
+}
+
+impl FocusSwitchConfig {
+    // 计算聚焦某个窗口时应该使用的设备
+    pub fn target_device(&self, window: &WindowInfo) -> Option<String> {
+        self.mappings
+            .iter()
+            .find(|mapping| mapping.process.eq_ignore_ascii_case(&window.process_name))
+            .map(|mapping| mapping.device.clone())
+            .or_else(|| self.fallback_device.clone())
+    }
+}
+
+// 焦点切换的迟滞处理：焦点变化只记录待切换的目标，稳定 settle 时长后才真正切换
+#[derive(Debug)]
+pub struct FocusSwitcher {
+    settle: Duration,
+    pending: Option<(String, Instant)>,
+}
+
+impl FocusSwitcher {
+    pub fn new(settle: Duration) -> Self {
+        Self {
+            settle,
+            pending: None,
+        }
+    }
+
+    pub fn set_settle(&mut self, settle: Duration) {
+        self.settle = settle;
+    }
+
+    // 焦点变化；target 为 None 表示新的前台应用不需要切换设备。
+    // current 是当前的默认设备：用户或其他程序可能绕过焦点跟随切换过设备，
+    // 所以和实际的默认设备比较，而不是记住自己上次切到哪里
+    pub fn on_focus(
+        &mut self,
+        target: Option<String>,
+        current: Option<&AudioDevice>,
+        now: Instant,
+    ) {
+        match target {
+            // 目标已经是默认设备，取消尚未生效的切换（比如 alt-tab 出去又马上回来）
+            Some(device) if current.is_some_and(|current| current.matches(&device)) => {
+                self.pending = None
+            }
+            Some(device) => {
+                // 同一个目标重复出现时保留原来的计时
+                if self.pending.as_ref().map(|(pending, _)| pending) != Some(&device) {
+                    self.pending = Some((device, now));
+                }
+            }
+            None => self.pending = None,
+        }
+    }
+
+    // 关闭焦点跟随时丢弃尚未生效的切换
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    // 如果待切换的目标已经稳定足够久，返回它
+    pub fn poll(&mut self, now: Instant) -> Option<String> {
+        let (_, since) = self.pending.as_ref()?;
+        if now.duration_since(*since) < self.settle {
+            return None;
+        }
+
+        self.pending.take().map(|(device, _)| device)
+    }
+
+    // 下一次需要调用 poll 的时间
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|(_, since)| *since + self.settle)
+    }
+}
+
+// 启动前台窗口监视，每次前台窗口变化时发送新的窗口 id
+pub fn watch_focus(source: SharedWindowSource) -> Receiver<u64> {
+    let (sender, receiver) = channel();
+
+    #[cfg(all(target_os = "windows", feature = "win32"))]
+    {
+        let _ = source;
+        win32::spawn_foreground_hook(sender);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    {
+        use crate::logging::MONITOR;
+        use log::warn;
+
+        if let Err(e) = x11::spawn_active_window_watcher(sender.clone()) {
+            warn!(
+                target: MONITOR,
+                "Failed to watch _NET_ACTIVE_WINDOW, falling back to polling: {}",
+                e
+            );
+            spawn_polling_watcher(source, sender);
+        }
+    }
+
+    #[cfg(not(any(
+        all(target_os = "windows", feature = "win32"),
+        all(target_os = "linux", feature = "x11")
+    )))]
+    {
+        spawn_polling_watcher(source, sender);
+    }
+
+    receiver
+}
+
+// 没有事件源时退化为轮询 WindowSource::focused_window
+#[cfg_attr(all(target_os = "windows", feature = "win32"), allow(dead_code))]
+fn spawn_polling_watcher(source: SharedWindowSource, sender: Sender<u64>) {
+    std::thread::spawn(move || {
+        let mut last = None;
+        loop {
+            let focused = source.focused_window();
+            if focused != last {
+                if let Some(id) = focused {
+                    if sender.send(id).is_err() {
+                        break;
+                    }
+                }
+                last = focused;
+            }
+            std::thread::sleep(Duration::from_millis(250));
+        }
+    });
+}
+
+#[cfg(all(target_os = "windows", feature = "win32"))]
+mod win32 {
+    use crate::logging::MONITOR;
+    use log::warn;
+    use std::sync::OnceLock;
+    use std::sync::mpsc::Sender;
+    use windows::Win32::{
+        Foundation::HWND,
+        UI::{
+            Accessibility::{HWINEVENTHOOK, SetWinEventHook},
+            WindowsAndMessaging::{
+                DispatchMessageW, EVENT_SYSTEM_FOREGROUND, GetMessageW, MSG, TranslateMessage,
+                WINEVENT_OUTOFCONTEXT,
+            },
+        },
+    };
+
+    // WinEvent 回调没有用户数据参数，只能通过全局变量把事件送出去
+    static FOCUS_SENDER: OnceLock<Sender<u64>> = OnceLock::new();
+
+    unsafe extern "system" fn foreground_event_proc(
+        _hook: HWINEVENTHOOK,
+        _event: u32,
+        hwnd: HWND,
+        _id_object: i32,
+        _id_child: i32,
+        _event_thread: u32,
+        _event_time: u32,
+    ) {
+        if hwnd.0 != 0 {
+            if let Some(sender) = FOCUS_SENDER.get() {
+                let _ = sender.send(hwnd.0 as u64);
+            }
+        }
+    }
+
+    pub fn spawn_foreground_hook(sender: Sender<u64>) {
+        if FOCUS_SENDER.set(sender).is_err() {
+            warn!(target: MONITOR, "Foreground hook already installed");
+            return;
+        }
+
+        // 钩子必须安装在有消息循环的线程上
+        std::thread::spawn(|| unsafe {
+            let hook = SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                None,
+                Some(foreground_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            if hook.is_invalid() {
+                warn!(target: MONITOR, "Failed to install foreground window hook");
+                return;
+            }
+
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, HWND(0), 0, 0).as_bool() {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        });
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+mod x11 {
+    use crate::logging::MONITOR;
+    use log::warn;
+    use std::sync::mpsc::Sender;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::Event;
+    use x11rb::protocol::xproto::{AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask};
+
+    // 订阅根窗口的属性变化，_NET_ACTIVE_WINDOW 改变时读取新的活动窗口
+    pub fn spawn_active_window_watcher(sender: Sender<u64>) -> Result<(), String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+
+        let active_window = conn
+            .intern_atom(false, b"_NET_ACTIVE_WINDOW")
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?
+            .atom;
+
+        conn.change_window_attributes(
+            root,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .map_err(|e| e.to_string())?
+        .check()
+        .map_err(|e| e.to_string())?;
+
+        std::thread::spawn(move || {
+            loop {
+                let event = match conn.wait_for_event() {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!(target: MONITOR, "X11 focus watcher stopped: {}", e);
+                        break;
+                    }
+                };
+
+                let Event::PropertyNotify(notify) = event else {
+                    continue;
+                };
+                if notify.atom != active_window {
+                    continue;
+                }
+
+                let focused = conn
+                    .get_property(false, root, active_window, AtomEnum::WINDOW, 0, 1)
+                    .ok()
+                    .and_then(|cookie| cookie.reply().ok())
+                    .and_then(|reply| reply.value32().and_then(|mut values| values.next()));
+
+                if let Some(window) = focused.filter(|window| *window != 0) {
+                    if sender.send(window as u64).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTLE: Duration = Duration::from_millis(800);
+
+    fn device(name: &str) -> AudioDevice {
+        AudioDevice {
+            id: format!("{{id}}.{}", name),
+            name: name.to_string(),
+            is_default: true,
+        }
+    }
+
+    fn target(name: &str) -> Option<String> {
+        Some(name.to_string())
+    }
+
+    fn ms(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn switches_after_focus_settles() {
+        let start = Instant::now();
+        let speakers = device("Speakers");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        switcher.on_focus(target("Interface"), Some(&speakers), start);
+        assert_eq!(switcher.next_deadline(), Some(ms(start, 800)));
+        assert_eq!(switcher.poll(ms(start, 799)), None);
+        assert_eq!(switcher.poll(ms(start, 800)), target("Interface"));
+        assert_eq!(switcher.poll(ms(start, 2000)), None);
+        assert_eq!(switcher.next_deadline(), None);
+    }
+
+    #[test]
+    fn repeated_focus_keeps_the_original_timer() {
+        let start = Instant::now();
+        let speakers = device("Speakers");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        switcher.on_focus(target("Interface"), Some(&speakers), start);
+        switcher.on_focus(target("Interface"), Some(&speakers), ms(start, 500));
+        assert_eq!(switcher.poll(ms(start, 800)), target("Interface"));
+    }
+
+    #[test]
+    fn new_target_restarts_the_timer() {
+        let start = Instant::now();
+        let speakers = device("Speakers");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        switcher.on_focus(target("Interface"), Some(&speakers), start);
+        switcher.on_focus(target("Headset"), Some(&speakers), ms(start, 500));
+        assert_eq!(switcher.poll(ms(start, 800)), None);
+        assert_eq!(switcher.poll(ms(start, 1300)), target("Headset"));
+    }
+
+    #[test]
+    fn quick_alt_tab_back_cancels_the_switch() {
+        let start = Instant::now();
+        let interface = device("Interface");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        // 在 DAW 中使用声卡，短暂切到浏览器后马上回来
+        switcher.on_focus(target("Speakers"), Some(&interface), start);
+        switcher.on_focus(target("Interface"), Some(&interface), ms(start, 300));
+        assert_eq!(switcher.poll(ms(start, 1000)), None);
+        assert_eq!(switcher.next_deadline(), None);
+    }
+
+    #[test]
+    fn unmapped_focus_cancels_the_switch() {
+        let start = Instant::now();
+        let speakers = device("Speakers");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        switcher.on_focus(target("Interface"), Some(&speakers), start);
+        switcher.on_focus(None, Some(&speakers), ms(start, 300));
+        assert_eq!(switcher.poll(ms(start, 1000)), None);
+    }
+
+    #[test]
+    fn switches_again_after_an_external_change() {
+        let start = Instant::now();
+        let interface = device("Interface");
+        let speakers = device("Speakers");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        switcher.on_focus(target("Interface"), Some(&speakers), start);
+        assert_eq!(switcher.poll(ms(start, 800)), target("Interface"));
+
+        // 用户随后在系统设置里切回了音箱，再次聚焦 DAW 时仍然需要切换
+        switcher.on_focus(target("Interface"), Some(&speakers), ms(start, 5000));
+        assert_eq!(switcher.poll(ms(start, 5800)), target("Interface"));
+
+        // 已经是默认设备时不再切换
+        switcher.on_focus(target("Interface"), Some(&interface), ms(start, 9000));
+        assert_eq!(switcher.poll(ms(start, 9800)), None);
+    }
+
+    #[test]
+    fn matches_current_device_by_id() {
+        let start = Instant::now();
+        let interface = device("Interface");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        switcher.on_focus(Some(interface.id.clone()), Some(&interface), start);
+        assert_eq!(switcher.poll(ms(start, 800)), None);
+    }
+
+    #[test]
+    fn cancel_drops_the_pending_switch() {
+        let start = Instant::now();
+        let speakers = device("Speakers");
+        let mut switcher = FocusSwitcher::new(SETTLE);
+
+        switcher.on_focus(target("Interface"), Some(&speakers), start);
+        switcher.cancel();
+        assert_eq!(switcher.poll(ms(start, 800)), None);
+    }
+
+    #[test]
+    fn target_device_uses_mapping_then_fallback() {
+        let config = FocusSwitchConfig {
+            enabled: true,
+            mappings: vec![AppDeviceMapping {
+                process: "Ableton Live 11 Suite.exe".to_string(),
+                device: "Interface".to_string(),
+            }],
+            fallback_device: Some("Speakers".to_string()),
+            settle_ms: 800,
+        };
+        let window = |process_name: &str| WindowInfo {
+            id: 1,
+            title: String::new(),
+            class: String::new(),
+            pid: 1,
+            process_name: process_name.to_string(),
+        };
+
+        assert_eq!(
+            config.target_device(&window("ableton live 11 suite.exe")),
+            target("Interface")
+        );
+        assert_eq!(
+            config.target_device(&window("firefox.exe")),
+            target("Speakers")
+        );
+    }
+}
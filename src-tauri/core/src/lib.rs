@@ -0,0 +1,20 @@
+// AudioCat 核心库：音频设备的列出和切换、配置文件、规则引擎和各平台的窗口/进程来源，
+// 不依赖 Tauri 和 webview，托盘应用和其他工具都基于它。
+// 平台后端由 Cargo 特性选择：wasapi（Windows 音频）、win32（Windows 窗口/进程/焦点）、
+// x11（Linux 窗口/焦点）；没有启用的后端退化为返回错误或脚本数据的实现。
+// local-socket 特性提供托盘应用和命令行工具共用的本地套接字。
+pub mod audio;
+pub mod config;
+pub mod error;
+pub mod event_history;
+pub mod focus_switch;
+#[cfg(feature = "local-socket")]
+pub mod local_socket;
+pub mod logging;
+pub mod monitor;
+pub mod process_monitor;
+pub mod rules;
+pub mod schedule;
+pub mod switch_history;
+pub mod window_picker;
+pub mod window_source;
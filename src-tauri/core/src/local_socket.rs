@@ -0,0 +1,134 @@
+// 每个用户自己的本地套接字：Windows 上是只允许当前用户访问的命名管道，其他平台是 XDG_RUNTIME_DIR
+// （没有时是临时目录下只有自己能访问的子目录）中权限为 0600 的 Unix 套接字文件。
+// 单实例和本地控制接口各用一个，kind 区分用途；命令行工具用 connect 连接正在运行的托盘应用。
+use crate::config::APP_IDENTIFIER;
+use interprocess::local_socket::{Listener, ListenerOptions, Name, Stream, prelude::*};
+use std::io;
+
+#[cfg(windows)]
+use interprocess::os::windows::security_descriptor::SecurityDescriptor;
+
+fn name(kind: &str) -> io::Result<Name<'static>> {
+    let user = std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "default".to_string());
+    let file_name = format!("{}.{}.{}.sock", APP_IDENTIFIER, user, kind);
+
+    #[cfg(windows)]
+    {
+        use interprocess::local_socket::GenericNamespaced;
+        file_name.to_ns_name::<GenericNamespaced>()
+    }
+    #[cfg(not(windows))]
+    {
+        use interprocess::local_socket::GenericFilePath;
+        socket_dir()?
+            .join(file_name)
+            .to_fs_name::<GenericFilePath>()
+    }
+}
+
+// XDG_RUNTIME_DIR 按规范只有本用户可以访问；临时目录所有用户都能写，在里面建一个私有的子目录
+#[cfg(not(windows))]
+fn socket_dir() -> io::Result<std::path::PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(dir.into());
+    }
+
+    let uid = unsafe { libc::getuid() };
+    let dir = std::env::temp_dir().join(format!("{}-{}", APP_IDENTIFIER, uid));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    // 目录可能是其他用户事先建好的，不跟随符号链接，检查所有者和权限
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+// 只允许当前用户访问的安全描述符，默认的命名管道权限允许所有用户读取
+#[cfg(windows)]
+fn current_user_only() -> io::Result<SecurityDescriptor> {
+    use widestring::U16CString;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, HLOCAL, LocalFree};
+    use windows::Win32::Security::Authorization::ConvertSidToStringSidW;
+    use windows::Win32::Security::{GetTokenInformation, TOKEN_QUERY, TOKEN_USER, TokenUser};
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+    use windows::core::PWSTR;
+
+    let sid = unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)?;
+        let mut size = 0;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut size);
+        let mut buffer = vec![0u8; size as usize];
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr().cast()),
+            size,
+            &mut size,
+        );
+        let _ = CloseHandle(token);
+        result?;
+
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+        let mut sid = PWSTR::null();
+        ConvertSidToStringSidW(user.User.Sid, &mut sid)?;
+        let string = sid.to_string();
+        let _ = LocalFree(HLOCAL(sid.0.cast()));
+        string.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+    };
+
+    // 受保护的 DACL，只有一条允许当前用户完全访问的项
+    let sddl = U16CString::from_str(format!("D:P(A;;GA;;;{})", sid))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    SecurityDescriptor::deserialize(&sddl)
+}
+
+fn create(kind: &str, overwrite: bool) -> io::Result<Listener> {
+    let options = || -> io::Result<ListenerOptions<'static>> {
+        Ok(ListenerOptions::new()
+            .name(name(kind)?)
+            .try_overwrite(overwrite))
+    };
+
+    #[cfg(windows)]
+    {
+        use interprocess::os::windows::local_socket::ListenerOptionsExt;
+        options()?
+            .security_descriptor(current_user_only()?)
+            .create_sync()
+    }
+    #[cfg(not(windows))]
+    {
+        use interprocess::os::unix::local_socket::ListenerOptionsExt;
+        // 不支持设置套接字权限的平台（如 macOS）只依靠所在目录的权限
+        match options()?.mode(0o600).create_sync() {
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => options()?.create_sync(),
+            result => result,
+        }
+    }
+}
+
+pub fn connect(kind: &str) -> io::Result<Stream> {
+    Stream::connect(name(kind)?)
+}
+
+// 开始监听；上次异常退出留下的套接字文件连不上时覆盖它，有实例在监听时返回 AddrInUse
+pub fn listen(kind: &str) -> io::Result<Listener> {
+    match create(kind, false) {
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse && connect(kind).is_err() => {
+            create(kind, true)
+        }
+        result => result,
+    }
+}
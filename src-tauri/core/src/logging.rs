@@ -0,0 +1,289 @@
+// 日志：按级别和模块（target）记录到应用数据目录下的滚动日志文件，debug 版本同时输出到控制台。
+// release 版本没有控制台窗口，排查问题时看日志文件或偏好设置"关于"页的日志。
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// 日志 target，按功能划分
+pub const AUDIO: &str = "audio"; // 音频设备枚举、切换、音量
+pub const CONFIG: &str = "config"; // 配置文件和窗口位置的读写
+pub const MONITOR: &str = "monitor"; // 窗口/进程/设备监听、规则、定时、自动隐藏
+pub const TRAY: &str = "tray"; // 托盘菜单、主窗口和应用生命周期
+pub const API: &str = "api"; // 本地控制接口、HTTP 接口等外部控制
+
+const TARGETS: [&str; 5] = [AUDIO, CONFIG, MONITOR, TRAY, API];
+
+const LOG_FILE_NAME: &str = "audiocat.log";
+// 单个日志文件的大小上限，超过后滚动为 audiocat.log.1、audiocat.log.2 ...
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+// 保留的旧日志文件数量
+const KEEP_FILES: usize = 3;
+
+pub const DEFAULT_LEVEL: &str = "info";
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.size + line.len() as u64 + 1 > MAX_FILE_BYTES && self.size > 0 {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    // audiocat.log.2 -> .3，.1 -> .2，当前文件 -> .1，再新建当前文件
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for index in (1..KEEP_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        *self = LogFile::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+struct Logger {
+    file: Mutex<Option<LogFile>>,
+    console: AtomicBool, // 是否同时输出到 stderr
+}
+
+static LOGGER: Logger = Logger {
+    file: Mutex::new(None),
+    console: AtomicBool::new(cfg!(debug_assertions)),
+};
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // 依赖库（tauri、tao 等）的日志只记录警告和错误
+        if TARGETS.contains(&metadata.target()) {
+            metadata.level() <= log::max_level()
+        } else {
+            metadata.level() <= Level::Warn
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} [{}] {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if self.console.load(Ordering::Relaxed) {
+            eprintln!("{}", line);
+        }
+
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(log_file) = file.as_mut() {
+                if log_file.write_line(&line).is_err() {
+                    // 写入失败（比如磁盘满了）时不再尝试，避免每条日志都报错
+                    *file = None;
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(log_file) = file.as_mut() {
+                let _ = log_file.file.flush();
+            }
+        }
+    }
+}
+
+// 安装全局 logger，此时还没有日志文件；应用启动后再调用 attach_file 写入文件
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+}
+
+// 是否输出到控制台；debug 版本默认输出，release 版本只在需要时（比如命令行报错）打开
+pub fn set_console(enabled: bool) {
+    LOGGER.console.store(enabled, Ordering::Relaxed);
+}
+
+// 开始写入 dir 下的日志文件
+pub fn attach_file(dir: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create log directory: {}", e))?;
+    let path = dir.join(LOG_FILE_NAME);
+    let log_file = LogFile::open(path.clone())
+        .map_err(|e| format!("Failed to open log file {}: {}", path.display(), e))?;
+
+    let mut file = LOGGER
+        .file
+        .lock()
+        .map_err(|_| "Logger poisoned".to_string())?;
+    *file = Some(log_file);
+    Ok(path)
+}
+
+// 解析级别名称（off、error、warn、info、debug、trace，不区分大小写）
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level: {}", level))
+}
+
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+pub fn current_level() -> String {
+    log::max_level().as_str().to_lowercase()
+}
+
+// 读取最近的 count 行日志，当前文件不够时从上一个滚动文件补足
+pub fn tail(count: usize) -> Result<Vec<String>, String> {
+    let path = match LOGGER.file.lock() {
+        Ok(file) => match file.as_ref() {
+            Some(log_file) => log_file.path.clone(),
+            None => return Ok(Vec::new()),
+        },
+        Err(_) => return Err("Logger poisoned".to_string()),
+    };
+    tail_file(&path, count)
+}
+
+fn tail_file(path: &Path, count: usize) -> Result<Vec<String>, String> {
+    let mut lines = read_lines(path)?;
+    if lines.len() < count {
+        let rotated = rotated_path(path, 1);
+        if rotated.exists() {
+            let mut older = read_lines(&rotated)?;
+            older.append(&mut lines);
+            lines = older;
+        }
+    }
+
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines.split_off(skip))
+}
+
+fn read_lines(path: &Path) -> Result<Vec<String>, String> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(|line| line.to_string())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read log file: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // 两行就超过 MAX_FILE_BYTES，每写一行都会滚动
+    fn long_line(fill: char) -> String {
+        fill.to_string().repeat(MAX_FILE_BYTES as usize * 3 / 5)
+    }
+
+    fn first_char(path: &Path) -> Option<char> {
+        fs::read_to_string(path).ok()?.chars().next()
+    }
+
+    #[test]
+    fn rotates_and_keeps_the_newest_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        let mut log_file = LogFile::open(path.clone()).unwrap();
+
+        // 最早的 a 在第四次滚动时被丢弃
+        for fill in ['a', 'b', 'c', 'd', 'e'] {
+            log_file.write_line(&long_line(fill)).unwrap();
+        }
+
+        assert_eq!(first_char(&path), Some('e'));
+        assert_eq!(first_char(&rotated_path(&path, 1)), Some('d'));
+        assert_eq!(first_char(&rotated_path(&path, 2)), Some('c'));
+        assert_eq!(first_char(&rotated_path(&path, KEEP_FILES)), Some('b'));
+        assert!(!rotated_path(&path, KEEP_FILES + 1).exists());
+    }
+
+    #[test]
+    fn reopening_continues_the_size_count() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        LogFile::open(path.clone())
+            .unwrap()
+            .write_line(&long_line('a'))
+            .unwrap();
+
+        let mut log_file = LogFile::open(path.clone()).unwrap();
+        log_file.write_line(&long_line('b')).unwrap();
+        assert_eq!(first_char(&rotated_path(&path, 1)), Some('a'));
+        assert_eq!(first_char(&path), Some('b'));
+    }
+
+    #[test]
+    fn tail_fills_up_from_the_rotated_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        fs::write(rotated_path(&path, 1), "one\ntwo\nthree\n").unwrap();
+        fs::write(&path, "four\nfive\n").unwrap();
+
+        assert_eq!(tail_file(&path, 1).unwrap(), ["five"]);
+        assert_eq!(tail_file(&path, 2).unwrap(), ["four", "five"]);
+        assert_eq!(
+            tail_file(&path, 4).unwrap(),
+            ["two", "three", "four", "five"]
+        );
+        assert_eq!(tail_file(&path, 10).unwrap().len(), 5);
+        assert!(tail_file(&path, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tail_of_missing_files_is_empty() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(LOG_FILE_NAME);
+        assert!(tail_file(&path, 10).unwrap().is_empty());
+
+        // 只有当前文件时不需要滚动文件
+        fs::write(&path, "only\n").unwrap();
+        assert_eq!(tail_file(&path, 10).unwrap(), ["only"]);
+    }
+
+    #[test]
+    fn parses_level_names() {
+        assert_eq!(parse_level("Debug"), Ok(LevelFilter::Debug));
+        assert_eq!(parse_level(" off "), Ok(LevelFilter::Off));
+        assert_eq!(
+            parse_level("loud"),
+            Err("Unknown log level: loud".to_string())
+        );
+    }
+}
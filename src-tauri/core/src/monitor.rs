@@ -0,0 +1,605 @@
+// 窗口监听规则：每条规则可以按可执行文件名、窗口类名、标题子串、通配符或正则匹配窗口
+use crate::window_source::WindowInfo;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+
+// 规则的匹配方式，以 {"type": "...", "value": "..."} 的形式保存在配置文件中
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum WindowMatcher {
+    ExactTitle(String),                   // 标题完全相等（旧版配置迁移后的默认方式）
+    Substring(String),                    // 标题包含子串
+    Glob(CompiledPattern<glob::Pattern>), // 标题匹配通配符，如 "* - Visual Studio Code"
+    Regex(CompiledPattern<regex::Regex>), // 标题匹配正则表达式
+    Process(String),                      // 可执行文件名，如 "obs64.exe"，忽略大小写
+    Class(String),                        // 窗口类名，忽略大小写
+}
+
+// 能从源文本编译的匹配模式
+pub trait Compile: Sized {
+    fn compile(source: &str) -> Result<Self, String>;
+}
+
+impl Compile for glob::Pattern {
+    fn compile(source: &str) -> Result<Self, String> {
+        glob::Pattern::new(source).map_err(|e| format!("Invalid glob pattern '{}': {}", source, e))
+    }
+}
+
+impl Compile for regex::Regex {
+    fn compile(source: &str) -> Result<Self, String> {
+        regex::Regex::new(source).map_err(|e| format!("Invalid regex '{}': {}", source, e))
+    }
+}
+
+// 通配符和正则在创建（包括从配置反序列化）时编译一次，之后每次轮询都复用编译结果。
+// 比较、序列化和调试输出只看源文本；编译失败的模式保留错误，由 validate 报告，匹配时视为不匹配
+#[derive(Clone)]
+pub struct CompiledPattern<T> {
+    source: String,
+    compiled: Result<T, String>,
+}
+
+impl<T: Compile> CompiledPattern<T> {
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let compiled = T::compile(&source);
+        Self { source, compiled }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn compiled(&self) -> Result<&T, &str> {
+        self.compiled.as_ref().map_err(String::as_str)
+    }
+}
+
+impl<T> PartialEq for CompiledPattern<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl<T> Eq for CompiledPattern<T> {}
+
+impl<T> fmt::Debug for CompiledPattern<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl<T> Serialize for CompiledPattern<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.source.serialize(serializer)
+    }
+}
+
+impl<'de, T: Compile> Deserialize<'de> for CompiledPattern<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+// 规则在什么窗口事件上触发
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerEvent {
+    Opened,
+    #[default]
+    Closed, // 旧版只支持窗口关闭
+    Focused,
+}
+
+// 规则触发后执行的动作
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", content = "device", rename_all = "snake_case")]
+pub enum MonitorAction {
+    #[default]
+    ShowSwitcher, // 置顶显示切换器（旧版行为）
+    SwitchTo(String), // 切换到指定设备，可以是设备 id 或设备名称
+    #[serde(alias = "switch_back")]
+    Revert, // 恢复到切换历史中上一个仍然存在的设备
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "MonitorRuleRepr")]
+pub struct MonitorRule {
+    pub matcher: WindowMatcher,
+    pub event: TriggerEvent,
+    pub action: MonitorAction,
+}
+
+// 旧版配置中 monitored_windows 是字符串数组，这里同时接受字符串和新的规则对象
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MonitorRuleRepr {
+    Legacy(String),
+    Rule {
+        matcher: WindowMatcher,
+        #[serde(default)]
+        event: TriggerEvent,
+        #[serde(default)]
+        action: MonitorAction,
+    },
+}
+
+impl From<MonitorRuleRepr> for MonitorRule {
+    fn from(repr: MonitorRuleRepr) -> Self {
+        match repr {
+            MonitorRuleRepr::Legacy(title) => MonitorRule {
+                matcher: WindowMatcher::ExactTitle(title),
+                event: TriggerEvent::Closed,
+                action: MonitorAction::ShowSwitcher,
+            },
+            MonitorRuleRepr::Rule {
+                matcher,
+                event,
+                action,
+            } => MonitorRule {
+                matcher,
+                event,
+                action,
+            },
+        }
+    }
+}
+
+impl WindowMatcher {
+    // 检查规则本身是否合法（比如正则和通配符能否编译）
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            WindowMatcher::Glob(pattern) => pattern.compiled().map(|_| ()).map_err(str::to_string),
+            WindowMatcher::Regex(pattern) => pattern.compiled().map(|_| ()).map_err(str::to_string),
+            WindowMatcher::ExactTitle(value)
+            | WindowMatcher::Substring(value)
+            | WindowMatcher::Process(value)
+            | WindowMatcher::Class(value) => {
+                if value.trim().is_empty() {
+                    Err("Monitor rule pattern must not be empty".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        match self {
+            WindowMatcher::ExactTitle(title) => window.title == *title,
+            WindowMatcher::Substring(needle) => window.title.contains(needle.as_str()),
+            WindowMatcher::Glob(pattern) => pattern
+                .compiled()
+                .is_ok_and(|pattern| pattern.matches(&window.title)),
+            WindowMatcher::Regex(pattern) => pattern
+                .compiled()
+                .is_ok_and(|re| re.is_match(&window.title)),
+            WindowMatcher::Process(name) => window.process_name.eq_ignore_ascii_case(name),
+            WindowMatcher::Class(class) => window.class.eq_ignore_ascii_case(class),
+        }
+    }
+}
+
+impl MonitorRule {
+    // 用于命令行列出规则
+    pub fn describe(&self) -> String {
+        let action = match &self.action {
+            MonitorAction::ShowSwitcher => "show switcher".to_string(),
+            MonitorAction::SwitchTo(device) => format!("switch to {}", device),
+            MonitorAction::Revert => "revert device".to_string(),
+        };
+        format!("window {:?} {:?} -> {}", self.matcher, self.event, action)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.matcher.validate()?;
+        if let MonitorAction::SwitchTo(device) = &self.action {
+            if device.trim().is_empty() {
+                return Err("Target device must not be empty".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TriggerEvent {
+    // 窗口事件对应的触发时机，标题变化不触发任何规则
+    pub fn of(event: &WindowEvent) -> Option<TriggerEvent> {
+        match event {
+            WindowEvent::Opened { .. } => Some(TriggerEvent::Opened),
+            WindowEvent::Closed { .. } => Some(TriggerEvent::Closed),
+            WindowEvent::Focused { .. } => Some(TriggerEvent::Focused),
+            WindowEvent::TitleChanged { .. } => None,
+        }
+    }
+}
+
+// 窗口实例的唯一标识：窗口句柄（或 X11 窗口 id）加上所属进程 id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowKey {
+    pub id: u64,
+    pub pid: u32,
+}
+
+impl From<&WindowInfo> for WindowKey {
+    fn from(window: &WindowInfo) -> Self {
+        WindowKey {
+            id: window.id,
+            pid: window.pid,
+        }
+    }
+}
+
+// 监听到的窗口变化
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WindowEvent {
+    // 出现了新的窗口实例
+    Opened {
+        window: WindowInfo,
+    },
+    // 窗口实例真正消失（句柄 + pid 不再存在）
+    Closed {
+        window: WindowInfo,
+    },
+    // 同一个窗口实例只是改了标题，比如浏览器切换标签页
+    TitleChanged {
+        window: WindowInfo,
+        old_title: String,
+    },
+    // 窗口成为前台窗口
+    Focused {
+        window: WindowInfo,
+    },
+}
+
+impl WindowEvent {
+    pub fn window(&self) -> &WindowInfo {
+        match self {
+            WindowEvent::Opened { window }
+            | WindowEvent::Closed { window }
+            | WindowEvent::TitleChanged { window, .. }
+            | WindowEvent::Focused { window } => window,
+        }
+    }
+
+    // 用于事件历史的一行描述
+    pub fn describe(&self) -> String {
+        let window = self.window();
+        let target = if window.process_name.is_empty() {
+            window.title.clone()
+        } else {
+            format!("{} ({})", window.title, window.process_name)
+        };
+        match self {
+            WindowEvent::Opened { .. } => format!("opened: {}", target),
+            WindowEvent::Closed { .. } => format!("closed: {}", target),
+            WindowEvent::TitleChanged { old_title, .. } => {
+                format!("title changed: {} -> {}", old_title, target)
+            }
+            WindowEvent::Focused { .. } => format!("focused: {}", target),
+        }
+    }
+}
+
+// 按窗口实例（而不是标题）跟踪上次看到的窗口
+#[derive(Debug, Default)]
+pub struct WindowTracker {
+    windows: HashMap<WindowKey, WindowInfo>,
+    focused: Option<WindowKey>,
+}
+
+impl WindowTracker {
+    // 用当前窗口列表重置，不产生任何事件
+    pub fn reset(&mut self, windows: Vec<WindowInfo>, focused_id: Option<u64>) {
+        self.focused = focused_id
+            .and_then(|id| windows.iter().find(|w| w.id == id))
+            .map(WindowKey::from);
+        self.windows = windows
+            .into_iter()
+            .map(|w| (WindowKey::from(&w), w))
+            .collect();
+    }
+
+    // 与上一次的快照比较，返回匹配任意一个 matcher 的窗口的变化，并更新快照
+    pub fn update(
+        &mut self,
+        current: Vec<WindowInfo>,
+        focused_id: Option<u64>,
+        matchers: &[WindowMatcher],
+    ) -> Vec<WindowEvent> {
+        let current: HashMap<WindowKey, WindowInfo> = current
+            .into_iter()
+            .map(|w| (WindowKey::from(&w), w))
+            .collect();
+        let is_monitored =
+            |window: &WindowInfo| matchers.iter().any(|matcher| matcher.matches(window));
+
+        let mut events = Vec::new();
+
+        for (key, previous) in &self.windows {
+            match current.get(key) {
+                None => {
+                    if is_monitored(previous) {
+                        events.push(WindowEvent::Closed {
+                            window: previous.clone(),
+                        });
+                    }
+                }
+                Some(window) if window.title != previous.title => {
+                    if is_monitored(previous) || is_monitored(window) {
+                        events.push(WindowEvent::TitleChanged {
+                            window: window.clone(),
+                            old_title: previous.title.clone(),
+                        });
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (key, window) in &current {
+            if !self.windows.contains_key(key) && is_monitored(window) {
+                events.push(WindowEvent::Opened {
+                    window: window.clone(),
+                });
+            }
+        }
+
+        // 焦点切换到另一个窗口实例时才上报，一直停留在同一窗口不重复触发
+        let focused = focused_id
+            .and_then(|id| current.values().find(|w| w.id == id))
+            .map(|w| (WindowKey::from(w), w));
+        if let Some((key, window)) = focused {
+            if self.focused != Some(key) && is_monitored(window) {
+                events.push(WindowEvent::Focused {
+                    window: window.clone(),
+                });
+            }
+        }
+        self.focused = focused.map(|(key, _)| key);
+
+        self.windows = current;
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(title: &str) -> WindowInfo {
+        WindowInfo {
+            id: 1,
+            title: title.to_string(),
+            class: String::new(),
+            pid: 1,
+            process_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn patterns_round_trip_as_strings() {
+        let json = r#"{"type":"glob","value":"* - Visual Studio Code"}"#;
+        let matcher: WindowMatcher = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            matcher,
+            WindowMatcher::Glob(CompiledPattern::new("* - Visual Studio Code"))
+        );
+        assert_eq!(serde_json::to_string(&matcher).unwrap(), json);
+        assert!(matcher.matches(&window("main.rs - Visual Studio Code")));
+        assert!(!matcher.matches(&window("Visual Studio Code")));
+    }
+
+    #[test]
+    fn invalid_patterns_fail_validation_and_never_match() {
+        let matcher: WindowMatcher =
+            serde_json::from_str(r#"{"type":"regex","value":"(unclosed"}"#).unwrap();
+        assert!(matcher.validate().unwrap_err().starts_with("Invalid regex"));
+        assert!(!matcher.matches(&window("(unclosed")));
+    }
+
+    #[test]
+    fn cloned_matchers_keep_the_compiled_pattern() {
+        let matcher = WindowMatcher::Regex(CompiledPattern::new(r"^Zoom( Meeting)?$"));
+        let cloned = matcher.clone();
+        assert!(cloned.matches(&window("Zoom Meeting")));
+        assert!(!cloned.matches(&window("Zoom Workplace")));
+    }
+
+    // 旧版 window_config.json：monitored_windows 是窗口标题的数组
+    #[test]
+    fn legacy_title_lists_migrate_to_exact_title_rules() {
+        let json = r#"{
+            "monitored_windows": ["Zoom Meeting", "OBS 30.1.2"],
+            "auto_hide_delay": 3,
+            "main_window_position": null,
+            "preference_window_position": null,
+            "dark_mode": false
+        }"#;
+        let config: crate::config::WindowConfig = serde_json::from_str(json).unwrap();
+        let legacy = |title: &str| MonitorRule {
+            matcher: WindowMatcher::ExactTitle(title.to_string()),
+            event: TriggerEvent::Closed,
+            action: MonitorAction::ShowSwitcher,
+        };
+        assert_eq!(
+            config.monitored_windows,
+            vec![legacy("Zoom Meeting"), legacy("OBS 30.1.2")]
+        );
+
+        // 保存后是新格式，再读一次结果不变
+        let saved = serde_json::to_string(&config.monitored_windows).unwrap();
+        assert!(saved.contains(r#"{"type":"exact_title","value":"Zoom Meeting"}"#));
+        let reloaded: Vec<MonitorRule> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(reloaded, config.monitored_windows);
+    }
+
+    #[test]
+    fn legacy_strings_and_rule_objects_can_be_mixed() {
+        let json = r#"[
+            "Zoom Meeting",
+            {"matcher": {"type": "process", "value": "obs64.exe"}},
+            {
+                "matcher": {"type": "regex", "value": "^Teams"},
+                "event": "opened",
+                "action": {"type": "switch_to", "device": "Headphones"}
+            }
+        ]"#;
+        let rules: Vec<MonitorRule> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                MonitorRule {
+                    matcher: WindowMatcher::ExactTitle("Zoom Meeting".to_string()),
+                    event: TriggerEvent::Closed,
+                    action: MonitorAction::ShowSwitcher,
+                },
+                MonitorRule {
+                    matcher: WindowMatcher::Process("obs64.exe".to_string()),
+                    event: TriggerEvent::Closed,
+                    action: MonitorAction::ShowSwitcher,
+                },
+                MonitorRule {
+                    matcher: WindowMatcher::Regex(CompiledPattern::new("^Teams")),
+                    event: TriggerEvent::Opened,
+                    action: MonitorAction::SwitchTo("Headphones".to_string()),
+                },
+            ]
+        );
+    }
+
+    fn instance(id: u64, pid: u32, title: &str) -> WindowInfo {
+        WindowInfo {
+            id,
+            pid,
+            ..window(title)
+        }
+    }
+
+    fn zoom() -> Vec<WindowMatcher> {
+        vec![WindowMatcher::Glob(CompiledPattern::new("Zoom*"))]
+    }
+
+    // 同一次比较里的事件顺序取决于 HashMap 遍历顺序
+    fn sorted(mut events: Vec<WindowEvent>) -> Vec<WindowEvent> {
+        events.sort_by_key(|event| (event.window().id, event.describe()));
+        events
+    }
+
+    #[test]
+    fn tracker_keeps_same_titled_windows_apart() {
+        let mut tracker = WindowTracker::default();
+        tracker.reset(vec![instance(1, 10, "Zoom Meeting")], None);
+
+        // 第二个同名窗口是新实例
+        let events = tracker.update(
+            vec![
+                instance(1, 10, "Zoom Meeting"),
+                instance(2, 20, "Zoom Meeting"),
+            ],
+            None,
+            &zoom(),
+        );
+        assert_eq!(
+            events,
+            vec![WindowEvent::Opened {
+                window: instance(2, 20, "Zoom Meeting")
+            }]
+        );
+
+        // 关掉其中一个，另一个同名窗口还在，只报告关掉的那个
+        let events = tracker.update(vec![instance(2, 20, "Zoom Meeting")], None, &zoom());
+        assert_eq!(
+            events,
+            vec![WindowEvent::Closed {
+                window: instance(1, 10, "Zoom Meeting")
+            }]
+        );
+    }
+
+    #[test]
+    fn tracker_reports_title_changes_instead_of_close() {
+        let mut tracker = WindowTracker::default();
+        tracker.reset(vec![instance(1, 10, "Zoom")], None);
+
+        let events = tracker.update(vec![instance(1, 10, "Zoom Meeting")], None, &zoom());
+        assert_eq!(
+            events,
+            vec![WindowEvent::TitleChanged {
+                window: instance(1, 10, "Zoom Meeting"),
+                old_title: "Zoom".to_string(),
+            }]
+        );
+
+        // 改成不匹配的标题也只是改标题，窗口还在
+        let events = tracker.update(vec![instance(1, 10, "Settings")], None, &zoom());
+        assert_eq!(
+            events,
+            vec![WindowEvent::TitleChanged {
+                window: instance(1, 10, "Settings"),
+                old_title: "Zoom Meeting".to_string(),
+            }]
+        );
+        assert!(
+            tracker
+                .update(vec![instance(1, 10, "Settings")], None, &zoom())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn tracker_closes_only_when_id_and_pid_disappear() {
+        let mut tracker = WindowTracker::default();
+        tracker.reset(vec![instance(1, 10, "Zoom Meeting")], None);
+
+        // 窗口 id 被另一个进程复用，算作旧窗口关闭、新窗口打开
+        let events = tracker.update(vec![instance(1, 11, "Zoom Meeting")], None, &zoom());
+        assert_eq!(
+            sorted(events),
+            vec![
+                WindowEvent::Closed {
+                    window: instance(1, 10, "Zoom Meeting")
+                },
+                WindowEvent::Opened {
+                    window: instance(1, 11, "Zoom Meeting")
+                },
+            ]
+        );
+
+        // 不匹配的窗口关闭不上报
+        tracker.reset(
+            vec![instance(1, 11, "Zoom Meeting"), instance(2, 20, "Notepad")],
+            None,
+        );
+        assert!(
+            tracker
+                .update(vec![instance(1, 11, "Zoom Meeting")], None, &zoom())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn tracker_reports_focus_once_per_instance() {
+        let mut tracker = WindowTracker::default();
+        let windows = vec![
+            instance(1, 10, "Zoom Meeting"),
+            instance(2, 20, "Zoom Chat"),
+        ];
+        tracker.reset(windows.clone(), Some(1));
+
+        assert!(tracker.update(windows.clone(), Some(1), &zoom()).is_empty());
+        assert_eq!(
+            tracker.update(windows.clone(), Some(2), &zoom()),
+            vec![WindowEvent::Focused {
+                window: instance(2, 20, "Zoom Chat")
+            }]
+        );
+        assert!(tracker.update(windows.clone(), Some(2), &zoom()).is_empty());
+        // 焦点离开后再回来会再次上报
+        assert!(tracker.update(windows.clone(), None, &zoom()).is_empty());
+        assert_eq!(tracker.update(windows, Some(2), &zoom()).len(), 1);
+    }
+}
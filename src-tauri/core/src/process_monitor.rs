@@ -0,0 +1,411 @@
+// 进程监听：很多程序（游戏启动器、无界面的 OBS、后台通话客户端）没有稳定的可见窗口，
+// 这里直接扫描进程表，在指定可执行文件启动或退出时执行与窗口监听相同的动作。
+use crate::monitor::MonitorAction;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String, // 可执行文件名，如 "obs64.exe"
+}
+
+pub trait ProcessSource: Send + Sync {
+    fn list_processes(&self) -> Result<Vec<ProcessInfo>, String>;
+}
+
+pub type SharedProcessSource = Arc<dyn ProcessSource>;
+
+pub fn default_process_source() -> SharedProcessSource {
+    #[cfg(all(target_os = "windows", feature = "win32"))]
+    {
+        Arc::new(ToolhelpProcessSource)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Arc::new(ProcfsProcessSource)
+    }
+
+    #[cfg(not(any(all(target_os = "windows", feature = "win32"), target_os = "linux")))]
+    {
+        Arc::new(ScriptedProcessSource::default())
+    }
+}
+
+// 进程在什么时候触发规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessTrigger {
+    #[default]
+    Started,
+    Exited,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessRule {
+    pub process: String, // 可执行文件名，忽略大小写
+    #[serde(default)]
+    pub event: ProcessTrigger,
+    #[serde(default)]
+    pub action: MonitorAction,
+}
+
+impl ProcessRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.process.trim().is_empty() {
+            return Err("Process name must not be empty".to_string());
+        }
+        if let MonitorAction::SwitchTo(device) = &self.action {
+            if device.trim().is_empty() {
+                return Err("Target device must not be empty".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProcessEvent {
+    Started { process: ProcessInfo },
+    Exited { process: ProcessInfo },
+}
+
+impl ProcessEvent {
+    pub fn trigger(&self) -> ProcessTrigger {
+        match self {
+            ProcessEvent::Started { .. } => ProcessTrigger::Started,
+            ProcessEvent::Exited { .. } => ProcessTrigger::Exited,
+        }
+    }
+
+    pub fn process(&self) -> &ProcessInfo {
+        match self {
+            ProcessEvent::Started { process } | ProcessEvent::Exited { process } => process,
+        }
+    }
+
+    // 用于事件历史的一行描述
+    pub fn describe(&self) -> String {
+        let process = self.process();
+        match self {
+            ProcessEvent::Started { .. } => format!("started: {} ({})", process.name, process.pid),
+            ProcessEvent::Exited { .. } => format!("exited: {} ({})", process.name, process.pid),
+        }
+    }
+}
+
+// 按 pid 跟踪上次看到的进程，只关心规则里出现的可执行文件（忽略大小写）
+#[derive(Debug, Default)]
+pub struct ProcessTracker {
+    processes: HashMap<u32, ProcessInfo>,
+    initialized: bool,
+}
+
+impl ProcessTracker {
+    // 第一次调用只记录快照，不把已经在运行的进程当成"启动"
+    pub fn update(&mut self, current: Vec<ProcessInfo>, names: &[String]) -> Vec<ProcessEvent> {
+        let current: HashMap<u32, ProcessInfo> = current
+            .into_iter()
+            .filter(|process| {
+                names
+                    .iter()
+                    .any(|name| process.name.eq_ignore_ascii_case(name))
+            })
+            .map(|process| (process.pid, process))
+            .collect();
+
+        let mut events = Vec::new();
+        if self.initialized {
+            for (pid, process) in &self.processes {
+                // pid 被复用成其他程序时也算作退出
+                if current.get(pid).map(|p| &p.name) != Some(&process.name) {
+                    events.push(ProcessEvent::Exited {
+                        process: process.clone(),
+                    });
+                }
+            }
+            for (pid, process) in &current {
+                if self.processes.get(pid).map(|p| &p.name) != Some(&process.name) {
+                    events.push(ProcessEvent::Started {
+                        process: process.clone(),
+                    });
+                }
+            }
+        }
+
+        self.processes = current;
+        self.initialized = true;
+        events
+    }
+
+    // 规则变化后重新建立快照
+    pub fn reset(&mut self) {
+        self.processes.clear();
+        self.initialized = false;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Linux: 扫描 /proc
+// ---------------------------------------------------------------------------
+
+#[cfg(target_os = "linux")]
+pub struct ProcfsProcessSource;
+
+#[cfg(target_os = "linux")]
+impl ProcessSource for ProcfsProcessSource {
+    fn list_processes(&self) -> Result<Vec<ProcessInfo>, String> {
+        let entries =
+            std::fs::read_dir("/proc").map_err(|e| format!("Failed to read /proc: {}", e))?;
+
+        let mut processes = Vec::new();
+        for entry in entries.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|s| s.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            // 进程可能在扫描过程中退出
+            if let Some(name) = crate::window_source::process_name(pid) {
+                processes.push(ProcessInfo { pid, name });
+            }
+        }
+
+        Ok(processes)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Windows: Toolhelp 快照
+// ---------------------------------------------------------------------------
+
+#[cfg(all(target_os = "windows", feature = "win32"))]
+pub struct ToolhelpProcessSource;
+
+#[cfg(all(target_os = "windows", feature = "win32"))]
+impl ProcessSource for ToolhelpProcessSource {
+    fn list_processes(&self) -> Result<Vec<ProcessInfo>, String> {
+        use windows::Win32::{
+            Foundation::CloseHandle,
+            System::Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW,
+                TH32CS_SNAPPROCESS,
+            },
+        };
+
+        let mut processes = Vec::new();
+
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
+                .map_err(|e| format!("CreateToolhelp32Snapshot failed: {:?}", e))?;
+
+            let mut entry = PROCESSENTRY32W {
+                dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+                ..Default::default()
+            };
+
+            let mut has_entry = Process32FirstW(snapshot, &mut entry).is_ok();
+            while has_entry {
+                let length = entry
+                    .szExeFile
+                    .iter()
+                    .position(|c| *c == 0)
+                    .unwrap_or(entry.szExeFile.len());
+                processes.push(ProcessInfo {
+                    pid: entry.th32ProcessID,
+                    name: String::from_utf16_lossy(&entry.szExeFile[..length]),
+                });
+                has_entry = Process32NextW(snapshot, &mut entry).is_ok();
+            }
+
+            let _ = CloseHandle(snapshot);
+        }
+
+        Ok(processes)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 脚本化的假进程表
+// ---------------------------------------------------------------------------
+
+// 每次调用 list_processes 依次返回一帧进程表，脚本用完后一直返回最后一帧
+#[derive(Default)]
+pub struct ScriptedProcessSource {
+    frames: Mutex<VecDeque<Vec<ProcessInfo>>>,
+    last: Mutex<Vec<ProcessInfo>>,
+}
+
+// 脚本接口供测试和集成调试使用
+impl ScriptedProcessSource {
+    pub fn new(frames: Vec<Vec<ProcessInfo>>) -> Self {
+        Self {
+            frames: Mutex::new(frames.into()),
+            last: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn push_frame(&self, frame: Vec<ProcessInfo>) {
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.push_back(frame);
+        }
+    }
+}
+
+impl ProcessSource for ScriptedProcessSource {
+    fn list_processes(&self) -> Result<Vec<ProcessInfo>, String> {
+        let mut frames = self
+            .frames
+            .lock()
+            .map_err(|_| "Scripted process source poisoned".to_string())?;
+        let mut last = self
+            .last
+            .lock()
+            .map_err(|_| "Scripted process source poisoned".to_string())?;
+
+        if let Some(frame) = frames.pop_front() {
+            *last = frame;
+        }
+
+        Ok(last.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+        }
+    }
+
+    // 依次把脚本里的每一帧交给 tracker，返回每帧产生的事件
+    fn run(frames: Vec<Vec<ProcessInfo>>, names: &[&str]) -> Vec<Vec<ProcessEvent>> {
+        let count = frames.len();
+        let source = ScriptedProcessSource::new(frames);
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        let mut tracker = ProcessTracker::default();
+        (0..count)
+            .map(|_| {
+                let mut events = tracker.update(source.list_processes().unwrap(), &names);
+                events.sort_by_key(|event| (event.process().pid, event.trigger() as u8));
+                events
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_snapshot_produces_no_events() {
+        let events = run(vec![vec![process(1, "obs64.exe")]], &["obs64.exe"]);
+        assert_eq!(events, vec![vec![]]);
+    }
+
+    #[test]
+    fn detects_started_process() {
+        let events = run(
+            vec![
+                vec![process(1, "explorer.exe")],
+                vec![process(1, "explorer.exe"), process(2, "obs64.exe")],
+            ],
+            &["obs64.exe"],
+        );
+        assert_eq!(
+            events[1],
+            vec![ProcessEvent::Started {
+                process: process(2, "obs64.exe")
+            }]
+        );
+    }
+
+    #[test]
+    fn detects_exited_process() {
+        let events = run(
+            vec![
+                vec![process(1, "explorer.exe"), process(2, "obs64.exe")],
+                vec![process(1, "explorer.exe")],
+                vec![process(1, "explorer.exe")],
+            ],
+            &["obs64.exe"],
+        );
+        assert_eq!(
+            events[1],
+            vec![ProcessEvent::Exited {
+                process: process(2, "obs64.exe")
+            }]
+        );
+        // 脚本用完后重复最后一帧，不再产生事件
+        assert!(events[2].is_empty());
+    }
+
+    #[test]
+    fn reused_pid_counts_as_exit_and_start() {
+        let events = run(
+            vec![
+                vec![process(7, "obs64.exe")],
+                vec![process(7, "Discord.exe")],
+            ],
+            &["obs64.exe", "discord.exe"],
+        );
+        assert_eq!(
+            events[1],
+            vec![
+                ProcessEvent::Started {
+                    process: process(7, "Discord.exe")
+                },
+                ProcessEvent::Exited {
+                    process: process(7, "obs64.exe")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reused_pid_for_unwatched_process_is_exit_only() {
+        let events = run(
+            vec![
+                vec![process(7, "obs64.exe")],
+                vec![process(7, "notepad.exe")],
+            ],
+            &["obs64.exe"],
+        );
+        assert_eq!(
+            events[1],
+            vec![ProcessEvent::Exited {
+                process: process(7, "obs64.exe")
+            }]
+        );
+    }
+
+    #[test]
+    fn matches_names_ignoring_case() {
+        let events = run(vec![vec![], vec![process(3, "OBS64.EXE")]], &["obs64.exe"]);
+        assert_eq!(
+            events[1],
+            vec![ProcessEvent::Started {
+                process: process(3, "OBS64.EXE")
+            }]
+        );
+    }
+
+    #[test]
+    fn reset_takes_a_new_snapshot() {
+        let source = ScriptedProcessSource::new(vec![vec![], vec![process(1, "obs64.exe")]]);
+        let names = vec!["obs64.exe".to_string()];
+        let mut tracker = ProcessTracker::default();
+        tracker.update(source.list_processes().unwrap(), &names);
+        tracker.reset();
+        assert!(
+            tracker
+                .update(source.list_processes().unwrap(), &names)
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,800 @@
+// 声明式规则引擎：触发器 + 可选条件 + 按顺序执行的动作
+// 窗口监听、进程监听和定时规则在运行时都会转换成这里的规则，由同一条路径执行。
+use crate::monitor::{
+    CompiledPattern, MonitorAction, MonitorRule, TriggerEvent, WindowEvent, WindowMatcher,
+};
+use crate::process_monitor::{ProcessEvent, ProcessRule, ProcessTrigger};
+use crate::schedule::{MissedPolicy, ScheduleEdge, ScheduleRule, ScheduleTrigger, time_in_window};
+use chrono::{NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 触发记录最多保留的条数
+const MAX_FIRINGS: usize = 100;
+
+fn default_true() -> bool {
+    true
+}
+
+// 设备在什么时候触发规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceTrigger {
+    #[default]
+    Connected,
+    Disconnected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleTrigger {
+    Window {
+        matcher: WindowMatcher,
+        #[serde(default)]
+        event: TriggerEvent,
+    },
+    Process {
+        process: String, // 可执行文件名，忽略大小写
+        #[serde(default)]
+        event: ProcessTrigger,
+    },
+    Device {
+        #[serde(default)]
+        device: String, // 设备 id 或名称，为空表示任意设备
+        #[serde(default)]
+        event: DeviceTrigger,
+    },
+    Schedule {
+        schedule: ScheduleTrigger,
+        #[serde(default)]
+        edge: ScheduleEdge,
+        #[serde(default)]
+        missed: MissedPolicy,
+    },
+    Hotkey {
+        shortcut: String, // 全局快捷键，如 "CmdOrCtrl+Alt+A"
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    // 当前默认设备是（或不是）指定设备
+    CurrentDevice {
+        device: String,
+        #[serde(default)]
+        negate: bool,
+    },
+    // 当前时间在时间段内，end 早于 start 表示跨越午夜；时间格式 "HH:MM"
+    TimeOfDay {
+        start: CompiledPattern<NaiveTime>,
+        end: CompiledPattern<NaiveTime>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    SwitchDevice {
+        device: String, // 设备 id 或名称
+    },
+    RevertDevice,
+    SetVolume {
+        level: u8, // 0-100
+    },
+    Mute {
+        #[serde(default = "default_true")]
+        muted: bool,
+    },
+    ShowSwitcher,
+    HideSwitcher,
+    RunCommand {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    Notify {
+        title: String,
+        #[serde(default)]
+        body: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub trigger: RuleTrigger,
+    #[serde(default)]
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+}
+
+// 交给引擎匹配的事件；定时规则由调度器按下标直接触发，不经过这里
+pub enum RuleEvent<'a> {
+    Window(&'a WindowEvent),
+    Process(&'a ProcessEvent),
+    Device {
+        event: DeviceTrigger,
+        id: &'a str,
+        name: &'a str,
+    },
+    Hotkey(&'a str),
+}
+
+impl RuleEvent<'_> {
+    // 用于日志和切换历史的描述
+    pub fn describe(&self) -> String {
+        match self {
+            RuleEvent::Window(event) => event.window().title.clone(),
+            RuleEvent::Process(event) => event.process().name.clone(),
+            RuleEvent::Device { name, .. } => name.to_string(),
+            RuleEvent::Hotkey(shortcut) => shortcut.to_string(),
+        }
+    }
+}
+
+// 条件求值时需要的上下文
+pub struct RuleContext {
+    pub current_device: Option<(String, String)>, // (id, 名称)
+    pub now: NaiveDateTime,
+}
+
+impl RuleTrigger {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            RuleTrigger::Window { matcher, .. } => matcher.validate(),
+            RuleTrigger::Process { process, .. } if process.trim().is_empty() => {
+                Err("Process name must not be empty".to_string())
+            }
+            RuleTrigger::Schedule { schedule, edge, .. } => schedule.validate(*edge),
+            RuleTrigger::Hotkey { shortcut } if shortcut.trim().is_empty() => {
+                Err("Shortcut must not be empty".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn matches(&self, event: &RuleEvent) -> bool {
+        match (self, event) {
+            (RuleTrigger::Window { matcher, event }, RuleEvent::Window(window_event)) => {
+                TriggerEvent::of(window_event) == Some(*event)
+                    && matcher.matches(window_event.window())
+            }
+            (RuleTrigger::Process { process, event }, RuleEvent::Process(process_event)) => {
+                process_event.trigger() == *event
+                    && process_event.process().name.eq_ignore_ascii_case(process)
+            }
+            (
+                RuleTrigger::Device { device, event },
+                RuleEvent::Device {
+                    event: device_event,
+                    id,
+                    name,
+                },
+            ) => {
+                device_event == event
+                    && (device.is_empty() || device == id || device.eq_ignore_ascii_case(name))
+            }
+            (RuleTrigger::Hotkey { shortcut }, RuleEvent::Hotkey(pressed)) => {
+                shortcut.eq_ignore_ascii_case(pressed)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RuleTrigger::Window { matcher, event } => format!("window {:?} {:?}", matcher, event),
+            RuleTrigger::Process { process, event } => format!("process {} {:?}", process, event),
+            RuleTrigger::Device { device, event } if device.is_empty() => {
+                format!("any device {:?}", event)
+            }
+            RuleTrigger::Device { device, event } => format!("device {} {:?}", device, event),
+            RuleTrigger::Schedule { schedule, edge, .. } => {
+                format!("{} {:?}", schedule.describe(), edge)
+            }
+            RuleTrigger::Hotkey { shortcut } => format!("hotkey {}", shortcut),
+        }
+    }
+}
+
+impl RuleCondition {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            RuleCondition::CurrentDevice { device, .. } if device.trim().is_empty() => {
+                Err("Condition device must not be empty".to_string())
+            }
+            RuleCondition::TimeOfDay { start, end } => {
+                let start = start.compiled().map_err(str::to_string)?;
+                let end = end.compiled().map_err(str::to_string)?;
+                if start == end {
+                    return Err("Time of day start and end must differ".to_string());
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn holds(&self, context: &RuleContext) -> bool {
+        match self {
+            RuleCondition::CurrentDevice { device, negate } => {
+                let is_current = context
+                    .current_device
+                    .as_ref()
+                    .is_some_and(|(id, name)| device == id || device.eq_ignore_ascii_case(name));
+                is_current != *negate
+            }
+            RuleCondition::TimeOfDay { start, end } => match (start.compiled(), end.compiled()) {
+                (Ok(start), Ok(end)) => time_in_window(context.now.time(), *start, *end),
+                _ => false,
+            },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RuleCondition::CurrentDevice {
+                device,
+                negate: false,
+            } => {
+                format!("current device is {}", device)
+            }
+            RuleCondition::CurrentDevice {
+                device,
+                negate: true,
+            } => {
+                format!("current device is not {}", device)
+            }
+            RuleCondition::TimeOfDay { start, end } => {
+                format!("time between {} and {}", start.as_str(), end.as_str())
+            }
+        }
+    }
+}
+
+impl RuleAction {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            RuleAction::SwitchDevice { device } if device.trim().is_empty() => {
+                Err("Target device must not be empty".to_string())
+            }
+            RuleAction::SetVolume { level } if *level > 100 => {
+                Err(format!("Volume must be between 0 and 100, got {}", level))
+            }
+            RuleAction::RunCommand { program, .. } if program.trim().is_empty() => {
+                Err("Command must not be empty".to_string())
+            }
+            RuleAction::Notify { title, .. } if title.trim().is_empty() => {
+                Err("Notification title must not be empty".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RuleAction::SwitchDevice { device } => format!("switch to {}", device),
+            RuleAction::RevertDevice => "revert device".to_string(),
+            RuleAction::SetVolume { level } => format!("set volume to {}%", level),
+            RuleAction::Mute { muted: true } => "mute".to_string(),
+            RuleAction::Mute { muted: false } => "unmute".to_string(),
+            RuleAction::ShowSwitcher => "show switcher".to_string(),
+            RuleAction::HideSwitcher => "hide switcher".to_string(),
+            RuleAction::RunCommand { program, args } => {
+                format!("run {} {}", program, args.join(" "))
+                    .trim_end()
+                    .to_string()
+            }
+            RuleAction::Notify { title, .. } => format!("notify \"{}\"", title),
+        }
+    }
+}
+
+impl From<&MonitorAction> for RuleAction {
+    fn from(action: &MonitorAction) -> Self {
+        match action {
+            MonitorAction::ShowSwitcher => RuleAction::ShowSwitcher,
+            MonitorAction::SwitchTo(device) => RuleAction::SwitchDevice {
+                device: device.clone(),
+            },
+            MonitorAction::Revert => RuleAction::RevertDevice,
+        }
+    }
+}
+
+impl Rule {
+    pub fn validate(&self) -> Result<(), String> {
+        self.trigger.validate()?;
+        for condition in &self.conditions {
+            condition.validate()?;
+        }
+        if self.actions.is_empty() {
+            return Err("Rule must have at least one action".to_string());
+        }
+        for action in &self.actions {
+            action.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn label(&self) -> String {
+        if self.name.trim().is_empty() {
+            self.trigger.describe()
+        } else {
+            self.name.clone()
+        }
+    }
+
+    // 第一个不满足的条件
+    pub fn failed_condition(&self, context: &RuleContext) -> Option<&RuleCondition> {
+        self.conditions
+            .iter()
+            .find(|condition| !condition.holds(context))
+    }
+}
+
+// 把窗口监听、进程监听和定时规则转换成引擎规则，排在用户规则前面
+pub fn legacy_rules(
+    monitored_windows: &[MonitorRule],
+    process_rules: &[ProcessRule],
+    schedule_rules: &[ScheduleRule],
+) -> Vec<Rule> {
+    let mut rules = Vec::new();
+
+    for (index, rule) in monitored_windows.iter().enumerate() {
+        rules.push(Rule {
+            id: format!("window-{}", index),
+            name: String::new(),
+            enabled: true,
+            trigger: RuleTrigger::Window {
+                matcher: rule.matcher.clone(),
+                event: rule.event,
+            },
+            conditions: Vec::new(),
+            actions: vec![RuleAction::from(&rule.action)],
+        });
+    }
+
+    for (index, rule) in process_rules.iter().enumerate() {
+        rules.push(Rule {
+            id: format!("process-{}", index),
+            name: String::new(),
+            enabled: true,
+            trigger: RuleTrigger::Process {
+                process: rule.process.clone(),
+                event: rule.event,
+            },
+            conditions: Vec::new(),
+            actions: vec![RuleAction::from(&rule.action)],
+        });
+    }
+
+    for (index, rule) in schedule_rules.iter().enumerate() {
+        let edges = std::iter::once((ScheduleEdge::Start, Some(&rule.action))).chain(
+            std::iter::once((ScheduleEdge::End, rule.end_action.as_ref())),
+        );
+        for (edge, action) in edges {
+            let Some(action) = action else {
+                continue;
+            };
+            rules.push(Rule {
+                id: format!("schedule-{}-{:?}", index, edge).to_lowercase(),
+                name: rule.label(),
+                enabled: true,
+                trigger: RuleTrigger::Schedule {
+                    schedule: rule.trigger.clone(),
+                    edge,
+                    missed: rule.missed,
+                },
+                conditions: Vec::new(),
+                actions: vec![RuleAction::from(action)],
+            });
+        }
+    }
+
+    rules
+}
+
+// ---------------------------------------------------------------------------
+// 触发记录
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionOutcome {
+    pub action: String,
+    pub error: Option<String>, // None 表示成功；试运行时始终为 None
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleFiring {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub trigger: String,            // 触发规则的窗口标题、进程名、设备名等
+    pub skipped_by: Option<String>, // 不满足的条件，Some 时没有执行任何动作
+    pub actions: Vec<ActionOutcome>,
+    pub dry_run: bool,
+    pub timestamp: u64, // Unix 时间戳（秒）
+}
+
+impl RuleFiring {
+    pub fn new(rule: &Rule, trigger: &str, dry_run: bool) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            rule_id: rule.id.clone(),
+            rule_name: rule.label(),
+            trigger: trigger.to_string(),
+            skipped_by: None,
+            actions: Vec::new(),
+            dry_run,
+            timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RuleLog {
+    firings: Vec<RuleFiring>,
+}
+
+impl RuleLog {
+    pub fn push(&mut self, firing: RuleFiring) {
+        self.firings.push(firing);
+        if self.firings.len() > MAX_FIRINGS {
+            self.firings.remove(0);
+        }
+    }
+
+    pub fn firings(&self) -> &[RuleFiring] {
+        &self.firings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_monitor::ProcessInfo;
+    use crate::window_source::WindowInfo;
+
+    fn window(title: &str) -> WindowInfo {
+        WindowInfo {
+            id: 1,
+            title: title.to_string(),
+            class: String::new(),
+            pid: 1,
+            process_name: "zoom.exe".to_string(),
+        }
+    }
+
+    fn process(name: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid: 1,
+            name: name.to_string(),
+        }
+    }
+
+    fn context(current_device: Option<(&str, &str)>, time: &str) -> RuleContext {
+        RuleContext {
+            current_device: current_device.map(|(id, name)| (id.to_string(), name.to_string())),
+            now: NaiveDateTime::parse_from_str(&format!("2024-01-01 {}", time), "%Y-%m-%d %H:%M")
+                .unwrap(),
+        }
+    }
+
+    fn time_of_day(start: &str, end: &str) -> RuleCondition {
+        RuleCondition::TimeOfDay {
+            start: CompiledPattern::new(start),
+            end: CompiledPattern::new(end),
+        }
+    }
+
+    fn rule(trigger: RuleTrigger, actions: Vec<RuleAction>) -> Rule {
+        Rule {
+            id: "test".to_string(),
+            name: String::new(),
+            enabled: true,
+            trigger,
+            conditions: Vec::new(),
+            actions,
+        }
+    }
+
+    #[test]
+    fn window_triggers_match_event_and_matcher() {
+        let trigger = RuleTrigger::Window {
+            matcher: WindowMatcher::Process("Zoom.exe".to_string()),
+            event: TriggerEvent::Opened,
+        };
+        let opened = WindowEvent::Opened {
+            window: window("Zoom Meeting"),
+        };
+        let closed = WindowEvent::Closed {
+            window: window("Zoom Meeting"),
+        };
+        let renamed = WindowEvent::TitleChanged {
+            window: window("Zoom Meeting"),
+            old_title: "Zoom".to_string(),
+        };
+        assert!(trigger.matches(&RuleEvent::Window(&opened)));
+        assert!(!trigger.matches(&RuleEvent::Window(&closed)));
+        // 改标题不是窗口监听的触发事件
+        assert!(!trigger.matches(&RuleEvent::Window(&renamed)));
+
+        let other = WindowEvent::Opened {
+            window: WindowInfo {
+                process_name: "obs64.exe".to_string(),
+                ..window("Zoom Meeting")
+            },
+        };
+        assert!(!trigger.matches(&RuleEvent::Window(&other)));
+    }
+
+    #[test]
+    fn process_triggers_ignore_case() {
+        let trigger = RuleTrigger::Process {
+            process: "OBS64.exe".to_string(),
+            event: ProcessTrigger::Exited,
+        };
+        let exited = ProcessEvent::Exited {
+            process: process("obs64.exe"),
+        };
+        let started = ProcessEvent::Started {
+            process: process("obs64.exe"),
+        };
+        assert!(trigger.matches(&RuleEvent::Process(&exited)));
+        assert!(!trigger.matches(&RuleEvent::Process(&started)));
+        assert!(!trigger.matches(&RuleEvent::Process(&ProcessEvent::Exited {
+            process: process("obs32.exe"),
+        })));
+    }
+
+    #[test]
+    fn device_triggers_match_id_name_or_any() {
+        let connected = |id, name| RuleEvent::Device {
+            event: DeviceTrigger::Connected,
+            id,
+            name,
+        };
+        let by_name = RuleTrigger::Device {
+            device: "usb headset".to_string(),
+            event: DeviceTrigger::Connected,
+        };
+        let by_id = RuleTrigger::Device {
+            device: "{0.0.0}.{abc}".to_string(),
+            event: DeviceTrigger::Connected,
+        };
+        let any = RuleTrigger::Device {
+            device: String::new(),
+            event: DeviceTrigger::Disconnected,
+        };
+
+        assert!(by_name.matches(&connected("{0.0.0}.{abc}", "USB Headset")));
+        assert!(by_id.matches(&connected("{0.0.0}.{abc}", "USB Headset")));
+        assert!(!by_id.matches(&connected("{0.0.0}.{ABC}", "USB Headset")));
+        assert!(!by_name.matches(&connected("{0.0.0}.{def}", "Speakers")));
+        assert!(!any.matches(&connected("{0.0.0}.{abc}", "USB Headset")));
+        assert!(any.matches(&RuleEvent::Device {
+            event: DeviceTrigger::Disconnected,
+            id: "{0.0.0}.{def}",
+            name: "Speakers",
+        }));
+    }
+
+    #[test]
+    fn hotkey_triggers_ignore_case_and_other_kinds() {
+        let trigger = RuleTrigger::Hotkey {
+            shortcut: "CmdOrCtrl+Alt+A".to_string(),
+        };
+        assert!(trigger.matches(&RuleEvent::Hotkey("cmdorctrl+alt+a")));
+        assert!(!trigger.matches(&RuleEvent::Hotkey("CmdOrCtrl+Alt+B")));
+
+        // 定时规则由调度器直接触发，不匹配任何事件
+        let schedule = RuleTrigger::Schedule {
+            schedule: ScheduleTrigger::Cron {
+                expression: CompiledPattern::new("* * * * *"),
+            },
+            edge: ScheduleEdge::Start,
+            missed: MissedPolicy::CatchUp,
+        };
+        assert!(!schedule.matches(&RuleEvent::Hotkey("CmdOrCtrl+Alt+A")));
+    }
+
+    #[test]
+    fn current_device_condition_supports_negate() {
+        let is_headset = RuleCondition::CurrentDevice {
+            device: "Headset".to_string(),
+            negate: false,
+        };
+        let not_headset = RuleCondition::CurrentDevice {
+            device: "Headset".to_string(),
+            negate: true,
+        };
+        let headset = context(Some(("{abc}", "headset")), "12:00");
+        let speakers = context(Some(("{def}", "Speakers")), "12:00");
+        let unknown = context(None, "12:00");
+
+        assert!(is_headset.holds(&headset));
+        assert!(!is_headset.holds(&speakers));
+        assert!(!is_headset.holds(&unknown));
+        assert!(!not_headset.holds(&headset));
+        assert!(not_headset.holds(&speakers));
+        assert!(not_headset.holds(&unknown));
+    }
+
+    #[test]
+    fn time_of_day_condition_wraps_midnight() {
+        let office = time_of_day("09:00", "18:00");
+        assert!(office.holds(&context(None, "09:00")));
+        assert!(office.holds(&context(None, "17:59")));
+        assert!(!office.holds(&context(None, "18:00")));
+        assert!(!office.holds(&context(None, "08:59")));
+
+        let night = time_of_day("22:00", "06:00");
+        assert!(night.holds(&context(None, "23:30")));
+        assert!(night.holds(&context(None, "00:00")));
+        assert!(night.holds(&context(None, "05:59")));
+        assert!(!night.holds(&context(None, "06:00")));
+        assert!(!night.holds(&context(None, "21:59")));
+
+        // 无法解析的时间视为不满足
+        assert!(!time_of_day("9am", "18:00").holds(&context(None, "12:00")));
+    }
+
+    #[test]
+    fn time_of_day_condition_round_trips_as_strings() {
+        let json = r#"{"type":"time_of_day","start":"22:00","end":"06:00"}"#;
+        let condition: RuleCondition = serde_json::from_str(json).unwrap();
+        assert_eq!(condition, time_of_day("22:00", "06:00"));
+        assert_eq!(serde_json::to_string(&condition).unwrap(), json);
+        assert_eq!(condition.describe(), "time between 22:00 and 06:00");
+    }
+
+    #[test]
+    fn validate_checks_trigger_conditions_and_actions() {
+        let show = vec![RuleAction::ShowSwitcher];
+        let hotkey = RuleTrigger::Hotkey {
+            shortcut: "CmdOrCtrl+Alt+A".to_string(),
+        };
+        assert!(rule(hotkey.clone(), show.clone()).validate().is_ok());
+
+        let empty_process = RuleTrigger::Process {
+            process: " ".to_string(),
+            event: ProcessTrigger::Started,
+        };
+        assert!(rule(empty_process, show.clone()).validate().is_err());
+        let cron_end = RuleTrigger::Schedule {
+            schedule: ScheduleTrigger::Cron {
+                expression: CompiledPattern::new("0 9 * * *"),
+            },
+            edge: ScheduleEdge::End,
+            missed: MissedPolicy::CatchUp,
+        };
+        assert!(rule(cron_end, show.clone()).validate().is_err());
+
+        assert_eq!(
+            rule(hotkey.clone(), Vec::new()).validate(),
+            Err("Rule must have at least one action".to_string())
+        );
+        assert!(
+            rule(hotkey.clone(), vec![RuleAction::SetVolume { level: 101 }])
+                .validate()
+                .is_err()
+        );
+        assert!(
+            rule(
+                hotkey.clone(),
+                vec![
+                    RuleAction::ShowSwitcher,
+                    RuleAction::SwitchDevice {
+                        device: String::new()
+                    }
+                ]
+            )
+            .validate()
+            .is_err()
+        );
+
+        let mut with_conditions = rule(hotkey, show);
+        with_conditions.conditions = vec![time_of_day("09:00", "09:00")];
+        assert_eq!(
+            with_conditions.validate(),
+            Err("Time of day start and end must differ".to_string())
+        );
+        with_conditions.conditions = vec![time_of_day("25:00", "09:00")];
+        assert!(
+            with_conditions
+                .validate()
+                .unwrap_err()
+                .starts_with("Invalid time")
+        );
+    }
+
+    #[test]
+    fn legacy_rules_convert_in_order() {
+        let monitored = vec![MonitorRule {
+            matcher: WindowMatcher::ExactTitle("Zoom Meeting".to_string()),
+            event: TriggerEvent::Closed,
+            action: MonitorAction::ShowSwitcher,
+        }];
+        let processes = vec![ProcessRule {
+            process: "obs64.exe".to_string(),
+            event: ProcessTrigger::Started,
+            action: MonitorAction::SwitchTo("Headset".to_string()),
+        }];
+        let weekly = ScheduleTrigger::Weekly {
+            days: Vec::new(),
+            start: CompiledPattern::new("09:00"),
+            end: CompiledPattern::new("18:00"),
+        };
+        let schedules = vec![
+            ScheduleRule {
+                name: "Office".to_string(),
+                trigger: weekly.clone(),
+                action: MonitorAction::SwitchTo("Headset".to_string()),
+                end_action: Some(MonitorAction::Revert),
+                missed: MissedPolicy::Skip,
+            },
+            // 没有结束动作时只生成开始规则
+            ScheduleRule {
+                name: String::new(),
+                trigger: weekly.clone(),
+                action: MonitorAction::ShowSwitcher,
+                end_action: None,
+                missed: MissedPolicy::CatchUp,
+            },
+        ];
+
+        let rules = legacy_rules(&monitored, &processes, &schedules);
+        let ids: Vec<&str> = rules.iter().map(|rule| rule.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "window-0",
+                "process-0",
+                "schedule-0-start",
+                "schedule-0-end",
+                "schedule-1-start"
+            ]
+        );
+
+        assert_eq!(
+            rules[0].trigger,
+            RuleTrigger::Window {
+                matcher: WindowMatcher::ExactTitle("Zoom Meeting".to_string()),
+                event: TriggerEvent::Closed,
+            }
+        );
+        assert_eq!(rules[0].actions, vec![RuleAction::ShowSwitcher]);
+        assert_eq!(
+            rules[1].actions,
+            vec![RuleAction::SwitchDevice {
+                device: "Headset".to_string()
+            }]
+        );
+        assert_eq!(rules[3].name, "Office");
+        assert_eq!(
+            rules[3].trigger,
+            RuleTrigger::Schedule {
+                schedule: weekly,
+                edge: ScheduleEdge::End,
+                missed: MissedPolicy::Skip,
+            }
+        );
+        assert_eq!(rules[3].actions, vec![RuleAction::RevertDevice]);
+        assert_eq!(rules[4].name, "schedule 09:00-18:00");
+        assert!(
+            rules
+                .iter()
+                .all(|rule| rule.enabled && rule.validate().is_ok())
+        );
+    }
+}
@@ -0,0 +1,748 @@
+// 定时规则：按星期 + 时间段或 cron 表达式切换默认设备/显示切换器
+// 例如工作日 9:00-18:00 使用耳机，下班后恢复音箱。
+use crate::logging::MONITOR;
+use crate::monitor::{Compile, CompiledPattern, MonitorAction};
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Timelike};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+// 两次检查之间超过这个间隔就认为中间错过了时间点（系统睡眠、休眠或程序被挂起）
+const MAX_TICK_GAP_MINUTES: i64 = 2;
+
+// 补执行时最多回溯的分钟数，更长的空档一律视为跨过了时间点
+const MAX_CATCH_UP_MINUTES: i64 = 7 * 24 * 60;
+
+// ---------------------------------------------------------------------------
+// 可注入的时钟
+// ---------------------------------------------------------------------------
+
+pub trait Clock: Send + Sync {
+    // 当前本地时间（不带时区）
+    fn now(&self) -> NaiveDateTime;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        chrono::Local::now().naive_local()
+    }
+}
+
+pub fn default_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+// 手动拨动的时钟，用于测试和模拟睡眠唤醒
+pub struct ManualClock {
+    now: Mutex<NaiveDateTime>,
+}
+
+// 供测试和集成调试使用
+impl ManualClock {
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn set(&self, now: NaiveDateTime) {
+        if let Ok(mut current) = self.now.lock() {
+            *current = now;
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut current) = self.now.lock() {
+            *current += duration;
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> NaiveDateTime {
+        self.now
+            .lock()
+            .map(|now| *now)
+            .unwrap_or(NaiveDateTime::MIN)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 规则
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Day {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Day {
+    fn matches(self, time: NaiveDateTime) -> bool {
+        self as u32 == time.weekday().num_days_from_monday()
+    }
+}
+
+// 时间和 cron 表达式在加载配置时解析一次，调度器每分钟检查时直接使用解析结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    // 每周指定几天的时间段，时间格式 "HH:MM"；end 早于 start 表示跨越午夜；days 为空表示每天
+    Weekly {
+        #[serde(default)]
+        days: Vec<Day>,
+        start: CompiledPattern<NaiveTime>,
+        end: CompiledPattern<NaiveTime>,
+    },
+    // 标准 5 段 cron 表达式：分 时 日 月 星期
+    Cron {
+        expression: CompiledPattern<CronSchedule>,
+    },
+}
+
+// 错过时间点（睡眠唤醒、程序启动）时怎么处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedPolicy {
+    // 补执行一次：时间段规则按当前是否处于时间段内执行对应动作，cron 规则合并为一次执行
+    #[default]
+    CatchUp,
+    // 跳过错过的时间点，只等待下一个时间点
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    #[serde(default)]
+    pub name: String,
+    pub trigger: ScheduleTrigger,
+    pub action: MonitorAction, // 进入时间段 / cron 时间点时执行
+    #[serde(default)]
+    pub end_action: Option<MonitorAction>, // 离开时间段时执行，cron 规则忽略
+    #[serde(default)]
+    pub missed: MissedPolicy,
+}
+
+impl ScheduleRule {
+    pub fn validate(&self) -> Result<(), String> {
+        self.trigger.validate(ScheduleEdge::Start)?;
+        for action in std::iter::once(&self.action).chain(self.end_action.iter()) {
+            if let MonitorAction::SwitchTo(device) = action {
+                if device.trim().is_empty() {
+                    return Err("Target device must not be empty".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 用于日志和切换历史的描述
+    pub fn label(&self) -> String {
+        if !self.name.trim().is_empty() {
+            return self.name.clone();
+        }
+        self.trigger.describe()
+    }
+}
+
+impl ScheduleTrigger {
+    pub fn validate(&self, edge: ScheduleEdge) -> Result<(), String> {
+        self.compiled()?;
+        if matches!(self, ScheduleTrigger::Cron { .. }) && edge == ScheduleEdge::End {
+            return Err("Cron schedules have no end".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ScheduleTrigger::Weekly { start, end, .. } => {
+                format!("schedule {}-{}", start.as_str(), end.as_str())
+            }
+            ScheduleTrigger::Cron { expression } => format!("cron {}", expression.as_str()),
+        }
+    }
+
+    // 取出解析结果，不会重新解析
+    fn compiled(&self) -> Result<CompiledTrigger<'_>, String> {
+        match self {
+            ScheduleTrigger::Weekly { days, start, end } => {
+                let start = *start.compiled().map_err(str::to_string)?;
+                let end = *end.compiled().map_err(str::to_string)?;
+                if start == end {
+                    return Err("Schedule start and end must differ".to_string());
+                }
+                Ok(CompiledTrigger::Weekly { days, start, end })
+            }
+            ScheduleTrigger::Cron { expression } => Ok(CompiledTrigger::Cron(
+                expression.compiled().map_err(str::to_string)?,
+            )),
+        }
+    }
+}
+
+pub fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", value))
+}
+
+// 规则条件里的时间在加载配置时解析一次
+impl Compile for NaiveTime {
+    fn compile(source: &str) -> Result<Self, String> {
+        parse_time(source)
+    }
+}
+
+// 时间是否处于 [start, end) 内；end 早于 start 表示跨越午夜
+pub fn time_in_window(time: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start < end {
+        time >= start && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+enum CompiledTrigger<'a> {
+    Weekly {
+        days: &'a [Day],
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+    Cron(&'a CronSchedule),
+}
+
+impl CompiledTrigger<'_> {
+    // 某一分钟是否处于时间段内；跨午夜的时间段属于开始的那一天
+    fn in_window(&self, time: NaiveDateTime) -> bool {
+        let CompiledTrigger::Weekly { days, start, end } = self else {
+            return false;
+        };
+        let clock = time.time();
+        if !time_in_window(clock, *start, *end) {
+            return false;
+        }
+
+        // 跨午夜时间段在午夜之后的部分按前一天算
+        let day = if start > end && clock < *end {
+            time - Duration::days(1)
+        } else {
+            time
+        };
+        days.is_empty() || days.iter().any(|d| d.matches(day))
+    }
+
+    // 这一分钟开始时发生的边界
+    fn boundary_at(&self, minute: NaiveDateTime) -> Option<ScheduleEdge> {
+        match self {
+            CompiledTrigger::Weekly { .. } => {
+                let before = self.in_window(minute - Duration::minutes(1));
+                match (before, self.in_window(minute)) {
+                    (false, true) => Some(ScheduleEdge::Start),
+                    (true, false) => Some(ScheduleEdge::End),
+                    _ => None,
+                }
+            }
+            CompiledTrigger::Cron(cron) => cron.matches(minute).then_some(ScheduleEdge::Start),
+        }
+    }
+}
+
+// 时间段的开始或结束；cron 规则只有开始
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleEdge {
+    #[default]
+    Start,
+    End,
+}
+
+// ---------------------------------------------------------------------------
+// cron 表达式
+// ---------------------------------------------------------------------------
+
+// 支持 *、数字、a-b 范围、逗号列表和 /n 步长；星期 0 和 7 都表示周日
+#[derive(Clone)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl Compile for CronSchedule {
+    fn compile(source: &str) -> Result<Self, String> {
+        CronSchedule::parse(source)
+    }
+}
+
+impl CronSchedule {
+    fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!(
+                "Invalid cron expression '{}', expected 5 fields",
+                expression
+            ));
+        };
+
+        let mut days_of_week = parse_cron_field(day_of_week, 0, 7)?;
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+
+        Ok(Self {
+            minutes: parse_cron_field(minute, 0, 59)?,
+            hours: parse_cron_field(hour, 0, 23)?,
+            days_of_month: parse_cron_field(day_of_month, 1, 31)?,
+            months: parse_cron_field(month, 1, 12)?,
+            days_of_week,
+            // 和 cron 一致：以 * 开头（包括 */n）的字段不算作限制
+            day_of_month_any: day_of_month.starts_with('*'),
+            day_of_week_any: day_of_week.starts_with('*'),
+        })
+    }
+
+    fn matches(&self, time: NaiveDateTime) -> bool {
+        let day_of_month = self.days_of_month[time.day() as usize];
+        let day_of_week = self.days_of_week[time.weekday().num_days_from_sunday() as usize];
+        // 和 cron 一致：日和星期都有限制时满足其一即可
+        let day = match (self.day_of_month_any, self.day_of_week_any) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+
+        self.minutes[time.minute() as usize]
+            && self.hours[time.hour() as usize]
+            && self.months[time.month() as usize]
+            && day
+    }
+}
+
+// 返回按值索引的表，下标 0..=max
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let invalid = || format!("Invalid cron field '{}'", field);
+    let mut allowed = vec![false; max as usize + 1];
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+
+        let (from, to) = if range == "*" {
+            (min, max)
+        } else if let Some((from, to)) = range.split_once('-') {
+            (
+                from.parse::<u32>().map_err(|_| invalid())?,
+                to.parse::<u32>().map_err(|_| invalid())?,
+            )
+        } else {
+            let value = range.parse::<u32>().map_err(|_| invalid())?;
+            // "5/15" 表示从 5 开始每 15 个单位
+            if part.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if from < min || to > max || from > to {
+            return Err(invalid());
+        }
+        for value in (from..=to).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+
+    Ok(allowed)
+}
+
+// ---------------------------------------------------------------------------
+// 调度器
+// ---------------------------------------------------------------------------
+
+// 调度器检查的一项：某个时间表的开始或结束
+pub struct ScheduleEntry<'a> {
+    pub trigger: &'a ScheduleTrigger,
+    pub edge: ScheduleEdge,
+    pub missed: MissedPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleFiring {
+    pub index: usize,   // 触发的是第几项
+    pub catch_up: bool, // 是否是对错过时间点的补执行
+}
+
+// 记录上次检查到的分钟，每次检查处理 (上次, 现在] 之间经过的时间点
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    last_check: Option<NaiveDateTime>,
+}
+
+impl Scheduler {
+    pub fn tick(&mut self, now: NaiveDateTime, entries: &[ScheduleEntry]) -> Vec<ScheduleFiring> {
+        let now = truncate_to_minute(now);
+        let last = self.last_check;
+
+        // 同一分钟内重复检查，或者系统时间被往回调了
+        if let Some(last) = last {
+            if now <= last {
+                self.last_check = Some(now);
+                return Vec::new();
+            }
+        }
+        self.last_check = Some(now);
+
+        let mut firings = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let trigger = match entry.trigger.compiled() {
+                Ok(trigger) => trigger,
+                Err(e) => {
+                    warn!(
+                        target: MONITOR,
+                        "Skipping invalid schedule '{}': {}",
+                        entry.trigger.describe(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            match last {
+                Some(last) if now - last <= Duration::minutes(MAX_TICK_GAP_MINUTES) => {
+                    // 正常检查：逐分钟执行经过的边界
+                    let mut minute = last + Duration::minutes(1);
+                    while minute <= now {
+                        if trigger.boundary_at(minute) == Some(entry.edge) {
+                            firings.push(ScheduleFiring {
+                                index,
+                                catch_up: false,
+                            });
+                        }
+                        minute += Duration::minutes(1);
+                    }
+                }
+                _ => {
+                    if entry.missed == MissedPolicy::Skip {
+                        continue;
+                    }
+                    if catch_up(&trigger, last, now) == Some(entry.edge) {
+                        firings.push(ScheduleFiring {
+                            index,
+                            catch_up: true,
+                        });
+                    }
+                }
+            }
+        }
+
+        firings
+    }
+}
+
+// 错过时间点后的补执行，只执行一次：
+// - 程序刚启动（last 为 None）：时间段规则在时间段内时触发开始，不在时间段内时不做任何事，
+//   避免每次启动都覆盖用户手动选择的设备；cron 规则不补执行
+// - 睡眠唤醒：如果空档内跨过了边界，时间段规则按当前状态触发开始或结束，
+//   cron 规则合并为一次执行
+fn catch_up(
+    trigger: &CompiledTrigger<'_>,
+    last: Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> Option<ScheduleEdge> {
+    let Some(last) = last else {
+        return trigger.in_window(now).then_some(ScheduleEdge::Start);
+    };
+
+    let earliest = now - Duration::minutes(MAX_CATCH_UP_MINUTES);
+    let mut minute = last.max(earliest) + Duration::minutes(1);
+    let mut crossed = last < earliest;
+    while !crossed && minute <= now {
+        crossed = trigger.boundary_at(minute).is_some();
+        minute += Duration::minutes(1);
+    }
+    if !crossed {
+        return None;
+    }
+
+    match trigger {
+        CompiledTrigger::Weekly { .. } if trigger.in_window(now) => Some(ScheduleEdge::Start),
+        CompiledTrigger::Weekly { .. } => Some(ScheduleEdge::End),
+        CompiledTrigger::Cron(_) => Some(ScheduleEdge::Start),
+    }
+}
+
+fn truncate_to_minute(time: NaiveDateTime) -> NaiveDateTime {
+    time.with_second(0)
+        .and_then(|time| time.with_nanosecond(0))
+        .unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-01 是周一
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn weekly(days: Vec<Day>, start: &str, end: &str) -> ScheduleTrigger {
+        ScheduleTrigger::Weekly {
+            days,
+            start: CompiledPattern::new(start),
+            end: CompiledPattern::new(end),
+        }
+    }
+
+    fn cron(expression: &str) -> ScheduleTrigger {
+        ScheduleTrigger::Cron {
+            expression: CompiledPattern::new(expression),
+        }
+    }
+
+    fn cron_matches(expression: &str, time: &str) -> bool {
+        CronSchedule::parse(expression).unwrap().matches(at(time))
+    }
+
+    fn entry(
+        trigger: &ScheduleTrigger,
+        edge: ScheduleEdge,
+        missed: MissedPolicy,
+    ) -> ScheduleEntry<'_> {
+        ScheduleEntry {
+            trigger,
+            edge,
+            missed,
+        }
+    }
+
+    fn fired(index: usize, catch_up: bool) -> ScheduleFiring {
+        ScheduleFiring { index, catch_up }
+    }
+
+    #[test]
+    fn weekly_window_crosses_midnight() {
+        let trigger = weekly(vec![Day::Fri], "22:00", "06:00");
+        let trigger = trigger.compiled().unwrap();
+        assert!(!trigger.in_window(at("2024-01-05 21:59")));
+        assert!(trigger.in_window(at("2024-01-05 22:00")));
+        // 周五开始的时间段延续到周六早上
+        assert!(trigger.in_window(at("2024-01-06 05:59")));
+        assert!(!trigger.in_window(at("2024-01-06 06:00")));
+        // 周五凌晨属于周四的时间段
+        assert!(!trigger.in_window(at("2024-01-05 01:00")));
+        assert!(!trigger.in_window(at("2024-01-06 23:00")));
+
+        assert_eq!(
+            trigger.boundary_at(at("2024-01-05 22:00")),
+            Some(ScheduleEdge::Start)
+        );
+        assert_eq!(
+            trigger.boundary_at(at("2024-01-06 06:00")),
+            Some(ScheduleEdge::End)
+        );
+        assert_eq!(trigger.boundary_at(at("2024-01-06 00:00")), None);
+    }
+
+    #[test]
+    fn weekly_window_rejects_equal_times() {
+        assert!(weekly(vec![], "09:00", "09:00").compiled().is_err());
+        assert!(weekly(vec![], "9am", "18:00").compiled().is_err());
+    }
+
+    #[test]
+    fn triggers_are_parsed_when_loaded() {
+        let json = r#"{"type":"weekly","days":["mon"],"start":"09:00","end":"18:00"}"#;
+        let trigger: ScheduleTrigger = serde_json::from_str(json).unwrap();
+        assert_eq!(trigger, weekly(vec![Day::Mon], "09:00", "18:00"));
+        assert_eq!(serde_json::to_string(&trigger).unwrap(), json);
+
+        // 无法解析的时间和表达式照样加载，由 validate 报告，调度器跳过
+        let json = r#"{"type":"cron","expression":"61 * * * *"}"#;
+        let trigger: ScheduleTrigger = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            trigger.validate(ScheduleEdge::Start).unwrap_err(),
+            "Invalid cron field '61'"
+        );
+        let entries = [entry(&trigger, ScheduleEdge::Start, MissedPolicy::CatchUp)];
+        let mut scheduler = Scheduler::default();
+        assert!(scheduler.tick(at("2024-01-01 09:00"), &entries).is_empty());
+    }
+
+    #[test]
+    fn cron_parses_fields() {
+        assert!(cron_matches("30 9 * * *", "2024-01-03 09:30"));
+        assert!(!cron_matches("30 9 * * *", "2024-01-03 09:31"));
+        assert!(cron_matches("0 9-17 * * 1-5", "2024-01-05 17:00"));
+        assert!(!cron_matches("0 9-17 * * 1-5", "2024-01-06 12:00"));
+        assert!(cron_matches("0 0 1,15 * *", "2024-01-15 00:00"));
+
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+    }
+
+    #[test]
+    fn cron_supports_steps() {
+        for minute in ["00", "15", "30", "45"] {
+            assert!(cron_matches(
+                "*/15 * * * *",
+                &format!("2024-01-01 10:{}", minute)
+            ));
+        }
+        assert!(!cron_matches("*/15 * * * *", "2024-01-01 10:20"));
+        // 5/20 从第 5 分钟开始
+        assert!(cron_matches("5/20 * * * *", "2024-01-01 10:45"));
+        assert!(!cron_matches("5/20 * * * *", "2024-01-01 10:40"));
+        assert!(cron_matches("0 8-18/2 * * *", "2024-01-01 16:00"));
+        assert!(!cron_matches("0 8-18/2 * * *", "2024-01-01 17:00"));
+    }
+
+    #[test]
+    fn cron_day_of_week_seven_is_sunday() {
+        assert!(cron_matches("0 9 * * 7", "2024-01-07 09:00"));
+        assert!(cron_matches("0 9 * * 0", "2024-01-07 09:00"));
+        assert!(!cron_matches("0 9 * * 7", "2024-01-06 09:00"));
+        assert!(cron_matches("0 9 * * 5-7", "2024-01-07 09:00"));
+    }
+
+    #[test]
+    fn cron_combines_day_fields_like_cron() {
+        // 日和星期都有限制时满足其一即可：1 号或者周三
+        assert!(cron_matches("0 9 1 * 3", "2024-01-01 09:00"));
+        assert!(cron_matches("0 9 1 * 3", "2024-01-03 09:00"));
+        assert!(!cron_matches("0 9 1 * 3", "2024-01-02 09:00"));
+        // */2 不算作限制，需要同时满足：单数日并且是周一
+        assert!(cron_matches("0 9 */2 * 1", "2024-01-01 09:00"));
+        assert!(!cron_matches("0 9 */2 * 1", "2024-01-08 09:00"));
+        assert!(!cron_matches("0 9 */2 * 1", "2024-01-03 09:00"));
+        assert!(cron_matches("0 9 */2 * 1", "2024-01-15 09:00"));
+    }
+
+    #[test]
+    fn tick_fires_boundaries_as_the_clock_advances() {
+        let clock = ManualClock::new(at("2024-01-05 21:58"));
+        let trigger = weekly(vec![Day::Fri], "22:00", "06:00");
+        let entries = [
+            entry(&trigger, ScheduleEdge::Start, MissedPolicy::CatchUp),
+            entry(&trigger, ScheduleEdge::End, MissedPolicy::CatchUp),
+        ];
+        let mut scheduler = Scheduler::default();
+
+        // 启动时不在时间段内，不做任何事
+        assert!(scheduler.tick(clock.now(), &entries).is_empty());
+        clock.advance(Duration::minutes(1));
+        assert!(scheduler.tick(clock.now(), &entries).is_empty());
+        clock.advance(Duration::seconds(75));
+        assert_eq!(scheduler.tick(clock.now(), &entries), vec![fired(0, false)]);
+        // 同一分钟内再次检查不重复触发
+        clock.advance(Duration::seconds(20));
+        assert!(scheduler.tick(clock.now(), &entries).is_empty());
+
+        clock.set(at("2024-01-06 05:59"));
+        scheduler.tick(clock.now(), &entries);
+        clock.advance(Duration::minutes(1));
+        assert_eq!(scheduler.tick(clock.now(), &entries), vec![fired(1, false)]);
+    }
+
+    #[test]
+    fn tick_catches_up_on_startup_inside_window() {
+        let clock = ManualClock::new(at("2024-01-05 23:30"));
+        let trigger = weekly(vec![Day::Fri], "22:00", "06:00");
+        let every_minute = cron("* * * * *");
+        let entries = [
+            entry(&trigger, ScheduleEdge::Start, MissedPolicy::CatchUp),
+            entry(&trigger, ScheduleEdge::End, MissedPolicy::CatchUp),
+            // 启动时 cron 规则不补执行
+            entry(&every_minute, ScheduleEdge::Start, MissedPolicy::CatchUp),
+        ];
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.tick(clock.now(), &entries), vec![fired(0, true)]);
+    }
+
+    #[test]
+    fn tick_catches_up_once_after_sleep() {
+        let clock = ManualClock::new(at("2024-01-05 21:00"));
+        let trigger = weekly(vec![Day::Fri], "22:00", "06:00");
+        let hourly = cron("0 * * * *");
+        let entries = [
+            entry(&trigger, ScheduleEdge::Start, MissedPolicy::CatchUp),
+            entry(&trigger, ScheduleEdge::End, MissedPolicy::CatchUp),
+            entry(&hourly, ScheduleEdge::Start, MissedPolicy::CatchUp),
+        ];
+        let mut scheduler = Scheduler::default();
+        scheduler.tick(clock.now(), &entries);
+
+        // 睡过了开始：按当前状态补执行开始，错过的多个整点合并为一次
+        clock.set(at("2024-01-05 23:30"));
+        assert_eq!(
+            scheduler.tick(clock.now(), &entries),
+            vec![fired(0, true), fired(2, true)]
+        );
+
+        // 睡过了整个时间段：只补执行结束
+        clock.set(at("2024-01-06 07:10"));
+        assert_eq!(
+            scheduler.tick(clock.now(), &entries),
+            vec![fired(1, true), fired(2, true)]
+        );
+
+        // 空档内没有跨过边界时不补执行
+        clock.set(at("2024-01-06 07:50"));
+        assert!(scheduler.tick(clock.now(), &entries).is_empty());
+    }
+
+    #[test]
+    fn tick_skips_missed_boundaries_when_configured() {
+        let clock = ManualClock::new(at("2024-01-05 21:00"));
+        let trigger = weekly(vec![Day::Fri], "22:00", "06:00");
+        let hourly = cron("0 * * * *");
+        let entries = [
+            entry(&trigger, ScheduleEdge::Start, MissedPolicy::Skip),
+            entry(&hourly, ScheduleEdge::Start, MissedPolicy::Skip),
+        ];
+        let mut scheduler = Scheduler::default();
+        scheduler.tick(clock.now(), &entries);
+
+        clock.set(at("2024-01-05 23:30"));
+        assert!(scheduler.tick(clock.now(), &entries).is_empty());
+        // 之后的时间点照常触发
+        clock.set(at("2024-01-05 23:59"));
+        scheduler.tick(clock.now(), &entries);
+        clock.advance(Duration::minutes(1));
+        assert_eq!(scheduler.tick(clock.now(), &entries), vec![fired(1, false)]);
+    }
+
+    #[test]
+    fn tick_ignores_clock_going_backwards() {
+        let clock = ManualClock::new(at("2024-01-01 09:05"));
+        let trigger = cron("0 9 * * *");
+        let entries = [entry(&trigger, ScheduleEdge::Start, MissedPolicy::CatchUp)];
+        let mut scheduler = Scheduler::default();
+        scheduler.tick(clock.now(), &entries);
+
+        clock.set(at("2024-01-01 08:59"));
+        assert!(scheduler.tick(clock.now(), &entries).is_empty());
+        clock.advance(Duration::minutes(1));
+        assert_eq!(scheduler.tick(clock.now(), &entries), vec![fired(0, false)]);
+    }
+}
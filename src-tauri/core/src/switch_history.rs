@@ -0,0 +1,168 @@
+// 音频设备切换历史：记录每次切换之前的设备和切换原因，用于"恢复之前的设备"
+use crate::logging::AUDIO;
+use log::info;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 历史最多保留的条数，超出后丢弃最早的记录
+const MAX_HISTORY: usize = 32;
+
+// 切换原因
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SwitchCause {
+    User,                      // 用户在界面上手动切换
+    Rule { trigger: String },  // 监听规则触发，记录触发的窗口标题或进程名
+    Focus { device: String },  // 焦点跟随切换，记录目标设备名称
+    Remote { source: String }, // 命令行参数等外部请求，记录请求来源
+}
+
+impl SwitchCause {
+    // 用于事件历史的描述
+    pub fn describe(&self) -> String {
+        match self {
+            SwitchCause::User => "user".to_string(),
+            SwitchCause::Rule { trigger } => format!("rule: {}", trigger),
+            SwitchCause::Focus { device } => format!("focus: {}", device),
+            SwitchCause::Remote { source } => format!("remote: {}", source),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwitchRecord {
+    pub previous_device_id: String, // 切换之前的默认设备
+    pub device_id: String,          // 切换到的设备
+    pub cause: SwitchCause,
+    pub timestamp: u64, // Unix 时间戳（秒）
+}
+
+#[derive(Debug, Default)]
+pub struct SwitchHistory {
+    records: Vec<SwitchRecord>,
+}
+
+impl SwitchHistory {
+    pub fn push(&mut self, previous_device_id: String, device_id: String, cause: SwitchCause) {
+        if previous_device_id == device_id {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.records.push(SwitchRecord {
+            previous_device_id,
+            device_id,
+            cause,
+            timestamp,
+        });
+
+        if self.records.len() > MAX_HISTORY {
+            self.records.remove(0);
+        }
+    }
+
+    // 弹出最近一条仍可恢复的记录；之前的设备已经不存在（比如耳机被拔掉）的记录会被跳过并丢弃
+    pub fn pop_available(&mut self, available_device_ids: &[String]) -> Option<SwitchRecord> {
+        while let Some(record) = self.records.pop() {
+            if available_device_ids.contains(&record.previous_device_id) {
+                return Some(record);
+            }
+            info!(
+                target: AUDIO,
+                "Skipping switch history entry, device no longer available: {}",
+                record.previous_device_id
+            );
+        }
+        None
+    }
+
+    pub fn records(&self) -> &[SwitchRecord] {
+        &self.records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn push(history: &mut SwitchHistory, previous: &str, device: &str) {
+        history.push(previous.to_string(), device.to_string(), SwitchCause::User);
+    }
+
+    #[test]
+    fn switching_to_the_same_device_is_not_recorded() {
+        let mut history = SwitchHistory::default();
+        push(&mut history, "speakers", "speakers");
+        assert!(history.records().is_empty());
+
+        push(&mut history, "speakers", "headphones");
+        let record = &history.records()[0];
+        assert_eq!(record.previous_device_id, "speakers");
+        assert_eq!(record.device_id, "headphones");
+        assert_eq!(record.cause, SwitchCause::User);
+        assert!(record.timestamp > 0);
+    }
+
+    #[test]
+    fn pop_returns_the_latest_record() {
+        let mut history = SwitchHistory::default();
+        push(&mut history, "speakers", "headphones");
+        push(&mut history, "headphones", "hdmi");
+
+        let available = ids(&["speakers", "headphones", "hdmi"]);
+        let record = history.pop_available(&available).unwrap();
+        assert_eq!(record.previous_device_id, "headphones");
+        let record = history.pop_available(&available).unwrap();
+        assert_eq!(record.previous_device_id, "speakers");
+        assert!(history.pop_available(&available).is_none());
+    }
+
+    #[test]
+    fn pop_drops_records_of_missing_devices() {
+        let mut history = SwitchHistory::default();
+        push(&mut history, "speakers", "headphones");
+        push(&mut history, "headphones", "usb");
+        push(&mut history, "usb", "hdmi");
+
+        // 耳机和 USB 声卡已经拔掉，跳过它们回到扬声器
+        let available = ids(&["speakers", "hdmi"]);
+        let record = history.pop_available(&available).unwrap();
+        assert_eq!(record.previous_device_id, "speakers");
+        assert_eq!(record.device_id, "headphones");
+        // 跳过的记录已经丢弃，设备重新出现也不会再恢复
+        assert!(history.records().is_empty());
+        assert!(
+            history
+                .pop_available(&ids(&["usb", "headphones"]))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn keeps_the_latest_records() {
+        let mut history = SwitchHistory::default();
+        for i in 0..MAX_HISTORY + 5 {
+            push(
+                &mut history,
+                &format!("device-{}", i),
+                &format!("device-{}", i + 1),
+            );
+        }
+
+        let records = history.records();
+        assert_eq!(records.len(), MAX_HISTORY);
+        assert_eq!(records[0].previous_device_id, "device-5");
+        assert_eq!(
+            records[MAX_HISTORY - 1].previous_device_id,
+            format!("device-{}", MAX_HISTORY + 4)
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// 偏好设置里选择监听窗口用的运行窗口列表：
+// 同一应用中标题相同的窗口合并为一项，按应用名排序，附带进程信息和图标。
+use crate::window_source::{WindowIcon, WindowInfo, WindowSource, process_path};
+use base64::Engine;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningWindow {
+    pub title: String,
+    pub process_name: String, // 可执行文件名，如 "obs64.exe"
+    pub process_path: String, // 可执行文件完整路径，未知时为空
+    pub pids: Vec<u32>,       // 所有同标题窗口所属的进程 id
+    pub class: String,        // 第一个窗口的类名
+    pub count: usize,         // 合并的窗口数量
+    pub icon: Option<String>, // PNG 图标的 data URL
+}
+
+// 枚举运行中的窗口并分组排序
+pub fn running_windows(source: &dyn WindowSource) -> Result<Vec<RunningWindow>, String> {
+    collect_windows(source, process_path)
+}
+
+// path_of 查询进程的可执行文件路径，每个进程只查一次
+fn collect_windows(
+    source: &dyn WindowSource,
+    path_of: impl Fn(u32) -> Option<String>,
+) -> Result<Vec<RunningWindow>, String> {
+    let windows = source.list_windows()?;
+    let mut paths: HashMap<u32, String> = HashMap::new();
+
+    let mut grouped = group_windows(windows, |pid| {
+        paths
+            .entry(pid)
+            .or_insert_with(|| path_of(pid).unwrap_or_default())
+            .clone()
+    });
+
+    for (window, id) in &mut grouped {
+        window.icon = source
+            .window_icon(*id)
+            .and_then(|icon| icon_data_url(&icon));
+    }
+
+    // 同一应用的窗口通常共用一个图标，取不到时沿用同应用其他窗口的图标
+    let mut app_icons: HashMap<String, String> = HashMap::new();
+    for (window, _) in &grouped {
+        if let Some(icon) = &window.icon {
+            app_icons
+                .entry(app_key(window))
+                .or_insert_with(|| icon.clone());
+        }
+    }
+    for (window, _) in &mut grouped {
+        if window.icon.is_none() {
+            window.icon = app_icons.get(&app_key(window)).cloned();
+        }
+    }
+
+    Ok(grouped.into_iter().map(|(window, _)| window).collect())
+}
+
+// 按 (应用, 标题) 合并窗口，返回每组及其第一个窗口的 id；
+// 按应用名排序（没有进程名的排在最后），同一应用内按标题排序
+fn group_windows(
+    windows: Vec<WindowInfo>,
+    mut path_of: impl FnMut(u32) -> String,
+) -> Vec<(RunningWindow, u64)> {
+    let mut grouped: Vec<(RunningWindow, u64)> = Vec::new();
+
+    for window in windows {
+        let existing = grouped.iter_mut().find(|(entry, _)| {
+            entry.title == window.title
+                && entry
+                    .process_name
+                    .eq_ignore_ascii_case(&window.process_name)
+        });
+        if let Some((entry, _)) = existing {
+            entry.count += 1;
+            if !entry.pids.contains(&window.pid) {
+                entry.pids.push(window.pid);
+            }
+            continue;
+        }
+
+        let process_path = path_of(window.pid);
+        grouped.push((
+            RunningWindow {
+                title: window.title,
+                process_name: window.process_name,
+                process_path,
+                pids: vec![window.pid],
+                class: window.class,
+                count: 1,
+                icon: None,
+            },
+            window.id,
+        ));
+    }
+
+    grouped.sort_by(|(a, _), (b, _)| {
+        a.process_name
+            .is_empty()
+            .cmp(&b.process_name.is_empty())
+            .then_with(|| app_key(a).cmp(&app_key(b)))
+            .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+    });
+    grouped
+}
+
+fn app_key(window: &RunningWindow) -> String {
+    window.process_name.to_lowercase()
+}
+
+// 把图标编码为 PNG 的 data URL，前端可直接用作 <img src>
+pub fn icon_data_url(icon: &WindowIcon) -> Option<String> {
+    let expected = (icon.width as usize)
+        .checked_mul(icon.height as usize)?
+        .checked_mul(4)?;
+    if icon.width == 0 || icon.height == 0 || icon.rgba.len() != expected {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, icon.width, icon.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().ok()?;
+        writer.write_image_data(&icon.rgba).ok()?;
+    }
+
+    Some(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(&bytes)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct FakeSource {
+        windows: Vec<WindowInfo>,
+        icons: HashMap<u64, WindowIcon>,
+    }
+
+    impl WindowSource for FakeSource {
+        fn list_windows(&self) -> Result<Vec<WindowInfo>, String> {
+            Ok(self.windows.clone())
+        }
+
+        fn window_icon(&self, id: u64) -> Option<WindowIcon> {
+            self.icons.get(&id).cloned()
+        }
+    }
+
+    fn window(id: u64, pid: u32, process_name: &str, title: &str) -> WindowInfo {
+        WindowInfo {
+            id,
+            title: title.to_string(),
+            class: format!("class-{}", id),
+            pid,
+            process_name: process_name.to_string(),
+        }
+    }
+
+    fn icon(width: u32, height: u32) -> WindowIcon {
+        WindowIcon {
+            width,
+            height,
+            rgba: vec![255; (width * height * 4) as usize],
+        }
+    }
+
+    fn names(grouped: &[(RunningWindow, u64)]) -> Vec<(&str, &str)> {
+        grouped
+            .iter()
+            .map(|(window, _)| (window.process_name.as_str(), window.title.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_process_and_title() {
+        let windows = vec![
+            window(1, 10, "Code.exe", "main.rs"),
+            window(2, 11, "code.exe", "main.rs"),
+            window(3, 10, "Code.exe", "main.rs"),
+            window(4, 10, "Code.exe", "lib.rs"),
+            window(5, 20, "notepad.exe", "main.rs"),
+        ];
+        let grouped = group_windows(windows, |pid| format!("/bin/{}", pid));
+        assert_eq!(
+            names(&grouped),
+            [
+                ("Code.exe", "lib.rs"),
+                ("Code.exe", "main.rs"),
+                ("notepad.exe", "main.rs"),
+            ]
+        );
+
+        // 进程名不区分大小写，保留第一个窗口的 id、类名和路径，进程 id 去重
+        let (merged, id) = &grouped[1];
+        assert_eq!(*id, 1);
+        assert_eq!(merged.count, 3);
+        assert_eq!(merged.pids, [10, 11]);
+        assert_eq!(merged.class, "class-1");
+        assert_eq!(merged.process_path, "/bin/10");
+    }
+
+    #[test]
+    fn sorts_by_app_then_title_with_unknown_processes_last() {
+        let windows = vec![
+            window(1, 0, "", "Alpha"),
+            window(2, 30, "zoom.exe", "Meeting"),
+            window(3, 10, "OBS64.exe", "scene"),
+            window(4, 10, "obs64.exe", "Projector"),
+            window(5, 20, "Discord.exe", "General"),
+        ];
+        let grouped = group_windows(windows, |_| String::new());
+        assert_eq!(
+            names(&grouped),
+            [
+                ("Discord.exe", "General"),
+                ("obs64.exe", "Projector"),
+                ("OBS64.exe", "scene"),
+                ("zoom.exe", "Meeting"),
+                ("", "Alpha"),
+            ]
+        );
+    }
+
+    #[test]
+    fn looks_up_each_process_path_once() {
+        let source = FakeSource {
+            windows: vec![
+                window(1, 10, "obs64.exe", "Scene"),
+                window(2, 10, "obs64.exe", "Projector"),
+                window(3, 20, "zoom.exe", "Meeting"),
+            ],
+            ..FakeSource::default()
+        };
+        let lookups = RefCell::new(Vec::new());
+        let windows = collect_windows(&source, |pid| {
+            lookups.borrow_mut().push(pid);
+            (pid == 10).then(|| "C:\\obs\\obs64.exe".to_string())
+        })
+        .unwrap();
+
+        assert_eq!(*lookups.borrow(), [10, 20]);
+        assert_eq!(windows[0].process_path, "C:\\obs\\obs64.exe");
+        assert_eq!(windows[2].process_path, "");
+    }
+
+    #[test]
+    fn windows_without_an_icon_borrow_one_from_the_same_app() {
+        let source = FakeSource {
+            windows: vec![
+                window(1, 10, "obs64.exe", "Scene"),
+                window(2, 11, "OBS64.exe", "Projector"),
+                window(3, 20, "zoom.exe", "Meeting"),
+            ],
+            icons: HashMap::from([(1, icon(2, 2))]),
+        };
+        let windows = collect_windows(&source, |_| None).unwrap();
+
+        let scene = windows
+            .iter()
+            .find(|window| window.title == "Scene")
+            .unwrap();
+        let projector = windows
+            .iter()
+            .find(|window| window.title == "Projector")
+            .unwrap();
+        let meeting = windows
+            .iter()
+            .find(|window| window.title == "Meeting")
+            .unwrap();
+        assert!(scene.icon.is_some());
+        assert_eq!(projector.icon, scene.icon);
+        assert_eq!(meeting.icon, None);
+    }
+
+    #[test]
+    fn icon_data_url_encodes_png() {
+        let url = icon_data_url(&icon(2, 3)).unwrap();
+        let encoded = url.strip_prefix("data:image/png;base64,").unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+
+        let decoder = png::Decoder::new(bytes.as_slice());
+        let reader = decoder.read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (2, 3));
+    }
+
+    #[test]
+    fn icon_data_url_rejects_bad_dimensions() {
+        assert_eq!(icon_data_url(&icon(0, 0)), None);
+        assert_eq!(
+            icon_data_url(&WindowIcon {
+                width: 0,
+                height: 4,
+                rgba: Vec::new(),
+            }),
+            None
+        );
+        // 像素数和宽高不符
+        let mut short = icon(2, 2);
+        short.rgba.pop();
+        assert_eq!(icon_data_url(&short), None);
+        assert_eq!(
+            icon_data_url(&WindowIcon {
+                width: u32::MAX,
+                height: u32::MAX,
+                rgba: Vec::new(),
+            }),
+            None
+        );
+    }
+}
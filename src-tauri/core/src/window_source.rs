@@ -0,0 +1,752 @@
+// 窗口来源抽象：把"枚举当前顶层窗口"从具体平台 API 中剥离出来，
+// 这样窗口监听逻辑既可以在 Windows / X11 上运行，也可以用脚本化的假数据驱动。
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// 单个顶层窗口的结构化信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowInfo {
+    pub id: u64,       // Windows 上为 HWND，X11 上为窗口 id
+    pub title: String, // 窗口标题
+    pub class: String, // 窗口类名（Windows 类名 / X11 WM_CLASS 的 class 部分）
+    pub pid: u32,      // 所属进程 id，未知时为 0
+    #[serde(default)]
+    pub process_name: String, // 可执行文件名，枚举时记录，进程退出后仍可用于匹配
+}
+
+// 窗口图标的原始像素，按行排列的 RGBA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowIcon {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+pub trait WindowSource: Send + Sync {
+    // 返回当前所有可见且有标题的顶层窗口
+    fn list_windows(&self) -> Result<Vec<WindowInfo>, String>;
+
+    // 返回当前前台（获得焦点）窗口的 id，无法获取时为 None
+    fn focused_window(&self) -> Option<u64> {
+        None
+    }
+
+    // 返回窗口的图标，无法获取时为 None
+    fn window_icon(&self, _id: u64) -> Option<WindowIcon> {
+        None
+    }
+}
+
+pub type SharedWindowSource = Arc<dyn WindowSource>;
+
+// 根据当前平台创建默认的窗口来源
+pub fn default_window_source() -> SharedWindowSource {
+    #[cfg(all(target_os = "windows", feature = "win32"))]
+    {
+        Arc::new(Win32WindowSource)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    {
+        use crate::logging::MONITOR;
+        use log::warn;
+
+        match X11WindowSource::connect(None) {
+            Ok(source) => Arc::new(source),
+            Err(e) => {
+                warn!(target: MONITOR, "Failed to connect to X11 display: {}", e);
+                Arc::new(ScriptedWindowSource::default())
+            }
+        }
+    }
+
+    #[cfg(not(any(
+        all(target_os = "windows", feature = "win32"),
+        all(target_os = "linux", feature = "x11")
+    )))]
+    {
+        Arc::new(ScriptedWindowSource::default())
+    }
+}
+
+// 根据 pid 获取可执行文件的完整路径
+pub fn process_path(pid: u32) -> Option<String> {
+    if pid == 0 {
+        return None;
+    }
+
+    #[cfg(all(target_os = "windows", feature = "win32"))]
+    {
+        use windows::Win32::{
+            Foundation::CloseHandle,
+            System::Threading::{
+                OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+                QueryFullProcessImageNameW,
+            },
+        };
+        use windows::core::PWSTR;
+
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+            let mut buffer = [0u16; 1024];
+            let mut size = buffer.len() as u32;
+            let result = QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut size,
+            );
+            let _ = CloseHandle(handle);
+            result.ok()?;
+
+            Some(String::from_utf16_lossy(&buffer[..size as usize]))
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_link(format!("/proc/{}/exe", pid))
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned())
+    }
+
+    #[cfg(not(any(all(target_os = "windows", feature = "win32"), target_os = "linux")))]
+    {
+        None
+    }
+}
+
+// 根据 pid 获取可执行文件名（不含路径），如 "obs64.exe"
+pub fn process_name(pid: u32) -> Option<String> {
+    if let Some(path) = process_path(pid) {
+        return path.rsplit(['\\', '/']).next().map(|name| name.to_string());
+    }
+
+    // Linux 上读不到 exe（如其他用户的进程）时回退到 comm，comm 会被截断为 15 个字符
+    #[cfg(target_os = "linux")]
+    if pid != 0 {
+        return std::fs::read_to_string(format!("/proc/{}/comm", pid))
+            .ok()
+            .map(|comm| comm.trim_end().to_string());
+    }
+
+    None
+}
+
+// ---------------------------------------------------------------------------
+// Windows: EnumWindows
+// ---------------------------------------------------------------------------
+
+#[cfg(all(target_os = "windows", feature = "win32"))]
+pub struct Win32WindowSource;
+
+#[cfg(all(target_os = "windows", feature = "win32"))]
+impl WindowSource for Win32WindowSource {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>, String> {
+        use windows::Win32::{Foundation::LPARAM, UI::WindowsAndMessaging::EnumWindows};
+
+        let mut windows = Vec::new();
+
+        unsafe {
+            EnumWindows(
+                Some(enum_windows_proc),
+                LPARAM(&mut windows as *mut Vec<WindowInfo> as isize),
+            )
+            .map_err(|e| format!("EnumWindows failed: {:?}", e))?;
+        }
+
+        Ok(windows)
+    }
+
+    fn focused_window(&self) -> Option<u64> {
+        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.0 == 0 {
+            None
+        } else {
+            Some(hwnd.0 as u64)
+        }
+    }
+
+    fn window_icon(&self, id: u64) -> Option<WindowIcon> {
+        use windows::Win32::{
+            Foundation::{HWND, LPARAM, WPARAM},
+            UI::WindowsAndMessaging::{
+                GCLP_HICON, GetClassLongPtrW, HICON, ICON_BIG, SMTO_ABORTIFHUNG,
+                SendMessageTimeoutW, WM_GETICON,
+            },
+        };
+
+        let hwnd = HWND(id as isize);
+        let mut result = 0usize;
+        unsafe {
+            // 先向窗口要大图标（窗口无响应时 100ms 后放弃），没有再用窗口类的图标
+            SendMessageTimeoutW(
+                hwnd,
+                WM_GETICON,
+                WPARAM(ICON_BIG as usize),
+                LPARAM(0),
+                SMTO_ABORTIFHUNG,
+                100,
+                Some(&mut result),
+            );
+            if result == 0 {
+                result = GetClassLongPtrW(hwnd, GCLP_HICON);
+            }
+            if result == 0 {
+                return None;
+            }
+            icon_pixels(HICON(result as isize))
+        }
+    }
+}
+
+// 把 HICON 的彩色位图读成 RGBA 像素
+#[cfg(all(target_os = "windows", feature = "win32"))]
+unsafe fn icon_pixels(icon: windows::Win32::UI::WindowsAndMessaging::HICON) -> Option<WindowIcon> {
+    use windows::Win32::{
+        Graphics::Gdi::{
+            BI_RGB, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, DeleteObject, GetDC,
+            GetDIBits, GetObjectW, ReleaseDC,
+        },
+        UI::WindowsAndMessaging::{GetIconInfo, ICONINFO},
+    };
+
+    let mut info = ICONINFO::default();
+    GetIconInfo(icon, &mut info).ok()?;
+
+    let mut bitmap = BITMAP::default();
+    let copied = GetObjectW(
+        info.hbmColor,
+        std::mem::size_of::<BITMAP>() as i32,
+        Some(&mut bitmap as *mut BITMAP as *mut std::ffi::c_void),
+    );
+
+    let mut pixels = None;
+    if copied > 0 && bitmap.bmWidth > 0 && bitmap.bmHeight > 0 {
+        let width = bitmap.bmWidth;
+        let height = bitmap.bmHeight;
+        let mut bmi = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height, // 负数表示自上而下的行顺序
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut buffer = vec![0u8; (width * height * 4) as usize];
+        let hdc = GetDC(None);
+        let lines = GetDIBits(
+            hdc,
+            info.hbmColor,
+            0,
+            height as u32,
+            Some(buffer.as_mut_ptr() as *mut std::ffi::c_void),
+            &mut bmi,
+            DIB_RGB_COLORS,
+        );
+        ReleaseDC(None, hdc);
+
+        if lines == height {
+            // BGRA -> RGBA；老式图标没有 alpha 通道时视为不透明
+            let has_alpha = buffer.chunks_exact(4).any(|pixel| pixel[3] != 0);
+            for pixel in buffer.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+                if !has_alpha {
+                    pixel[3] = 255;
+                }
+            }
+            pixels = Some(WindowIcon {
+                width: width as u32,
+                height: height as u32,
+                rgba: buffer,
+            });
+        }
+    }
+
+    // GetIconInfo 创建的位图需要调用方释放
+    let _ = DeleteObject(info.hbmColor);
+    let _ = DeleteObject(info.hbmMask);
+    pixels
+}
+
+// 窗口枚举回调函数
+#[cfg(all(target_os = "windows", feature = "win32"))]
+unsafe extern "system" fn enum_windows_proc(
+    hwnd: windows::Win32::Foundation::HWND,
+    lparam: windows::Win32::Foundation::LPARAM,
+) -> windows::Win32::Foundation::BOOL {
+    use windows::Win32::{
+        Foundation::BOOL,
+        UI::WindowsAndMessaging::{
+            GetClassNameW, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+        },
+    };
+
+    let windows = &mut *(lparam.0 as *mut Vec<WindowInfo>);
+
+    // 检查窗口是否可见
+    if IsWindowVisible(hwnd).as_bool() {
+        let mut buffer = [0u16; 256];
+        let length = GetWindowTextW(hwnd, &mut buffer);
+
+        if length > 0 {
+            let title = String::from_utf16_lossy(&buffer[..length as usize]);
+            if !title.trim().is_empty() {
+                let mut class_buffer = [0u16; 256];
+                let class_length = GetClassNameW(hwnd, &mut class_buffer);
+                let class = if class_length > 0 {
+                    String::from_utf16_lossy(&class_buffer[..class_length as usize])
+                } else {
+                    String::new()
+                };
+
+                let mut pid = 0u32;
+                GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+                windows.push(WindowInfo {
+                    id: hwnd.0 as u64,
+                    title,
+                    class,
+                    pid,
+                    process_name: process_name(pid).unwrap_or_default(),
+                });
+            }
+        }
+    }
+
+    BOOL::from(true) // 继续枚举
+}
+
+// ---------------------------------------------------------------------------
+// X11: 读取根窗口上的 _NET_CLIENT_LIST
+// ---------------------------------------------------------------------------
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub struct X11WindowSource {
+    conn: x11rb::rust_connection::RustConnection,
+    root: u32,
+    atoms: X11Atoms,
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+x11rb::atom_manager! {
+    X11Atoms: X11AtomsCookie {
+        _NET_CLIENT_LIST,
+        _NET_ACTIVE_WINDOW,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_ICON,
+        UTF8_STRING,
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+impl X11WindowSource {
+    // 连接到指定的 X display；None 表示使用 $DISPLAY（在 Xvfb 下测试时可传入 ":99"）
+    pub fn connect(display: Option<&str>) -> Result<Self, String> {
+        use x11rb::connection::Connection;
+
+        let (conn, screen_num) =
+            x11rb::connect(display).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+        let atoms = X11Atoms::new(&conn)
+            .map_err(|e| format!("Failed to intern atoms: {}", e))?
+            .reply()
+            .map_err(|e| format!("Failed to intern atoms: {}", e))?;
+
+        Ok(Self { conn, root, atoms })
+    }
+
+    fn window_title(&self, window: u32) -> Option<String> {
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        // 优先使用 UTF-8 的 _NET_WM_NAME，回退到传统的 WM_NAME
+        let net_name = self
+            .conn
+            .get_property(
+                false,
+                window,
+                self.atoms._NET_WM_NAME,
+                self.atoms.UTF8_STRING,
+                0,
+                1024,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        if !net_name.value.is_empty() {
+            return Some(String::from_utf8_lossy(&net_name.value).into_owned());
+        }
+
+        let wm_name = self
+            .conn
+            .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::ANY, 0, 1024)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some(String::from_utf8_lossy(&wm_name.value).into_owned())
+    }
+
+    fn window_class(&self, window: u32) -> String {
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        // WM_CLASS 是 "instance\0class\0"，取 class 部分
+        self.conn
+            .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| {
+                let parts: Vec<&[u8]> = reply.value.split(|b| *b == 0).collect();
+                let class = parts
+                    .get(1)
+                    .or_else(|| parts.first())
+                    .copied()
+                    .unwrap_or(&[]);
+                String::from_utf8_lossy(class).into_owned()
+            })
+            .unwrap_or_default()
+    }
+
+    fn window_pid(&self, window: u32) -> u32 {
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        self.conn
+            .get_property(
+                false,
+                window,
+                self.atoms._NET_WM_PID,
+                AtomEnum::CARDINAL,
+                0,
+                1,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().and_then(|mut values| values.next()))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "x11"))]
+impl WindowSource for X11WindowSource {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>, String> {
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                self.root,
+                self.atoms._NET_CLIENT_LIST,
+                AtomEnum::WINDOW,
+                0,
+                u32::MAX,
+            )
+            .map_err(|e| format!("Failed to query _NET_CLIENT_LIST: {}", e))?
+            .reply()
+            .map_err(|e| format!("Failed to query _NET_CLIENT_LIST: {}", e))?;
+
+        let client_list: Vec<u32> = match reply.value32() {
+            Some(values) => values.collect(),
+            None => return Ok(Vec::new()), // 没有 EWMH 兼容的窗口管理器
+        };
+
+        let mut windows = Vec::new();
+        for window in client_list {
+            let Some(title) = self.window_title(window) else {
+                continue; // 窗口可能已经被销毁
+            };
+            if title.trim().is_empty() {
+                continue;
+            }
+
+            let pid = self.window_pid(window);
+            windows.push(WindowInfo {
+                id: window as u64,
+                title,
+                class: self.window_class(window),
+                pid,
+                process_name: process_name(pid).unwrap_or_default(),
+            });
+        }
+
+        Ok(windows)
+    }
+
+    fn focused_window(&self) -> Option<u64> {
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        let window = self
+            .conn
+            .get_property(
+                false,
+                self.root,
+                self.atoms._NET_ACTIVE_WINDOW,
+                AtomEnum::WINDOW,
+                0,
+                1,
+            )
+            .ok()?
+            .reply()
+            .ok()?
+            .value32()?
+            .next()?;
+
+        // 0 表示当前没有活动窗口
+        if window == 0 {
+            None
+        } else {
+            Some(window as u64)
+        }
+    }
+
+    fn window_icon(&self, id: u64) -> Option<WindowIcon> {
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                id as u32,
+                self.atoms._NET_WM_ICON,
+                AtomEnum::CARDINAL,
+                0,
+                1 << 20,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        let values: Vec<u32> = reply.value32()?.collect();
+        pick_net_wm_icon(&values, PREFERRED_ICON_SIZE)
+    }
+}
+
+// 图标有多个尺寸时优先选择的边长
+#[cfg(all(target_os = "linux", feature = "x11"))]
+const PREFERRED_ICON_SIZE: u32 = 32;
+
+// _NET_WM_ICON 是若干个 [宽, 高, 宽*高 个 ARGB 像素] 依次排列，
+// 选择不小于 preferred 的最小尺寸，都比它小时选最大的
+#[cfg(all(target_os = "linux", feature = "x11"))]
+fn pick_net_wm_icon(values: &[u32], preferred: u32) -> Option<WindowIcon> {
+    let mut best: Option<(u32, u32, &[u32])> = None;
+    let mut rest = values;
+    while let [width, height, tail @ ..] = rest {
+        let (width, height) = (*width, *height);
+        let len = (width as usize).checked_mul(height as usize)?;
+        if width == 0 || height == 0 || len > tail.len() {
+            break;
+        }
+        let (pixels, next) = tail.split_at(len);
+        rest = next;
+
+        let better = match best {
+            None => true,
+            Some((best_width, _, _)) => {
+                if best_width >= preferred {
+                    width >= preferred && width < best_width
+                } else {
+                    width > best_width
+                }
+            }
+        };
+        if better {
+            best = Some((width, height, pixels));
+        }
+    }
+
+    let (width, height, pixels) = best?;
+    let rgba = pixels
+        .iter()
+        .flat_map(|argb| {
+            let [a, r, g, b] = argb.to_be_bytes();
+            [r, g, b, a]
+        })
+        .collect();
+    Some(WindowIcon {
+        width,
+        height,
+        rgba,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// 脚本化的假窗口来源，用于测试和无窗口系统的环境
+// ---------------------------------------------------------------------------
+
+// 每次调用 list_windows 依次返回一帧快照，脚本用完后一直返回最后一帧
+#[derive(Default)]
+pub struct ScriptedWindowSource {
+    frames: Mutex<VecDeque<Vec<WindowInfo>>>,
+    last: Mutex<Vec<WindowInfo>>,
+    focused: Mutex<Option<u64>>,
+}
+
+// 脚本接口供测试和集成调试使用
+impl ScriptedWindowSource {
+    pub fn new(frames: Vec<Vec<WindowInfo>>) -> Self {
+        Self {
+            frames: Mutex::new(frames.into()),
+            last: Mutex::new(Vec::new()),
+            focused: Mutex::new(None),
+        }
+    }
+
+    // 设置之后 focused_window 返回的窗口 id
+    pub fn set_focused(&self, id: Option<u64>) {
+        if let Ok(mut focused) = self.focused.lock() {
+            *focused = id;
+        }
+    }
+
+    // 在脚本末尾追加一帧
+    pub fn push_frame(&self, frame: Vec<WindowInfo>) {
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.push_back(frame);
+        }
+    }
+}
+
+impl WindowSource for ScriptedWindowSource {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>, String> {
+        let mut frames = self
+            .frames
+            .lock()
+            .map_err(|_| "Scripted window source poisoned".to_string())?;
+        let mut last = self
+            .last
+            .lock()
+            .map_err(|_| "Scripted window source poisoned".to_string())?;
+
+        if let Some(frame) = frames.pop_front() {
+            *last = frame;
+        }
+
+        Ok(last.clone())
+    }
+
+    fn focused_window(&self) -> Option<u64> {
+        self.focused.lock().ok().and_then(|focused| *focused)
+    }
+}
+
+// 需要运行在 :99 上的 X server：Xvfb :99 & cargo test -p audiocat-core -- --ignored x11
+#[cfg(all(test, target_os = "linux", feature = "x11"))]
+mod tests {
+    use super::*;
+    use crate::monitor::WindowMatcher;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        AtomEnum, ConnectionExt, CreateWindowAux, PropMode, WindowClass,
+    };
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    const DISPLAY: &str = ":99";
+
+    // 创建一个顶层窗口并映射，title 为空时不设置标题
+    fn create_window(conn: &RustConnection, root: u32, atoms: &X11Atoms, title: &str) -> u32 {
+        let window = conn.generate_id().unwrap();
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            100,
+            100,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        if !title.is_empty() {
+            conn.change_property8(
+                PropMode::REPLACE,
+                window,
+                atoms._NET_WM_NAME,
+                atoms.UTF8_STRING,
+                title.as_bytes(),
+            )
+            .unwrap();
+        }
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            b"audiocat-test\0AudioCatTest\0",
+        )
+        .unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            atoms._NET_WM_PID,
+            AtomEnum::CARDINAL,
+            &[std::process::id()],
+        )
+        .unwrap();
+        conn.map_window(window).unwrap();
+        window
+    }
+
+    #[test]
+    #[ignore = "needs an X server on :99"]
+    fn x11_lists_mapped_windows() {
+        let (conn, screen_num) = x11rb::connect(Some(DISPLAY)).unwrap();
+        let root = conn.setup().roots[screen_num].root;
+        let atoms = X11Atoms::new(&conn).unwrap().reply().unwrap();
+
+        let titled = create_window(&conn, root, &atoms, "AudioCat 测试窗口");
+        let untitled = create_window(&conn, root, &atoms, "");
+        // Xvfb 没有窗口管理器，由测试自己维护 EWMH 的窗口列表和活动窗口
+        conn.change_property32(
+            PropMode::REPLACE,
+            root,
+            atoms._NET_CLIENT_LIST,
+            AtomEnum::WINDOW,
+            &[titled, untitled],
+        )
+        .unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            root,
+            atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW,
+            &[titled],
+        )
+        .unwrap();
+        conn.sync().unwrap();
+
+        let source = X11WindowSource::connect(Some(DISPLAY)).unwrap();
+        let windows = source.list_windows().unwrap();
+        let window = windows
+            .iter()
+            .find(|window| window.id == titled as u64)
+            .expect("mapped window is listed");
+        assert_eq!(window.title, "AudioCat 测试窗口");
+        assert_eq!(window.class, "AudioCatTest");
+        assert_eq!(window.pid, std::process::id());
+        assert!(!window.process_name.is_empty());
+        assert!(!windows.iter().any(|window| window.id == untitled as u64));
+        assert_eq!(source.focused_window(), Some(titled as u64));
+
+        assert!(WindowMatcher::Class("audiocattest".to_string()).matches(window));
+        assert!(!WindowMatcher::Class("audiocat-test".to_string()).matches(window));
+
+        conn.delete_property(root, atoms._NET_CLIENT_LIST).unwrap();
+        conn.delete_property(root, atoms._NET_ACTIVE_WINDOW)
+            .unwrap();
+        conn.destroy_window(titled).unwrap();
+        conn.destroy_window(untitled).unwrap();
+        conn.sync().unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+// 自动隐藏倒计时：规则显示切换器后开始倒计时，结束时隐藏窗口
+// 倒计时由后端线程驱动，前端只根据 auto-hide-tick 事件显示剩余秒数。
+use serde::Serialize;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+// 鼠标离开窗口后至少再等这么久才隐藏
+const HOVER_EXTENSION: Duration = Duration::from_secs(2);
+
+pub enum AutoHideCommand {
+    Start(Duration),
+    Cancel,
+    Hover(bool), // 鼠标进入/离开窗口
+}
+
+pub type AutoHideSender = Sender<AutoHideCommand>;
+
+// 通过 auto-hide-tick 事件发给前端的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AutoHideStatus {
+    pub active: bool,   // 是否正在倒计时
+    pub remaining: u64, // 剩余秒数（向上取整）
+    pub paused: bool,   // 鼠标悬停时暂停
+}
+
+#[derive(Debug, Default)]
+pub struct AutoHideTimer {
+    active: bool,
+    remaining: Duration, // 暂停时剩余的时间，运行时为 running_since 那一刻剩余的时间
+    running_since: Option<Instant>, // None 表示暂停
+    hovered: bool,
+}
+
+impl AutoHideTimer {
+    pub fn start(&mut self, delay: Duration, now: Instant) {
+        self.active = true;
+        self.remaining = delay;
+        // 鼠标已经在窗口上时先暂停，离开后再开始计时
+        self.running_since = if self.hovered { None } else { Some(now) };
+    }
+
+    pub fn cancel(&mut self) {
+        self.active = false;
+        self.running_since = None;
+    }
+
+    pub fn set_hovered(&mut self, hovered: bool, now: Instant) {
+        if hovered == self.hovered {
+            return;
+        }
+        self.hovered = hovered;
+        if !self.active {
+            return;
+        }
+
+        if hovered {
+            self.remaining = self.remaining(now);
+            self.running_since = None;
+        } else {
+            self.remaining = self.remaining.max(HOVER_EXTENSION);
+            self.running_since = Some(now);
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        match self.running_since {
+            Some(since) => self
+                .remaining
+                .saturating_sub(now.saturating_duration_since(since)),
+            None => self.remaining,
+        }
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.active && self.running_since.is_some() && self.remaining(now).is_zero()
+    }
+
+    pub fn status(&self, now: Instant) -> AutoHideStatus {
+        let remaining = self.remaining(now);
+        let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        AutoHideStatus {
+            active: self.active,
+            remaining: if self.active { seconds } else { 0 },
+            paused: self.active && self.running_since.is_none(),
+        }
+    }
+
+    // 距离剩余秒数下一次变化的时间；没有在计时时返回 None
+    pub fn next_tick(&self, now: Instant) -> Option<Duration> {
+        if !self.active {
+            return None;
+        }
+        self.running_since?;
+
+        let remaining = self.remaining(now);
+        let fraction = Duration::from_nanos(u64::from(remaining.subsec_nanos()));
+        Some(if fraction.is_zero() {
+            remaining.min(Duration::from_secs(1))
+        } else {
+            fraction
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Duration {
+        Duration::from_secs_f64(seconds)
+    }
+
+    fn status(active: bool, remaining: u64, paused: bool) -> AutoHideStatus {
+        AutoHideStatus {
+            active,
+            remaining,
+            paused,
+        }
+    }
+
+    #[test]
+    fn counts_down_and_expires() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        assert_eq!(timer.status(start), status(false, 0, false));
+        assert!(!timer.is_expired(start));
+
+        timer.start(secs(5.0), start);
+        assert!(timer.is_active());
+        assert_eq!(timer.status(start), status(true, 5, false));
+        assert!(!timer.is_expired(start + secs(4.9)));
+        assert!(timer.is_expired(start + secs(5.0)));
+        assert!(timer.is_expired(start + secs(60.0)));
+    }
+
+    #[test]
+    fn status_rounds_remaining_seconds_up() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        timer.start(secs(3.0), start);
+
+        assert_eq!(timer.status(start + secs(0.1)).remaining, 3);
+        assert_eq!(timer.status(start + secs(1.0)).remaining, 2);
+        assert_eq!(timer.status(start + secs(2.999)).remaining, 1);
+        assert_eq!(timer.status(start + secs(3.0)).remaining, 0);
+    }
+
+    #[test]
+    fn hovering_pauses_the_countdown() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        timer.start(secs(5.0), start);
+
+        timer.set_hovered(true, start + secs(1.0));
+        assert_eq!(timer.status(start + secs(30.0)), status(true, 4, true));
+        assert!(!timer.is_expired(start + secs(30.0)));
+
+        // 离开时剩余时间还多，从暂停的地方继续
+        timer.set_hovered(false, start + secs(30.0));
+        assert_eq!(timer.remaining(start + secs(30.0)), secs(4.0));
+        assert!(timer.is_expired(start + secs(34.0)));
+    }
+
+    #[test]
+    fn leaving_the_window_waits_at_least_the_hover_extension() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        timer.start(secs(1.0), start);
+
+        timer.set_hovered(true, start + secs(0.5));
+        timer.set_hovered(false, start + secs(10.0));
+        assert_eq!(timer.remaining(start + secs(10.0)), HOVER_EXTENSION);
+        assert!(!timer.is_expired(start + secs(11.0)));
+        assert!(timer.is_expired(start + secs(10.0) + HOVER_EXTENSION));
+    }
+
+    #[test]
+    fn starting_while_hovered_waits_until_the_mouse_leaves() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        timer.set_hovered(true, start);
+
+        timer.start(secs(5.0), start);
+        assert_eq!(timer.status(start + secs(60.0)), status(true, 5, true));
+        assert_eq!(timer.next_tick(start), None);
+
+        timer.set_hovered(false, start + secs(60.0));
+        assert!(timer.is_expired(start + secs(65.0)));
+    }
+
+    #[test]
+    fn hovering_without_a_countdown_changes_nothing() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        timer.set_hovered(true, start);
+        timer.set_hovered(false, start + secs(1.0));
+        assert_eq!(timer.status(start + secs(1.0)), status(false, 0, false));
+
+        // 重复的离开事件不会再延长
+        timer.start(secs(1.0), start + secs(1.0));
+        timer.set_hovered(false, start + secs(1.5));
+        assert!(timer.is_expired(start + secs(2.0)));
+    }
+
+    #[test]
+    fn cancel_stops_the_countdown() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        timer.start(secs(1.0), start);
+        timer.cancel();
+
+        assert!(!timer.is_active());
+        assert!(!timer.is_expired(start + secs(10.0)));
+        assert_eq!(timer.status(start), status(false, 0, false));
+        assert_eq!(timer.next_tick(start), None);
+    }
+
+    #[test]
+    fn next_tick_is_when_the_shown_seconds_change() {
+        let start = Instant::now();
+        let mut timer = AutoHideTimer::default();
+        timer.start(secs(3.0), start);
+
+        assert_eq!(timer.next_tick(start), Some(secs(1.0)));
+        assert_eq!(timer.next_tick(start + secs(0.25)), Some(secs(0.75)));
+        assert_eq!(timer.next_tick(start + secs(2.5)), Some(secs(0.5)));
+        assert_eq!(timer.next_tick(start + secs(3.0)), Some(Duration::ZERO));
+
+        timer.start(secs(0.5), start);
+        assert_eq!(timer.next_tick(start), Some(secs(0.5)));
+    }
+}
@@ -0,0 +1,650 @@
+// 前端通过 invoke 调用的 Tauri 命令，在 lib.rs 的 invoke_handler 中注册
+use crate::audio::{AudioBackend, AudioDevice};
+use crate::auto_hide::AutoHideCommand;
+use crate::config::{
+    HttpApiConfig, MqttConfig, OscConfig, WindowPosition, get_window_config_path,
+    load_window_config, read_window_config, save_window_config,
+};
+use crate::error::{AppError, AppResult};
+use crate::event_history::{EventKind, HistoryEvent, HistoryFilter};
+use crate::focus_switch::FocusSwitchConfig;
+use crate::http_api;
+use crate::logging::{self, CONFIG, MONITOR, TRAY};
+use crate::monitor::{
+    MonitorRule, describe_firing, get_current_windows, get_focused_window, pop_revert_record,
+    record_revert, record_switch, register_rule_hotkeys, rule_context,
+};
+use crate::mqtt;
+use crate::osc;
+use crate::process_monitor::ProcessRule;
+use crate::rules::{ActionOutcome, Rule, RuleFiring};
+use crate::schedule::ScheduleRule;
+use crate::state::{
+    SharedState, audio, event_history, main_window, record_event, window_source, with_state,
+};
+use crate::switch_history::{SwitchCause, SwitchRecord};
+use crate::tray::{send_auto_hide, show_switcher};
+use crate::window_picker::{RunningWindow, running_windows};
+use crate::window_source::WindowInfo;
+use log::info;
+use tauri::{Emitter, Manager};
+
+// 保存窗口位置
+#[tauri::command]
+pub fn save_window_position(
+    app_handle: tauri::AppHandle,
+    window_label: String,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> AppResult<String> {
+    let mut current_config = read_window_config(&app_handle)?;
+
+    let position = WindowPosition {
+        x,
+        y,
+        width,
+        height,
+    };
+
+    match window_label.as_str() {
+        "main" => current_config.main_window_position = Some(position),
+        "preference" => current_config.preference_window_position = Some(position),
+        _ => {
+            return Err(AppError::invalid_input(format!(
+                "Unknown window label: {}",
+                window_label
+            )));
+        }
+    }
+
+    save_window_config(&app_handle, &current_config)?;
+
+    Ok(format!("Saved position for window: {}", window_label))
+}
+
+// 获取窗口位置
+#[tauri::command]
+pub fn get_window_position(
+    app_handle: tauri::AppHandle,
+    window_label: String,
+) -> AppResult<Option<WindowPosition>> {
+    let config = load_window_config(&app_handle);
+
+    match window_label.as_str() {
+        "main" => Ok(config.main_window_position),
+        "preference" => Ok(config.preference_window_position),
+        _ => Err(AppError::invalid_input(format!(
+            "Unknown window label: {}",
+            window_label
+        ))),
+    }
+}
+
+// 定义一个 Tauri 命令，用于设置音频设备
+#[tauri::command]
+pub async fn set_audio_device(
+    app_handle: tauri::AppHandle,
+    device_id: String,
+) -> AppResult<String> {
+    let outcome = audio(&app_handle)?
+        .call(move |backend| backend.set_default(&device_id))
+        .await?;
+    Ok(record_switch(&app_handle, outcome, SwitchCause::User))
+}
+
+// 恢复到上一次切换之前的设备，跳过已经不存在的设备
+#[tauri::command]
+pub async fn revert_audio_device(app_handle: tauri::AppHandle) -> AppResult<String> {
+    let audio = audio(&app_handle)?;
+    let devices = audio.call(AudioBackend::devices).await?;
+    let record = pop_revert_record(&app_handle, &devices)?;
+
+    let device_id = record.previous_device_id.clone();
+    let outcome = audio
+        .call(move |backend| backend.set_default(&device_id))
+        .await?;
+    Ok(record_revert(&app_handle, &record, &outcome))
+}
+
+// 获取设备切换历史（最早的在前）
+#[tauri::command]
+pub fn get_switch_history(app_handle: tauri::AppHandle) -> AppResult<Vec<SwitchRecord>> {
+    with_state(&app_handle, |app_state| {
+        app_state.switch_history.records().to_vec()
+    })
+}
+
+// 查询事件历史（最早的在前），filter 省略时返回全部
+#[tauri::command]
+pub fn get_event_history(
+    app_handle: tauri::AppHandle,
+    filter: Option<HistoryFilter>,
+) -> AppResult<Vec<HistoryEvent>> {
+    let history = event_history(&app_handle)?;
+    let history = history
+        .lock()
+        .map_err(|_| AppError::internal("Failed to access event history"))?;
+    Ok(history.query(&filter.unwrap_or_default()))
+}
+
+// 清空事件历史（包括文件）
+#[tauri::command]
+pub fn clear_event_history(app_handle: tauri::AppHandle) -> AppResult<String> {
+    let history = event_history(&app_handle)?;
+    let mut history = history
+        .lock()
+        .map_err(|_| AppError::internal("Failed to access event history"))?;
+    history.clear().map_err(AppError::config_io)?;
+    Ok("Event history cleared".to_string())
+}
+
+// 运行时调整日志级别，并保存到配置文件
+#[tauri::command]
+pub fn set_log_level(app_handle: tauri::AppHandle, level: String) -> AppResult<String> {
+    logging::set_level(logging::parse_level(&level).map_err(AppError::invalid_input)?);
+    let level = logging::current_level();
+
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.log_level = level.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    info!(target: CONFIG, "Log level set to {}", level);
+    Ok(format!("Log level set to {}", level))
+}
+
+// 获取当前日志级别
+#[tauri::command]
+pub fn get_log_level() -> String {
+    logging::current_level()
+}
+
+// 获取最近的日志（默认 200 行，最早的在前），用于关于页面
+#[tauri::command]
+pub fn get_log_tail(lines: Option<usize>) -> AppResult<Vec<String>> {
+    logging::tail(lines.unwrap_or(200)).map_err(AppError::config_io)
+}
+
+// 定义一个 Tauri 命令，用于获取当前音频设备
+#[tauri::command]
+pub async fn get_current_audio_device(app_handle: tauri::AppHandle) -> AppResult<AudioDevice> {
+    audio(&app_handle)?.call(AudioBackend::current_device).await
+}
+
+// 定义一个 Tauri 命令，用于获取所有可用的音频输出设备列表
+#[tauri::command]
+pub async fn get_audio_output_devices(app_handle: tauri::AppHandle) -> AppResult<Vec<AudioDevice>> {
+    audio(&app_handle)?.call(AudioBackend::devices).await
+}
+
+// 定义一个 Tauri 命令，用于创建应用程序数据目录
+#[tauri::command]
+pub fn create_app_data_dir(path: String) -> AppResult<()> {
+    std::fs::create_dir_all(path).map_err(|e| AppError::io("Failed to create directory", &e))
+}
+
+// 测试拖动功能的命令
+#[tauri::command]
+pub fn test_drag_functionality() -> AppResult<String> {
+    Ok("Drag functionality test".to_string())
+}
+
+// 设置窗口置顶状态
+#[tauri::command]
+pub fn set_window_pinned(app_handle: tauri::AppHandle, pinned: bool) -> AppResult<String> {
+    let window = main_window(&app_handle)?;
+
+    // 更新全局状态
+    with_state(&app_handle, |app_state| app_state.is_pinned = pinned)?;
+
+    // 设置窗口置顶状态
+    window
+        .set_always_on_top(pinned)
+        .map_err(|e| AppError::internal("Failed to set window always on top").with_detail(e))?;
+
+    // 手动置顶/取消置顶也算用户交互，结束正在进行的自动隐藏倒计时
+    send_auto_hide(&app_handle, AutoHideCommand::Cancel);
+    let _ = app_handle.emit("pin-mode-changed", pinned);
+
+    if pinned {
+        info!(target: TRAY, "Window pinned to top");
+    } else {
+        info!(target: TRAY, "Window unpinned from top");
+    }
+
+    Ok(format!(
+        "Window {}",
+        if pinned { "pinned" } else { "unpinned" }
+    ))
+}
+
+// 获取当前窗口置顶状态
+#[tauri::command]
+pub fn get_window_pinned(app_handle: tauri::AppHandle) -> AppResult<bool> {
+    with_state(&app_handle, |app_state| app_state.is_pinned)
+}
+
+// 获取当前运行的窗口列表（按应用分组排序，带进程信息和图标），供选择监听窗口
+#[tauri::command]
+pub fn get_running_windows(app_handle: tauri::AppHandle) -> AppResult<Vec<RunningWindow>> {
+    let source = window_source(&app_handle)?;
+    running_windows(source.as_ref())
+        .map_err(|e| AppError::backend("Failed to enumerate windows").with_detail(e))
+}
+
+// 获取当前运行窗口的结构化信息（id、标题、类名、pid）
+#[tauri::command]
+pub fn get_running_window_info(app_handle: tauri::AppHandle) -> AppResult<Vec<WindowInfo>> {
+    window_source(&app_handle)?
+        .list_windows()
+        .map_err(|e| AppError::backend("Failed to enumerate windows").with_detail(e))
+}
+
+// 设置监听的窗口列表
+#[tauri::command]
+pub fn set_monitored_windows(
+    app_handle: tauri::AppHandle,
+    windows: Vec<MonitorRule>,
+) -> AppResult<String> {
+    // 先校验规则（正则、通配符等），避免保存无法匹配的规则
+    for rule in &windows {
+        rule.validate().map_err(AppError::invalid_input)?;
+    }
+
+    // 更新内存中的状态
+    with_state(&app_handle, |app_state| {
+        app_state.monitored_windows = windows.clone()
+    })?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.monitored_windows = windows.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    Ok(format!("Set {} monitored windows", windows.len()))
+}
+
+// 获取监听的窗口列表
+#[tauri::command]
+pub fn get_monitored_windows(app_handle: tauri::AppHandle) -> AppResult<Vec<MonitorRule>> {
+    with_state(&app_handle, |app_state| app_state.monitored_windows.clone())
+}
+
+// 设置自动隐藏延迟
+#[tauri::command]
+pub fn set_auto_hide_delay(app_handle: tauri::AppHandle, delay: u64) -> AppResult<String> {
+    // 更新内存中的状态
+    with_state(&app_handle, |app_state| app_state.auto_hide_delay = delay)?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.auto_hide_delay = delay;
+    save_window_config(&app_handle, &current_config)?;
+
+    Ok(format!("Set auto hide delay to {} seconds", delay))
+}
+
+// 获取自动隐藏延迟
+#[tauri::command]
+pub fn get_auto_hide_delay(app_handle: tauri::AppHandle) -> AppResult<u64> {
+    with_state(&app_handle, |app_state| app_state.auto_hide_delay)
+}
+
+// 取消自动隐藏倒计时（用户与切换器交互时由前端调用）
+#[tauri::command]
+pub fn cancel_auto_hide(app_handle: tauri::AppHandle) {
+    send_auto_hide(&app_handle, AutoHideCommand::Cancel);
+}
+
+// 鼠标悬停在切换器上时暂停倒计时，离开后继续
+#[tauri::command]
+pub fn set_auto_hide_hover(app_handle: tauri::AppHandle, hovered: bool) {
+    send_auto_hide(&app_handle, AutoHideCommand::Hover(hovered));
+}
+
+// 偏好设置中测试自动隐藏
+#[tauri::command]
+pub fn test_auto_hide(app_handle: tauri::AppHandle) -> AppResult<()> {
+    show_switcher(&app_handle, "测试窗口")
+}
+
+// 开始监听窗口
+#[tauri::command]
+pub fn start_window_monitoring(app_handle: tauri::AppHandle) -> AppResult<String> {
+    // 初始化当前窗口列表
+    let windows = get_current_windows(&app_handle);
+    let focused = get_focused_window(&app_handle);
+    with_state(&app_handle, |app_state| {
+        app_state.monitoring_active = true;
+        app_state.window_tracker.reset(windows, focused);
+    })?;
+    Ok("Window monitoring started".to_string())
+}
+
+// 停止监听窗口
+#[tauri::command]
+pub fn stop_window_monitoring(app_handle: tauri::AppHandle) -> AppResult<String> {
+    with_state(&app_handle, |app_state| app_state.monitoring_active = false)?;
+    Ok("Window monitoring stopped".to_string())
+}
+
+// 设置进程监听规则
+#[tauri::command]
+pub fn set_process_rules(
+    app_handle: tauri::AppHandle,
+    rules: Vec<ProcessRule>,
+) -> AppResult<String> {
+    for rule in &rules {
+        rule.validate().map_err(AppError::invalid_input)?;
+    }
+
+    // 更新内存中的状态，规则变化后重新建立进程快照
+    with_state(&app_handle, |app_state| {
+        app_state.process_rules = rules.clone();
+        app_state.process_tracker.reset();
+    })?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.process_rules = rules.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    Ok(format!("Set {} process rules", rules.len()))
+}
+
+// 获取进程监听规则
+#[tauri::command]
+pub fn get_process_rules(app_handle: tauri::AppHandle) -> AppResult<Vec<ProcessRule>> {
+    with_state(&app_handle, |app_state| app_state.process_rules.clone())
+}
+
+// 设置定时规则
+#[tauri::command]
+pub fn set_schedule_rules(
+    app_handle: tauri::AppHandle,
+    rules: Vec<ScheduleRule>,
+) -> AppResult<String> {
+    for rule in &rules {
+        rule.validate().map_err(AppError::invalid_input)?;
+    }
+
+    with_state(&app_handle, |app_state| {
+        app_state.schedule_rules = rules.clone()
+    })?;
+
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.schedule_rules = rules.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    Ok(format!("Set {} schedule rules", rules.len()))
+}
+
+// 获取定时规则
+#[tauri::command]
+pub fn get_schedule_rules(app_handle: tauri::AppHandle) -> AppResult<Vec<ScheduleRule>> {
+    with_state(&app_handle, |app_state| app_state.schedule_rules.clone())
+}
+
+// 设置规则引擎的规则
+#[tauri::command]
+pub fn set_rules(app_handle: tauri::AppHandle, rules: Vec<Rule>) -> AppResult<String> {
+    for rule in &rules {
+        rule.validate().map_err(|e| {
+            AppError::invalid_input(format!("Invalid rule '{}': {}", rule.label(), e))
+        })?;
+    }
+
+    with_state(&app_handle, |app_state| {
+        app_state.rules = rules.clone();
+        app_state.process_tracker.reset();
+    })?;
+
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.rules = rules.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    register_rule_hotkeys(&app_handle);
+    Ok(format!("Set {} rules", rules.len()))
+}
+
+// 获取规则引擎的规则（不包含由窗口/进程/定时规则转换来的规则）
+#[tauri::command]
+pub fn get_rules(app_handle: tauri::AppHandle) -> AppResult<Vec<Rule>> {
+    with_state(&app_handle, |app_state| app_state.rules.clone())
+}
+
+// 获取规则触发记录（最早的在前）
+#[tauri::command]
+pub fn get_rule_log(app_handle: tauri::AppHandle) -> AppResult<Vec<RuleFiring>> {
+    with_state(&app_handle, |app_state| {
+        app_state.rule_log.firings().to_vec()
+    })
+}
+
+// 试运行规则：校验规则并按当前状态检查条件，列出会执行的动作但不执行
+#[tauri::command]
+pub async fn test_rule(app_handle: tauri::AppHandle, rule: Rule) -> AppResult<RuleFiring> {
+    rule.validate().map_err(AppError::invalid_input)?;
+
+    let current_device = audio(&app_handle)?
+        .call(AudioBackend::current_device)
+        .await
+        .ok();
+    let mut firing = RuleFiring::new(&rule, "test", true);
+    firing.skipped_by = rule
+        .failed_condition(&rule_context(&app_handle, current_device))
+        .map(|condition| condition.describe());
+
+    match &firing.skipped_by {
+        Some(condition) => info!(
+            target: MONITOR,
+            "Dry run of rule '{}': skipped, condition not met: {}",
+            rule.label(),
+            condition
+        ),
+        None => {
+            info!(
+                target: MONITOR,
+                "Dry run of rule '{}': would run {} actions",
+                rule.label(),
+                rule.actions.len()
+            );
+            firing.actions = rule
+                .actions
+                .iter()
+                .map(|action| ActionOutcome {
+                    action: action.describe(),
+                    error: None,
+                })
+                .collect();
+        }
+    }
+
+    if let Some(state) = app_handle.try_state::<SharedState>() {
+        if let Ok(mut app_state) = state.lock() {
+            app_state.rule_log.push(firing.clone());
+        }
+    }
+    record_event(
+        &app_handle,
+        EventKind::Rule,
+        describe_firing(&firing),
+        &firing,
+    );
+    Ok(firing)
+}
+
+// 获取焦点跟随配置
+#[tauri::command]
+pub fn get_focus_switch_config(app_handle: tauri::AppHandle) -> AppResult<FocusSwitchConfig> {
+    with_state(&app_handle, |app_state| app_state.focus_switch.clone())
+}
+
+// 设置焦点跟随配置
+#[tauri::command]
+pub fn set_focus_switch_config(
+    app_handle: tauri::AppHandle,
+    config: FocusSwitchConfig,
+) -> AppResult<String> {
+    // 更新内存中的状态
+    with_state(&app_handle, |app_state| {
+        app_state.focus_switch = config.clone()
+    })?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.focus_switch = config;
+    save_window_config(&app_handle, &current_config)?;
+
+    Ok("Focus switch config saved".to_string())
+}
+
+// 获取本地 HTTP 接口配置
+#[tauri::command]
+pub fn get_http_api_config(app_handle: tauri::AppHandle) -> AppResult<HttpApiConfig> {
+    with_state(&app_handle, |app_state| app_state.http_api.clone())
+}
+
+// 设置本地 HTTP 接口配置并按新配置启动或停止服务器；开启时 token 为空会重新生成，
+// 返回保存后的配置（包括新的 token）
+#[tauri::command]
+pub fn set_http_api_config(
+    app_handle: tauri::AppHandle,
+    mut config: HttpApiConfig,
+) -> AppResult<HttpApiConfig> {
+    config.validate().map_err(AppError::invalid_input)?;
+    config.ensure_token()?;
+
+    // 更新内存中的状态
+    with_state(&app_handle, |app_state| app_state.http_api = config.clone())?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.http_api = config.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    http_api::apply(&app_handle, &config);
+    info!(target: CONFIG, "HTTP API config saved: {:?}", config);
+    Ok(config)
+}
+
+// 获取 MQTT 桥接配置
+#[tauri::command]
+pub fn get_mqtt_config(app_handle: tauri::AppHandle) -> AppResult<MqttConfig> {
+    with_state(&app_handle, |app_state| app_state.mqtt.clone())
+}
+
+// 设置 MQTT 桥接配置并按新配置连接或断开 broker
+#[tauri::command]
+pub fn set_mqtt_config(app_handle: tauri::AppHandle, config: MqttConfig) -> AppResult<String> {
+    config.validate().map_err(AppError::invalid_input)?;
+
+    // 更新内存中的状态
+    with_state(&app_handle, |app_state| app_state.mqtt = config.clone())?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.mqtt = config.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    mqtt::apply(&app_handle, &config);
+    info!(target: CONFIG, "MQTT config saved: {:?}", config);
+    Ok("MQTT config saved".to_string())
+}
+
+// 获取 OSC 服务器配置
+#[tauri::command]
+pub fn get_osc_config(app_handle: tauri::AppHandle) -> AppResult<OscConfig> {
+    with_state(&app_handle, |app_state| app_state.osc.clone())
+}
+
+// 设置 OSC 服务器配置并按新配置启动、停止或换端口重启
+#[tauri::command]
+pub fn set_osc_config(app_handle: tauri::AppHandle, config: OscConfig) -> AppResult<String> {
+    config.validate().map_err(AppError::invalid_input)?;
+
+    // 更新内存中的状态
+    with_state(&app_handle, |app_state| app_state.osc = config.clone())?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.osc = config.clone();
+    save_window_config(&app_handle, &current_config)?;
+
+    osc::apply(&app_handle, &config);
+    info!(target: CONFIG, "OSC config saved: {:?}", config);
+    Ok("OSC config saved".to_string())
+}
+
+// 设置深色模式
+#[tauri::command]
+pub fn set_dark_mode(app_handle: tauri::AppHandle, dark_mode: bool) -> AppResult<String> {
+    // 更新内存中的状态
+    with_state(&app_handle, |app_state| app_state.dark_mode = dark_mode)?;
+
+    // 保存到配置文件
+    let mut current_config = read_window_config(&app_handle)?;
+    current_config.dark_mode = dark_mode;
+    save_window_config(&app_handle, &current_config)?;
+
+    // 通知所有窗口和事件流更新主题
+    let _ = app_handle.emit("dark-mode-changed", dark_mode);
+
+    Ok(format!("Dark mode set to: {}", dark_mode))
+}
+
+// 获取深色模式状态
+#[tauri::command]
+pub fn get_dark_mode(app_handle: tauri::AppHandle) -> AppResult<bool> {
+    with_state(&app_handle, |app_state| app_state.dark_mode)
+}
+
+// 打开配置文件夹
+#[tauri::command]
+pub fn open_config_folder(app_handle: tauri::AppHandle) -> AppResult<String> {
+    use std::process::Command;
+
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::config_io("Failed to get app data directory").with_detail(e))?;
+
+    // 确保目录存在
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| AppError::io("Failed to create app data directory", &e))?;
+
+    // 在 Windows 上使用 explorer 打开文件夹
+    #[cfg(target_os = "windows")]
+    {
+        match Command::new("explorer").arg(&app_data_dir).spawn() {
+            Ok(_) => Ok(format!("Opened config folder: {}", app_data_dir.display())),
+            Err(e) => Err(AppError::backend("Failed to open config folder").with_detail(e)),
+        }
+    }
+
+    // 在 macOS 上使用 open 命令
+    #[cfg(target_os = "macos")]
+    {
+        match Command::new("open").arg(&app_data_dir).spawn() {
+            Ok(_) => Ok(format!("Opened config folder: {}", app_data_dir.display())),
+            Err(e) => Err(AppError::backend("Failed to open config folder").with_detail(e)),
+        }
+    }
+
+    // 在 Linux 上使用 xdg-open 命令
+    #[cfg(target_os = "linux")]
+    {
+        match Command::new("xdg-open").arg(&app_data_dir).spawn() {
+            Ok(_) => Ok(format!("Opened config folder: {}", app_data_dir.display())),
+            Err(e) => Err(AppError::backend("Failed to open config folder").with_detail(e)),
+        }
+    }
+}
+
+// 获取配置文件路径
+#[tauri::command]
+pub fn get_config_file_path(app_handle: tauri::AppHandle) -> AppResult<String> {
+    let config_file = get_window_config_path(&app_handle)?;
+    Ok(config_file.to_string_lossy().to_string())
+}
@@ -0,0 +1,41 @@
+// 托盘应用的配置文件：配置类型和读写逻辑在 audiocat-core 中，这里按 Tauri 的应用数据目录定位文件
+use crate::error::{AppError, AppResult};
+use crate::logging::CONFIG;
+use audiocat_core::config as core_config;
+use log::warn;
+use std::path::PathBuf;
+use tauri::Manager;
+
+pub use audiocat_core::config::{
+    HttpApiConfig, MqttConfig, OscConfig, WindowConfig, WindowPosition,
+};
+
+// 获取配置文件路径
+pub fn get_window_config_path(app_handle: &tauri::AppHandle) -> AppResult<PathBuf> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::config_io("Failed to get app data directory").with_detail(e))?;
+    Ok(core_config::config_path(&app_data_dir))
+}
+
+// 启动时加载窗口配置，文件不存在或无法解析时使用默认配置
+pub fn load_window_config(app_handle: &tauri::AppHandle) -> WindowConfig {
+    match get_window_config_path(app_handle) {
+        Ok(config_path) => core_config::load_config(&config_path),
+        Err(e) => {
+            warn!(target: CONFIG, "Failed to get config path: {}", e);
+            WindowConfig::default()
+        }
+    }
+}
+
+// 修改配置前读取：文件无法解析时返回 ConfigIo，不能用默认配置覆盖用户的配置文件
+pub fn read_window_config(app_handle: &tauri::AppHandle) -> AppResult<WindowConfig> {
+    core_config::read_config(&get_window_config_path(app_handle)?)
+}
+
+// 保存窗口配置
+pub fn save_window_config(app_handle: &tauri::AppHandle, config: &WindowConfig) -> AppResult<()> {
+    core_config::save_config(&get_window_config_path(app_handle)?, config)
+}
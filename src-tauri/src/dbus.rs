@@ -0,0 +1,463 @@
+// D-Bus 服务（仅 Linux）：在会话总线上注册 org.audiocat.AudioCat，供桌面快捷键和面板小部件使用。
+// 对象路径 /org/audiocat/AudioCat，接口 org.audiocat.AudioCat，设备用 (id, 名称, 是否默认) 表示：
+//   方法：ListDevices、SetDefault(设备 id 或名称)、Show、Hide
+//   属性：CurrentDevice、MonitoredWindows（监听规则的 JSON）、Pinned
+//   信号：DefaultChanged、DevicesChanged
+// 连接 DBUS_SESSION_BUS_ADDRESS 指定的总线，测试时可以指向单独启动的 dbus-daemon。
+// 需要 dbus 特性：Linux 上还没有音频后端，设备相关的方法和属性都返回 BackendUnavailable。
+use crate::audio::{AudioBackend, AudioDevice};
+use crate::commands;
+use crate::error::{AppError, AppResult, ErrorCode};
+use crate::logging::API;
+use crate::monitor::{MonitorRule, switch_device};
+use crate::single_instance::{self, LaunchAction};
+use crate::state::{audio, main_window, window_error, with_state};
+use crate::switch_history::SwitchCause;
+use log::{info, warn};
+use serde::Deserialize;
+use std::sync::mpsc::channel;
+use tauri::Listener;
+use zbus::blocking::{Connection, connection};
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface};
+
+const BUS_NAME: &str = "org.audiocat.AudioCat";
+const OBJECT_PATH: &str = "/org/audiocat/AudioCat";
+
+// (id, 名称, 是否默认)
+type Device = (String, String, bool);
+
+fn device_tuple(device: AudioDevice) -> Device {
+    (device.id, device.name, device.is_default)
+}
+
+// 错误名是 org.audiocat.AudioCat.Error.<错误码>，和其他接口的错误码一一对应
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.audiocat.AudioCat.Error")]
+enum DbusError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    DeviceNotFound(String),
+    DeviceInactive(String),
+    BackendUnavailable(String),
+    ConfigIo(String),
+    Permission(String),
+    InvalidInput(String),
+    NotFound(String),
+    Internal(String),
+}
+
+impl From<AppError> for DbusError {
+    fn from(error: AppError) -> Self {
+        let message = error.to_string();
+        match error.code {
+            ErrorCode::DeviceNotFound => Self::DeviceNotFound(message),
+            ErrorCode::DeviceInactive => Self::DeviceInactive(message),
+            ErrorCode::BackendUnavailable => Self::BackendUnavailable(message),
+            ErrorCode::ConfigIo => Self::ConfigIo(message),
+            ErrorCode::Permission => Self::Permission(message),
+            ErrorCode::InvalidInput => Self::InvalidInput(message),
+            ErrorCode::NotFound => Self::NotFound(message),
+            ErrorCode::Internal => Self::Internal(message),
+        }
+    }
+}
+
+// 属性的读写只能返回 fdo::Error
+fn property_error(error: AppError) -> fdo::Error {
+    match error.code {
+        ErrorCode::InvalidInput => fdo::Error::InvalidArgs(error.to_string()),
+        ErrorCode::Permission => fdo::Error::AccessDenied(error.to_string()),
+        _ => fdo::Error::Failed(error.to_string()),
+    }
+}
+
+// 服务背后的应用操作；测试时换成不依赖 Tauri 的实现
+trait Host: Send + Sync + 'static {
+    fn devices(&self) -> AppResult<Vec<AudioDevice>>;
+    fn current_device(&self) -> AppResult<AudioDevice>;
+    fn set_default(&self, device: &str) -> AppResult<AudioDevice>;
+    fn show(&self) -> AppResult<()>;
+    fn hide(&self) -> AppResult<()>;
+    fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>>;
+    fn set_monitored_windows(&self, windows: Vec<MonitorRule>) -> AppResult<()>;
+    fn pinned(&self) -> AppResult<bool>;
+    fn set_pinned(&self, pinned: bool) -> AppResult<()>;
+}
+
+impl Host for tauri::AppHandle {
+    fn devices(&self) -> AppResult<Vec<AudioDevice>> {
+        audio(self)?.call_blocking(AudioBackend::devices)
+    }
+
+    fn current_device(&self) -> AppResult<AudioDevice> {
+        audio(self)?.call_blocking(AudioBackend::current_device)
+    }
+
+    fn set_default(&self, device: &str) -> AppResult<AudioDevice> {
+        let cause = SwitchCause::Remote {
+            source: "dbus".to_string(),
+        };
+        switch_device(self, device, cause)
+    }
+
+    fn show(&self) -> AppResult<()> {
+        single_instance::execute(self, &[LaunchAction::Show])
+    }
+
+    fn hide(&self) -> AppResult<()> {
+        main_window(self)?.hide().map_err(window_error)
+    }
+
+    fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>> {
+        commands::get_monitored_windows(self.clone())
+    }
+
+    fn set_monitored_windows(&self, windows: Vec<MonitorRule>) -> AppResult<()> {
+        commands::set_monitored_windows(self.clone(), windows).map(|_| ())
+    }
+
+    fn pinned(&self) -> AppResult<bool> {
+        commands::get_window_pinned(self.clone())
+    }
+
+    fn set_pinned(&self, pinned: bool) -> AppResult<()> {
+        commands::set_window_pinned(self.clone(), pinned).map(|_| ())
+    }
+}
+
+struct AudioCatService {
+    host: Box<dyn Host>,
+}
+
+#[interface(name = "org.audiocat.AudioCat")]
+impl AudioCatService {
+    fn list_devices(&self) -> Result<Vec<Device>, DbusError> {
+        let devices = self.host.devices()?;
+        Ok(devices.into_iter().map(device_tuple).collect())
+    }
+
+    fn set_default(&self, device: &str) -> Result<Device, DbusError> {
+        Ok(device_tuple(self.host.set_default(device)?))
+    }
+
+    fn show(&self) -> Result<(), DbusError> {
+        Ok(self.host.show()?)
+    }
+
+    fn hide(&self) -> Result<(), DbusError> {
+        Ok(self.host.hide()?)
+    }
+
+    #[zbus(property)]
+    fn current_device(&self) -> fdo::Result<Device> {
+        self.host
+            .current_device()
+            .map(device_tuple)
+            .map_err(property_error)
+    }
+
+    // 和配置文件中 monitored_windows 的格式相同
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn monitored_windows(&self) -> fdo::Result<String> {
+        let windows = self.host.monitored_windows().map_err(property_error)?;
+        serde_json::to_string(&windows).map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+
+    #[zbus(property)]
+    fn set_monitored_windows(&self, windows: &str) -> fdo::Result<()> {
+        let windows: Vec<MonitorRule> = serde_json::from_str(windows)
+            .map_err(|e| fdo::Error::InvalidArgs(format!("Invalid monitored windows: {}", e)))?;
+        self.host
+            .set_monitored_windows(windows)
+            .map_err(property_error)
+    }
+
+    #[zbus(property)]
+    fn pinned(&self) -> fdo::Result<bool> {
+        self.host.pinned().map_err(property_error)
+    }
+
+    #[zbus(property)]
+    fn set_pinned(&self, pinned: bool) -> fdo::Result<()> {
+        self.host.set_pinned(pinned).map_err(property_error)
+    }
+
+    #[zbus(signal)]
+    async fn default_changed(emitter: &SignalEmitter<'_>, device: Device) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn devices_changed(emitter: &SignalEmitter<'_>, devices: Vec<Device>)
+    -> zbus::Result<()>;
+}
+
+// 需要转成 D-Bus 信号的应用事件
+enum Signal {
+    Default(AudioDevice),
+    Devices(Vec<AudioDevice>),
+    Pinned,
+}
+
+#[derive(Deserialize)]
+struct DeviceSwitched {
+    current: AudioDevice,
+}
+
+// 在总线上注册服务名并导出对象
+fn serve(builder: connection::Builder<'_>, host: Box<dyn Host>) -> zbus::Result<Connection> {
+    builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, AudioCatService { host })?
+        .build()
+}
+
+pub fn start_service(app_handle: tauri::AppHandle) {
+    let connection = match connection::Builder::session()
+        .and_then(|builder| serve(builder, Box::new(app_handle.clone())))
+    {
+        Ok(connection) => connection,
+        Err(e) => {
+            warn!(target: API, "Failed to start D-Bus service: {}", e);
+            return;
+        }
+    };
+    let interface = match connection
+        .object_server()
+        .interface::<_, AudioCatService>(OBJECT_PATH)
+    {
+        Ok(interface) => interface,
+        Err(e) => {
+            warn!(target: API, "Failed to get D-Bus interface: {}", e);
+            return;
+        }
+    };
+    info!(target: API, "D-Bus service {} registered", BUS_NAME);
+    if let Err(e) = app_handle.current_device() {
+        if e.code == ErrorCode::BackendUnavailable {
+            warn!(target: API, "No audio backend, D-Bus device methods will fail: {}", e);
+        }
+    }
+
+    // 事件回调可能在任何线程上执行，信号统一在一个线程上发送
+    let (signals, receiver) = channel::<Signal>();
+    let sender = signals.clone();
+    app_handle.listen_any("device-switched", move |event| {
+        if let Ok(switched) = serde_json::from_str::<DeviceSwitched>(event.payload()) {
+            let _ = sender.send(Signal::Default(switched.current));
+        }
+    });
+    let sender = signals.clone();
+    app_handle.listen_any("devices-changed", move |event| {
+        if let Ok(devices) = serde_json::from_str(event.payload()) {
+            let _ = sender.send(Signal::Devices(devices));
+        }
+    });
+    app_handle.listen_any("pin-mode-changed", move |_| {
+        let _ = signals.send(Signal::Pinned);
+    });
+    // 服务一直运行，监听线程即使没有设备规则也检查设备变化
+    let _ = with_state(&app_handle, |app_state| app_state.device_subscribers += 1);
+
+    std::thread::spawn(move || {
+        // 连接在线程结束前一直保持，服务随之保持注册
+        let _connection = connection;
+        for signal in receiver {
+            let emitter = interface.signal_emitter();
+            let result = zbus::block_on(async {
+                match signal {
+                    Signal::Default(current) => {
+                        AudioCatService::default_changed(emitter, device_tuple(current)).await?;
+                        interface.get().current_device_changed(emitter).await
+                    }
+                    Signal::Devices(devices) => {
+                        let devices = devices.into_iter().map(device_tuple).collect();
+                        AudioCatService::devices_changed(emitter, devices).await
+                    }
+                    Signal::Pinned => interface.get().pinned_changed(emitter).await,
+                }
+            });
+            if let Err(e) = result {
+                warn!(target: API, "Failed to emit D-Bus signal: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+    use zbus::blocking::Proxy;
+
+    // 单独启动的会话总线，测试结束时关闭
+    struct Daemon {
+        child: Child,
+        address: String,
+    }
+
+    impl Daemon {
+        fn start() -> Self {
+            let mut child = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("dbus-daemon is not installed");
+            let mut address = String::new();
+            BufReader::new(child.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            Self {
+                child,
+                address: address.trim().to_string(),
+            }
+        }
+
+        fn connect(&self) -> Connection {
+            connection::Builder::address(self.address.as_str())
+                .and_then(|builder| builder.build())
+                .unwrap()
+        }
+    }
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeHost {
+        devices: Vec<AudioDevice>,
+        pinned: Arc<Mutex<bool>>,
+        shown: Arc<Mutex<usize>>,
+    }
+
+    impl Host for FakeHost {
+        fn devices(&self) -> AppResult<Vec<AudioDevice>> {
+            Ok(self.devices.clone())
+        }
+
+        fn current_device(&self) -> AppResult<AudioDevice> {
+            self.devices
+                .iter()
+                .find(|device| device.is_default)
+                .cloned()
+                .ok_or_else(|| AppError::backend("No audio backend"))
+        }
+
+        fn set_default(&self, device: &str) -> AppResult<AudioDevice> {
+            self.devices
+                .iter()
+                .find(|candidate| candidate.matches(device))
+                .cloned()
+                .ok_or_else(|| AppError::device_not_found(device))
+        }
+
+        fn show(&self) -> AppResult<()> {
+            *self.shown.lock().unwrap() += 1;
+            Ok(())
+        }
+
+        fn hide(&self) -> AppResult<()> {
+            Err(AppError::internal("Main window not found"))
+        }
+
+        fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>> {
+            Ok(Vec::new())
+        }
+
+        fn set_monitored_windows(&self, _windows: Vec<MonitorRule>) -> AppResult<()> {
+            Ok(())
+        }
+
+        fn pinned(&self) -> AppResult<bool> {
+            Ok(*self.pinned.lock().unwrap())
+        }
+
+        fn set_pinned(&self, pinned: bool) -> AppResult<()> {
+            *self.pinned.lock().unwrap() = pinned;
+            Ok(())
+        }
+    }
+
+    fn device(id: &str, name: &str, is_default: bool) -> AudioDevice {
+        AudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            is_default,
+        }
+    }
+
+    fn error_name(error: zbus::Error) -> String {
+        match error {
+            zbus::Error::MethodError(name, _, _) => name.to_string(),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn serves_methods_and_properties_on_a_private_bus() {
+        let daemon = Daemon::start();
+        let host = FakeHost {
+            devices: vec![
+                device("1", "Speakers", true),
+                device("2", "Headphones", false),
+            ],
+            ..FakeHost::default()
+        };
+        let pinned = host.pinned.clone();
+        let shown = host.shown.clone();
+        let builder = connection::Builder::address(daemon.address.as_str()).unwrap();
+        let _service = serve(builder, Box::new(host)).unwrap();
+
+        let client = daemon.connect();
+        let proxy = Proxy::new(&client, BUS_NAME, OBJECT_PATH, BUS_NAME).unwrap();
+
+        let devices: Vec<Device> = proxy.call("ListDevices", &()).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(
+            devices[1],
+            ("2".to_string(), "Headphones".to_string(), false)
+        );
+
+        let switched: Device = proxy.call("SetDefault", &("Headphones",)).unwrap();
+        assert_eq!(switched.0, "2");
+        let error = proxy
+            .call::<_, _, Device>("SetDefault", &("Missing",))
+            .unwrap_err();
+        assert_eq!(
+            error_name(error),
+            "org.audiocat.AudioCat.Error.DeviceNotFound"
+        );
+
+        proxy.call::<_, _, ()>("Show", &()).unwrap();
+        assert_eq!(*shown.lock().unwrap(), 1);
+        let error = proxy.call::<_, _, ()>("Hide", &()).unwrap_err();
+        assert_eq!(error_name(error), "org.audiocat.AudioCat.Error.Internal");
+
+        let current: Device = proxy.get_property("CurrentDevice").unwrap();
+        assert_eq!(current.1, "Speakers");
+        let windows: String = proxy.get_property("MonitoredWindows").unwrap();
+        assert_eq!(windows, "[]");
+        assert!(proxy.set_property("MonitoredWindows", "not json").is_err());
+
+        proxy.set_property("Pinned", true).unwrap();
+        assert!(*pinned.lock().unwrap());
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn reports_missing_backend() {
+        let daemon = Daemon::start();
+        let builder = connection::Builder::address(daemon.address.as_str()).unwrap();
+        let _service = serve(builder, Box::new(FakeHost::default())).unwrap();
+
+        let client = daemon.connect();
+        let proxy = Proxy::new(&client, BUS_NAME, OBJECT_PATH, BUS_NAME).unwrap();
+        assert!(proxy.get_property::<Device>("CurrentDevice").is_err());
+    }
+}
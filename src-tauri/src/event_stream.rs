@@ -0,0 +1,141 @@
+// 事件流：本地 HTTP 接口上的 GET /events 升级为 WebSocket，把状态变化以带类型的 JSON 消息推送给
+// 悬浮窗、仪表盘等外部工具。连接后先发送一条 snapshot，之后每条消息都是 {"type", "data"}：
+//   device_switched、devices_changed、monitor_triggered、rule_fired、pin_changed、theme_changed
+// 只由服务端推送，客户端发来的数据消息会被忽略；ping 和 close 按协议回复。
+use crate::audio::AudioBackend;
+use crate::error::AppResult;
+use crate::http_api::Request;
+use crate::logging::API;
+use crate::state::{audio, with_state};
+use log::{debug, info};
+use serde_json::{Value, json};
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{TryRecvError, channel};
+use std::time::{Duration, Instant};
+use tauri::{EventId, Listener};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+// 推送的 Tauri 事件和对应的消息类型
+const EVENTS: [(&str, &str); 6] = [
+    ("device-switched", "device_switched"),
+    ("devices-changed", "devices_changed"),
+    ("monitor-triggered", "monitor_triggered"),
+    ("rule-fired", "rule_fired"),
+    ("pin-mode-changed", "pin_changed"),
+    ("dark-mode-changed", "theme_changed"),
+];
+
+// 定期发送 ping，及时发现已经断开的连接
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+// 升级后连接的读超时。同一个线程上交替推送事件和读取客户端消息，这也是事件推送的最大延迟；
+// REST 请求用的是 http_api 中正常的超时
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+// 是否是 WebSocket 升级请求
+pub fn is_upgrade(request: &Request) -> bool {
+    request.header("Sec-WebSocket-Key").is_some()
+        && request
+            .header("Upgrade")
+            .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+// 完成握手后在当前线程上推送事件，直到连接断开
+pub fn serve(app_handle: &tauri::AppHandle, mut stream: TcpStream, request: Request) {
+    let Some(key) = request.header("Sec-WebSocket-Key") else {
+        return;
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    );
+    let upgraded = stream
+        .write_all(response.as_bytes())
+        .and_then(|_| stream.set_read_timeout(Some(READ_TIMEOUT)));
+    if let Err(e) = upgraded {
+        debug!(target: API, "Event stream handshake failed: {}", e);
+        return;
+    }
+    // 客户端可能紧跟着请求头发来了数据帧
+    let mut socket = WebSocket::from_partially_read(stream, request.body, Role::Server, None);
+
+    // 先订阅再生成快照，快照之后的变化不会丢失
+    let (outgoing, messages) = channel::<Value>();
+    let mut subscriptions: Vec<EventId> = Vec::new();
+    for (event, kind) in EVENTS {
+        let outgoing = outgoing.clone();
+        let id = app_handle.listen_any(event, move |message| {
+            let data = serde_json::from_str(message.payload()).unwrap_or(Value::Null);
+            let _ = outgoing.send(json!({ "type": kind, "data": data }));
+        });
+        subscriptions.push(id);
+    }
+    drop(outgoing);
+    // 有订阅者时监听线程即使没有设备规则也检查设备变化
+    let _ = with_state(app_handle, |app_state| app_state.device_subscribers += 1);
+    info!(target: API, "Event stream client connected");
+
+    let snapshot = match snapshot(app_handle) {
+        Ok(data) => json!({ "type": "snapshot", "data": data }),
+        Err(e) => json!({ "type": "error", "data": e }),
+    };
+    let mut result = socket.send(Message::text(snapshot.to_string()));
+    let mut last_ping = Instant::now();
+    while result.is_ok() {
+        result = match messages.try_recv() {
+            Ok(message) => socket.send(Message::text(message.to_string())),
+            Err(TryRecvError::Disconnected) => break,
+            Err(TryRecvError::Empty) if last_ping.elapsed() >= PING_INTERVAL => {
+                last_ping = Instant::now();
+                socket.send(Message::Ping(Default::default()))
+            }
+            // 没有事件要推送时等待客户端消息，最多等 READ_TIMEOUT。tungstenite 在读取时自动回复
+            // ping；收到 close 时回复 close，下一次读取返回 ConnectionClosed 结束推送
+            Err(TryRecvError::Empty) => match socket.read() {
+                Err(e) if timed_out(&e) => Ok(()),
+                read => read.map(|_| ()),
+            },
+        };
+    }
+    match result {
+        Err(tungstenite::Error::ConnectionClosed) | Ok(()) => {}
+        Err(e) => debug!(target: API, "Event stream closed: {}", e),
+    }
+
+    for id in subscriptions {
+        app_handle.unlisten(id);
+    }
+    let _ = with_state(app_handle, |app_state| {
+        app_state.device_subscribers = app_state.device_subscribers.saturating_sub(1)
+    });
+    info!(target: API, "Event stream client disconnected");
+}
+
+// 读超时内客户端没有发来消息
+fn timed_out(error: &tungstenite::Error) -> bool {
+    matches!(
+        error,
+        tungstenite::Error::Io(e)
+            if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+    )
+}
+
+// 连接时发送的当前状态
+fn snapshot(app_handle: &tauri::AppHandle) -> AppResult<Value> {
+    let audio = audio(app_handle)?;
+    let devices = audio.call_blocking(AudioBackend::devices)?;
+    let current = audio.call_blocking(AudioBackend::current_device)?;
+    let (pinned, dark_mode) = with_state(app_handle, |app_state| {
+        (app_state.is_pinned, app_state.dark_mode)
+    })?;
+    Ok(json!({
+        "devices": devices,
+        "current": current,
+        "pinned": pinned,
+        "dark_mode": dark_mode,
+    }))
+}
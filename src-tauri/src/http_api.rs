@@ -0,0 +1,947 @@
+// 本地 HTTP 接口（默认关闭）：只监听 127.0.0.1，除 /openapi.json 外的请求都要带
+// Authorization: Bearer <token>，端口和 token 保存在配置文件中。端点和 Tauri 命令使用同样的逻辑：
+//   GET /devices、GET /default、PUT /default {"device": 设备 id 或名称}
+//   GET/PUT /monitored-windows、GET/PUT /settings、GET /openapi.json
+//   GET /events：WebSocket 事件流，见 event_stream；浏览器不能设置请求头，也可以用 ?token=<token>
+// 每个连接只处理一个请求，回复后关闭；升级为事件流的连接交给 event_stream。
+use crate::audio::{AudioBackend, AudioDevice};
+use crate::commands;
+use crate::config::{HttpApiConfig, read_window_config, save_window_config};
+use crate::error::{AppError, AppResult, ErrorCode};
+use crate::event_stream;
+use crate::logging::API;
+use crate::monitor::{MonitorRule, switch_device};
+use crate::state::{audio, with_state};
+use crate::switch_history::SwitchCause;
+use log::{debug, info, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+
+// 请求体大小上限
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+// 请求头的大小和个数上限
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+const MAX_HEADERS: usize = 64;
+
+// REST 请求的读写超时：停在半路的请求不会一直占着线程。事件流升级后换成自己的读超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 正在运行的服务器：监听的端口和让监听线程退出的标志
+#[derive(Default)]
+pub struct HttpServer {
+    running: Mutex<Option<(u16, Arc<AtomicBool>)>>,
+}
+
+// 读取到的一个请求
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>, // 升级请求是请求头之后已经读到的 WebSocket 数据
+}
+
+impl Request {
+    // 请求头名称不区分大小写
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Deserialize)]
+struct DefaultDevice {
+    device: String, // 设备 id 或设备名称
+}
+
+#[derive(Serialize)]
+struct Settings {
+    auto_hide_delay: u64,
+    dark_mode: bool,
+    log_level: String,
+}
+
+// PUT /settings 只修改给出的字段
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsUpdate {
+    auto_hide_delay: Option<u64>,
+    dark_mode: Option<bool>,
+    log_level: Option<String>,
+}
+
+// 接口背后的应用操作；测试时换成不依赖 Tauri 的实现
+trait Host {
+    fn token(&self) -> AppResult<String>;
+    fn devices(&self) -> AppResult<Vec<AudioDevice>>;
+    fn current_device(&self) -> AppResult<AudioDevice>;
+    fn set_default(&self, device: &str) -> AppResult<AudioDevice>;
+    fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>>;
+    fn set_monitored_windows(&self, windows: Vec<MonitorRule>) -> AppResult<()>;
+    fn auto_hide_delay(&self) -> AppResult<u64>;
+    fn set_auto_hide_delay(&self, delay: u64) -> AppResult<()>;
+    fn dark_mode(&self) -> AppResult<bool>;
+    fn set_dark_mode(&self, dark_mode: bool) -> AppResult<()>;
+    fn log_level(&self) -> String;
+    fn set_log_level(&self, level: String) -> AppResult<()>;
+}
+
+impl Host for tauri::AppHandle {
+    fn token(&self) -> AppResult<String> {
+        with_state(self, |app_state| app_state.http_api.token.clone())
+    }
+
+    fn devices(&self) -> AppResult<Vec<AudioDevice>> {
+        audio(self)?.call_blocking(AudioBackend::devices)
+    }
+
+    fn current_device(&self) -> AppResult<AudioDevice> {
+        audio(self)?.call_blocking(AudioBackend::current_device)
+    }
+
+    fn set_default(&self, device: &str) -> AppResult<AudioDevice> {
+        let cause = SwitchCause::Remote {
+            source: "http".to_string(),
+        };
+        switch_device(self, device, cause)
+    }
+
+    fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>> {
+        commands::get_monitored_windows(self.clone())
+    }
+
+    fn set_monitored_windows(&self, windows: Vec<MonitorRule>) -> AppResult<()> {
+        commands::set_monitored_windows(self.clone(), windows).map(|_| ())
+    }
+
+    fn auto_hide_delay(&self) -> AppResult<u64> {
+        commands::get_auto_hide_delay(self.clone())
+    }
+
+    fn set_auto_hide_delay(&self, delay: u64) -> AppResult<()> {
+        commands::set_auto_hide_delay(self.clone(), delay).map(|_| ())
+    }
+
+    fn dark_mode(&self) -> AppResult<bool> {
+        commands::get_dark_mode(self.clone())
+    }
+
+    fn set_dark_mode(&self, dark_mode: bool) -> AppResult<()> {
+        commands::set_dark_mode(self.clone(), dark_mode).map(|_| ())
+    }
+
+    fn log_level(&self) -> String {
+        commands::get_log_level()
+    }
+
+    fn set_log_level(&self, level: String) -> AppResult<()> {
+        commands::set_log_level(self.clone(), level).map(|_| ())
+    }
+}
+
+// 启动时按配置启动服务器；开启但还没有 token 时生成一个并保存到配置文件
+pub fn init(app_handle: &tauri::AppHandle) {
+    let Ok(mut config) = with_state(app_handle, |app_state| app_state.http_api.clone()) else {
+        return;
+    };
+    match config.ensure_token() {
+        Ok(true) => {
+            let _ = with_state(app_handle, |app_state| app_state.http_api = config.clone());
+            let saved = read_window_config(app_handle).and_then(|mut window_config| {
+                window_config.http_api = config.clone();
+                save_window_config(app_handle, &window_config)
+            });
+            if let Err(e) = saved {
+                warn!(target: API, "Failed to save generated HTTP API token: {}", e);
+            }
+        }
+        Ok(false) => {}
+        Err(e) => {
+            warn!(target: API, "{}", e);
+            return;
+        }
+    }
+    apply(app_handle, &config);
+}
+
+// 按新配置启动、停止或换端口重启；token 每次请求时从状态中读取，修改 token 不需要重启
+pub fn apply(app_handle: &tauri::AppHandle, config: &HttpApiConfig) {
+    let Some(server) = app_handle.try_state::<HttpServer>() else {
+        return;
+    };
+    let Ok(mut running) = server.running.lock() else {
+        return;
+    };
+
+    if let Some((port, _)) = running.as_ref() {
+        if config.enabled && *port == config.port {
+            return;
+        }
+    }
+    if let Some((port, stop)) = running.take() {
+        stop.store(true, Ordering::SeqCst);
+        // 连一下自己，让阻塞在 accept 上的监听线程看到停止标志后退出
+        let _ = TcpStream::connect_timeout(&local_address(port), Duration::from_secs(1));
+        info!(target: API, "HTTP API on port {} stopped", port);
+    }
+    if !config.enabled {
+        return;
+    }
+
+    let listener = match TcpListener::bind(local_address(config.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!(target: API, "Failed to start HTTP API on port {}: {}", config.port, e);
+            return;
+        }
+    };
+    info!(target: API, "HTTP API listening on 127.0.0.1:{}", config.port);
+    let stop = Arc::new(AtomicBool::new(false));
+    *running = Some((config.port, stop.clone()));
+
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            match stream {
+                Ok(stream) => {
+                    let app_handle = app_handle.clone();
+                    std::thread::spawn(move || handle(&app_handle, stream));
+                }
+                Err(e) => warn!(target: API, "Failed to accept HTTP connection: {}", e),
+            }
+        }
+    });
+}
+
+fn local_address(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+fn handle(app_handle: &tauri::AppHandle, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(REQUEST_TIMEOUT));
+
+    let request = match read_request(&mut stream) {
+        Ok(request) => request,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            let error = AppError::invalid_input(e.to_string());
+            debug!(target: API, "HTTP API rejected request: {}", error);
+            let _ = write_response(&mut stream, 400, &to_json(&error));
+            return;
+        }
+        // 超时或者客户端断开，直接关闭连接
+        Err(e) => {
+            debug!(target: API, "Failed to read HTTP request: {}", e);
+            return;
+        }
+    };
+
+    if request.method == "GET"
+        && request.path == "/events"
+        && event_stream::is_upgrade(&request)
+        && authorized(app_handle, &request)
+    {
+        return event_stream::serve(app_handle, stream, request);
+    }
+    let (status, body) = respond(app_handle, &request);
+    debug!(target: API, "HTTP API {} {} -> {}", request.method, request.path, status);
+    let _ = write_response(&mut stream, status, &body);
+}
+
+// 返回状态码和 JSON 响应体
+fn respond(host: &dyn Host, request: &Request) -> (u16, Value) {
+    let result = if request.path != "/openapi.json" && !authorized(host, request) {
+        let error = AppError::new(ErrorCode::Permission, "Missing or invalid bearer token");
+        Err((401, error))
+    } else if request.method == "GET" && request.path == "/events" {
+        Err((
+            400,
+            AppError::invalid_input("/events requires a WebSocket upgrade"),
+        ))
+    } else {
+        route(host, request).map_err(|e| (status(&e), e))
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err((status, error)) => {
+            warn!(
+                target: API,
+                "HTTP API {} {} failed: {}",
+                request.method, request.path, error
+            );
+            (status, to_json(&error))
+        }
+    }
+}
+
+fn route(host: &dyn Host, request: &Request) -> AppResult<Value> {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/openapi.json") => Ok(openapi()),
+        ("GET", "/devices") => to_value(host.devices()?),
+        ("GET", "/default") => to_value(host.current_device()?),
+        ("PUT", "/default") => {
+            let body: DefaultDevice = read_body(request)?;
+            to_value(host.set_default(&body.device)?)
+        }
+        ("GET", "/monitored-windows") => to_value(host.monitored_windows()?),
+        ("PUT", "/monitored-windows") => {
+            host.set_monitored_windows(read_body(request)?)?;
+            to_value(host.monitored_windows()?)
+        }
+        ("GET", "/settings") => to_value(settings(host)?),
+        ("PUT", "/settings") => {
+            let update: SettingsUpdate = read_body(request)?;
+            if let Some(delay) = update.auto_hide_delay {
+                host.set_auto_hide_delay(delay)?;
+            }
+            if let Some(dark_mode) = update.dark_mode {
+                host.set_dark_mode(dark_mode)?;
+            }
+            if let Some(level) = update.log_level {
+                host.set_log_level(level)?;
+            }
+            to_value(settings(host)?)
+        }
+        (method, path) => Err(AppError::not_found(format!(
+            "No endpoint {} {}",
+            method, path
+        ))),
+    }
+}
+
+fn authorized(host: &dyn Host, request: &Request) -> bool {
+    let Ok(token) = host.token() else {
+        return false;
+    };
+    if token.is_empty() {
+        return false;
+    }
+    // 查询参数中的 token 只用于事件流，避免出现在其他请求的日志和历史记录中
+    if request.path == "/events"
+        && request
+            .query
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("token="))
+            .any(|value| constant_time_eq(value, &token))
+    {
+        return true;
+    }
+    let expected = format!("Bearer {}", token);
+    request
+        .headers
+        .iter()
+        .filter(|(field, _)| field.eq_ignore_ascii_case("Authorization"))
+        .any(|(_, value)| constant_time_eq(value, &expected))
+}
+
+// 比较 token 时不因第一个不同的字符提前返回
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn settings(host: &dyn Host) -> AppResult<Settings> {
+    Ok(Settings {
+        auto_hide_delay: host.auto_hide_delay()?,
+        dark_mode: host.dark_mode()?,
+        log_level: host.log_level(),
+    })
+}
+
+// 读取请求头，再按 Content-Length 读取请求体；格式不对时返回 InvalidData
+fn read_request(stream: &mut (impl Read + Write)) -> io::Result<Request> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let (head_length, method, target, headers) = loop {
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let status = parsed
+            .parse(&buffer)
+            .map_err(|e| invalid(format!("Invalid request: {}", e)))?;
+        if let httparse::Status::Complete(length) = status {
+            let headers: Vec<(String, String)> = parsed
+                .headers
+                .iter()
+                .map(|header| {
+                    let value = String::from_utf8_lossy(header.value);
+                    (header.name.to_string(), value.trim().to_string())
+                })
+                .collect();
+            let method = parsed.method.unwrap_or_default().to_string();
+            let target = parsed.path.unwrap_or_default().to_string();
+            break (length, method, target, headers);
+        }
+        if buffer.len() >= MAX_HEAD_BYTES {
+            return Err(invalid("Request header too large".to_string()));
+        }
+        let read = stream.read(&mut chunk)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (target, String::new()),
+    };
+    let mut request = Request {
+        method,
+        path,
+        query,
+        headers,
+        body: buffer.split_off(head_length),
+    };
+
+    if request.header("Transfer-Encoding").is_some() {
+        return Err(invalid(
+            "Chunked request bodies are not supported".to_string(),
+        ));
+    }
+    let Some(length) = request.header("Content-Length") else {
+        return Ok(request);
+    };
+    let length: usize = length
+        .parse()
+        .map_err(|_| invalid(format!("Invalid Content-Length: {}", length)))?;
+    if length > MAX_BODY_BYTES {
+        return Err(invalid("Request body too large".to_string()));
+    }
+    // curl 等客户端发送较大的请求体前会先等 100 Continue
+    let expects_continue = request
+        .header("Expect")
+        .is_some_and(|value| value.eq_ignore_ascii_case("100-continue"));
+    if expects_continue && request.body.len() < length {
+        stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+    }
+    while request.body.len() < length {
+        let read = stream.read(&mut chunk)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        request.body.extend_from_slice(&chunk[..read]);
+    }
+    request.body.truncate(length);
+    Ok(request)
+}
+
+fn write_response(stream: &mut impl Write, status: u16, body: &Value) -> io::Result<()> {
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+fn read_body<T: DeserializeOwned>(request: &Request) -> AppResult<T> {
+    serde_json::from_slice(&request.body)
+        .map_err(|e| AppError::invalid_input("Invalid request body").with_detail(e))
+}
+
+fn to_value(value: impl Serialize) -> AppResult<Value> {
+    serde_json::to_value(value)
+        .map_err(|e| AppError::internal("Failed to serialize response").with_detail(e))
+}
+
+fn to_json(error: &AppError) -> Value {
+    serde_json::to_value(error).unwrap_or_default()
+}
+
+fn status(error: &AppError) -> u16 {
+    match error.code {
+        ErrorCode::InvalidInput => 400,
+        ErrorCode::Permission => 403,
+        ErrorCode::DeviceNotFound | ErrorCode::NotFound => 404,
+        ErrorCode::DeviceInactive => 409,
+        ErrorCode::BackendUnavailable => 503,
+        ErrorCode::ConfigIo | ErrorCode::Internal => 500,
+    }
+}
+
+// GET /openapi.json 返回的接口文档
+fn openapi() -> Value {
+    let schema = |name: &str| json!({ "$ref": format!("#/components/schemas/{}", name) });
+    let json_body =
+        |schema: Value| json!({ "content": { "application/json": { "schema": schema } } });
+    // 失败时返回 Error，状态码见 status()
+    let ok = |description: &str, schema: Value| {
+        let mut success = json_body(schema);
+        success["description"] = json!(description);
+        let mut error = json_body(json!({ "$ref": "#/components/schemas/Error" }));
+        error["description"] = json!("错误");
+        json!({ "200": success, "default": error })
+    };
+    let string_enum = |values: &[&str]| json!({ "type": "string", "enum": values });
+
+    let device = schema("AudioDevice");
+    let devices = json!({ "type": "array", "items": device });
+    let rules = json!({ "type": "array", "items": schema("MonitorRule") });
+    let settings = schema("Settings");
+    let matcher_types = [
+        "exact_title",
+        "substring",
+        "glob",
+        "regex",
+        "process",
+        "class",
+    ];
+    let action_types = ["show_switcher", "switch_to", "revert"];
+    let log_levels = ["off", "error", "warn", "info", "debug", "trace"];
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "AudioCat HTTP API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "只监听 127.0.0.1，除本文档外的请求都需要 \
+                Authorization: Bearer <token>，token 见配置文件的 http_api.token"
+        },
+        "security": [{ "bearer": [] }],
+        "paths": {
+            "/devices": {
+                "get": { "summary": "输出设备列表", "responses": ok("设备列表", devices) }
+            },
+            "/default": {
+                "get": { "summary": "当前默认设备", "responses": ok("默认设备", device.clone()) },
+                "put": {
+                    "summary": "切换默认设备",
+                    "requestBody": json_body(json!({
+                        "type": "object",
+                        "required": ["device"],
+                        "properties": {
+                            "device": { "type": "string", "description": "设备 id 或设备名称" }
+                        }
+                    })),
+                    "responses": ok("切换后的默认设备", device)
+                }
+            },
+            "/monitored-windows": {
+                "get": { "summary": "窗口监听规则", "responses": ok("监听规则", rules.clone()) },
+                "put": {
+                    "summary": "替换窗口监听规则",
+                    "requestBody": json_body(rules.clone()),
+                    "responses": ok("保存后的监听规则", rules)
+                }
+            },
+            "/settings": {
+                "get": { "summary": "设置", "responses": ok("设置", settings.clone()) },
+                "put": {
+                    "summary": "修改设置，只修改给出的字段",
+                    "requestBody": json_body(settings.clone()),
+                    "responses": ok("修改后的设置", settings)
+                }
+            },
+            "/events": {
+                "get": {
+                    "summary": "WebSocket 事件流：连接后先推送 snapshot，之后推送状态变化",
+                    "parameters": [{
+                        "name": "token",
+                        "in": "query",
+                        "required": false,
+                        "description": "不能设置请求头时代替 Authorization",
+                        "schema": { "type": "string" }
+                    }],
+                    "responses": { "101": { "description": "升级为 WebSocket" } }
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": { "bearer": { "type": "http", "scheme": "bearer" } },
+            "schemas": {
+                "AudioDevice": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "name": { "type": "string" },
+                        "is_default": { "type": "boolean" }
+                    }
+                },
+                "MonitorRule": {
+                    "type": "object",
+                    "description": "和配置文件 monitored_windows 中的规则格式相同",
+                    "properties": {
+                        "matcher": {
+                            "type": "object",
+                            "properties": {
+                                "type": string_enum(&matcher_types),
+                                "value": { "type": "string" }
+                            }
+                        },
+                        "event": string_enum(&["opened", "closed", "focused"]),
+                        "action": {
+                            "type": "object",
+                            "properties": {
+                                "type": string_enum(&action_types),
+                                "device": { "type": "string" }
+                            }
+                        }
+                    }
+                },
+                "Settings": {
+                    "type": "object",
+                    "properties": {
+                        "auto_hide_delay": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "自动隐藏延迟（秒）"
+                        },
+                        "dark_mode": { "type": "boolean" },
+                        "log_level": string_enum(&log_levels)
+                    }
+                },
+                "Error": {
+                    "type": "object",
+                    "properties": {
+                        "code": { "type": "string" },
+                        "message": { "type": "string" },
+                        "detail": { "type": "string", "nullable": true }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::Mutex;
+
+    const TOKEN: &str = "secret-token";
+
+    struct FakeHost {
+        token: String,
+        devices: Vec<AudioDevice>,
+        switched: Mutex<Vec<String>>,
+        auto_hide_delay: Mutex<u64>,
+    }
+
+    impl Default for FakeHost {
+        fn default() -> Self {
+            Self {
+                token: TOKEN.to_string(),
+                devices: vec![
+                    device("1", "Speakers", true),
+                    device("2", "Headphones", false),
+                ],
+                switched: Mutex::default(),
+                auto_hide_delay: Mutex::new(3),
+            }
+        }
+    }
+
+    impl Host for FakeHost {
+        fn token(&self) -> AppResult<String> {
+            Ok(self.token.clone())
+        }
+
+        fn devices(&self) -> AppResult<Vec<AudioDevice>> {
+            Ok(self.devices.clone())
+        }
+
+        fn current_device(&self) -> AppResult<AudioDevice> {
+            Err(AppError::backend("No audio backend"))
+        }
+
+        fn set_default(&self, device: &str) -> AppResult<AudioDevice> {
+            let found = self
+                .devices
+                .iter()
+                .find(|candidate| candidate.matches(device))
+                .cloned()
+                .ok_or_else(|| AppError::device_not_found(device))?;
+            self.switched.lock().unwrap().push(found.id.clone());
+            Ok(found)
+        }
+
+        fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>> {
+            Ok(Vec::new())
+        }
+
+        fn set_monitored_windows(&self, _windows: Vec<MonitorRule>) -> AppResult<()> {
+            Ok(())
+        }
+
+        fn auto_hide_delay(&self) -> AppResult<u64> {
+            Ok(*self.auto_hide_delay.lock().unwrap())
+        }
+
+        fn set_auto_hide_delay(&self, delay: u64) -> AppResult<()> {
+            *self.auto_hide_delay.lock().unwrap() = delay;
+            Ok(())
+        }
+
+        fn dark_mode(&self) -> AppResult<bool> {
+            Ok(false)
+        }
+
+        fn set_dark_mode(&self, _dark_mode: bool) -> AppResult<()> {
+            Ok(())
+        }
+
+        fn log_level(&self) -> String {
+            "info".to_string()
+        }
+
+        fn set_log_level(&self, level: String) -> AppResult<()> {
+            Err(AppError::invalid_input(format!(
+                "Invalid log level: {}",
+                level
+            )))
+        }
+    }
+
+    fn device(id: &str, name: &str, is_default: bool) -> AudioDevice {
+        AudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            is_default,
+        }
+    }
+
+    fn request(method: &str, target: &str, headers: &[(&str, &str)], body: &str) -> Request {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query.to_string(),
+            headers: headers
+                .iter()
+                .map(|(field, value)| (field.to_string(), value.to_string()))
+                .collect(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    fn with_token(method: &str, path: &str, body: &str) -> Request {
+        let bearer = format!("Bearer {}", TOKEN);
+        request(method, path, &[("Authorization", &bearer)], body)
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_strings() {
+        assert!(constant_time_eq("", ""));
+        assert!(constant_time_eq("token", "token"));
+        assert!(!constant_time_eq("token", "tokem"));
+        assert!(!constant_time_eq("token", "token2"));
+        assert!(!constant_time_eq("Token", "token"));
+    }
+
+    #[test]
+    fn authorized_needs_the_bearer_token() {
+        let host = FakeHost::default();
+        let bearer = format!("Bearer {}", TOKEN);
+
+        assert!(authorized(
+            &host,
+            &request("GET", "/devices", &[("authorization", &bearer)], "")
+        ));
+        assert!(!authorized(&host, &request("GET", "/devices", &[], "")));
+        assert!(!authorized(
+            &host,
+            &request("GET", "/devices", &[("Authorization", TOKEN)], "")
+        ));
+        assert!(!authorized(
+            &host,
+            &request("GET", "/devices", &[("Authorization", "Bearer wrong")], "")
+        ));
+
+        // 没有 token 时拒绝所有请求，包括空的 Bearer
+        let no_token = FakeHost {
+            token: String::new(),
+            ..FakeHost::default()
+        };
+        assert!(!authorized(
+            &no_token,
+            &request("GET", "/devices", &[("Authorization", "Bearer ")], "")
+        ));
+    }
+
+    #[test]
+    fn query_token_only_works_for_the_event_stream() {
+        let host = FakeHost::default();
+        let query = format!("/events?foo=1&token={}", TOKEN);
+        assert!(authorized(&host, &request("GET", &query, &[], "")));
+        assert!(!authorized(
+            &host,
+            &request("GET", "/events?token=wrong", &[], "")
+        ));
+
+        let query = format!("/devices?token={}", TOKEN);
+        assert!(!authorized(&host, &request("GET", &query, &[], "")));
+    }
+
+    #[test]
+    fn status_follows_the_error_code() {
+        let status_of = |code| status(&AppError::new(code, "error"));
+        assert_eq!(status_of(ErrorCode::InvalidInput), 400);
+        assert_eq!(status_of(ErrorCode::Permission), 403);
+        assert_eq!(status_of(ErrorCode::DeviceNotFound), 404);
+        assert_eq!(status_of(ErrorCode::NotFound), 404);
+        assert_eq!(status_of(ErrorCode::DeviceInactive), 409);
+        assert_eq!(status_of(ErrorCode::BackendUnavailable), 503);
+        assert_eq!(status_of(ErrorCode::ConfigIo), 500);
+        assert_eq!(status_of(ErrorCode::Internal), 500);
+    }
+
+    #[test]
+    fn respond_checks_the_token_before_routing() {
+        let host = FakeHost::default();
+
+        let (status, body) = respond(&host, &request("GET", "/devices", &[], ""));
+        assert_eq!(status, 401);
+        assert_eq!(body["code"], "permission");
+
+        // 接口文档不需要 token
+        let (status, body) = respond(&host, &request("GET", "/openapi.json", &[], ""));
+        assert_eq!(status, 200);
+        assert_eq!(body["openapi"], "3.0.3");
+
+        let (status, _) = respond(&host, &with_token("GET", "/events", ""));
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn route_serves_the_endpoints() {
+        let host = FakeHost::default();
+
+        let (status, body) = respond(&host, &with_token("GET", "/devices", ""));
+        assert_eq!(status, 200);
+        assert_eq!(body[1]["name"], "Headphones");
+
+        let (status, body) = respond(
+            &host,
+            &with_token("PUT", "/default", r#"{"device":"Headphones"}"#),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["id"], "2");
+        assert_eq!(*host.switched.lock().unwrap(), ["2"]);
+
+        let (status, body) = respond(
+            &host,
+            &with_token("PUT", "/default", r#"{"device":"Missing"}"#),
+        );
+        assert_eq!(status, 404);
+        assert_eq!(body["code"], "device_not_found");
+
+        let (status, _) = respond(&host, &with_token("PUT", "/default", "not json"));
+        assert_eq!(status, 400);
+        let (status, _) = respond(&host, &with_token("GET", "/default", ""));
+        assert_eq!(status, 503);
+        let (status, _) = respond(&host, &with_token("DELETE", "/devices", ""));
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn settings_update_only_the_given_fields() {
+        let host = FakeHost::default();
+
+        let (status, body) = respond(
+            &host,
+            &with_token("PUT", "/settings", r#"{"auto_hide_delay":10}"#),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(
+            body,
+            json!({ "auto_hide_delay": 10, "dark_mode": false, "log_level": "info" })
+        );
+
+        let (status, _) = respond(&host, &with_token("PUT", "/settings", r#"{"volume":10}"#));
+        assert_eq!(status, 400);
+        let (status, body) = respond(
+            &host,
+            &with_token("PUT", "/settings", r#"{"log_level":"loud"}"#),
+        );
+        assert_eq!(status, 400);
+        assert_eq!(body["message"], "Invalid log level: loud");
+    }
+
+    #[test]
+    fn read_request_parses_head_and_body() {
+        let raw = "PUT /default?x=1 HTTP/1.1\r\nHost: localhost\r\n\
+                   Content-Length: 22\r\n\r\n{\"device\":\"Speakers\"}\nextra";
+        let request = read_request(&mut Cursor::new(raw.as_bytes().to_vec())).unwrap();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/default");
+        assert_eq!(request.query, "x=1");
+        assert_eq!(request.header("host"), Some("localhost"));
+        // 只读取 Content-Length 长度的请求体
+        assert_eq!(request.body, b"{\"device\":\"Speakers\"}\n");
+
+        // 没有 Content-Length 时保留请求头之后已经读到的数据，供 WebSocket 使用
+        let raw = "GET /events HTTP/1.1\r\nUpgrade: websocket\r\n\r\nframe";
+        let request = read_request(&mut Cursor::new(raw.as_bytes().to_vec())).unwrap();
+        assert_eq!(request.body, b"frame");
+    }
+
+    #[test]
+    fn read_request_rejects_bad_requests() {
+        let read = |raw: String| read_request(&mut Cursor::new(raw.into_bytes()));
+
+        let error = read("NOT A REQUEST\r\n\r\n".to_string()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let raw = format!(
+            "PUT /default HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert_eq!(read(raw).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let raw = format!("GET /{} HTTP/1.1\r\n", "a".repeat(MAX_HEAD_BYTES));
+        assert_eq!(read(raw).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // 客户端在请求体发完之前断开
+        let raw = "PUT /default HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}".to_string();
+        assert_eq!(read(raw).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn responses_close_the_connection() {
+        let mut output = Vec::new();
+        write_response(&mut output, 404, &json!({ "code": "not_found" })).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(text.contains("Content-Length: 20\r\nConnection: close\r\n\r\n"));
+        assert!(text.ends_with("{\"code\":\"not_found\"}"));
+    }
+}
@@ -1,7 +1,242 @@
+// AudioCat：常驻托盘的音频输出切换器。托盘、窗口和 Tauri 命令在这个库中，main.rs 只调用 run()；
+// 不依赖 Tauri 的部分在 audiocat-core 中，示例程序和其他工具直接链接它。
+mod auto_hide;
+mod commands;
+mod config;
+#[cfg(all(target_os = "linux", feature = "dbus"))]
+mod dbus;
+mod event_stream;
+mod http_api;
+mod monitor;
+mod mqtt;
+mod osc;
+mod rpc;
+mod single_instance;
+mod state;
+mod tray;
+
+// 音频、配置、规则和平台后端都在 audiocat-core 中，这里保持原来的 crate:: 路径
+use audiocat_core::{
+    audio, error, event_history, focus_switch, local_socket, logging, process_monitor, rules,
+    schedule, switch_history, window_picker, window_source,
+};
+
+use audio::AudioClient;
+use auto_hide::AutoHideSender;
+use config::load_window_config;
+use event_history::{EventHistory, SharedEventHistory};
+use http_api::HttpServer;
+use log::{debug, error, info, warn};
+use logging::{CONFIG, TRAY};
+use monitor::WindowTracker;
+use mqtt::MqttBridge;
+use osc::OscServer;
+use process_monitor::{ProcessTracker, SharedProcessSource, default_process_source};
+use rules::RuleLog;
+use schedule::{Scheduler, SharedClock, default_clock};
+use single_instance::Instance;
+use state::{AppState, SharedState};
+use std::sync::{Arc, Mutex};
+use switch_history::SwitchHistory;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::ShortcutState;
+use window_source::{SharedWindowSource, default_window_source};
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
+
+    // 已有实例在运行时把参数转发给它，由它执行
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let listener = match single_instance::claim(&args) {
+        Instance::Primary(listener) => listener,
+        Instance::Secondary(Ok(())) => return,
+        Instance::Secondary(Err(e)) => {
+            // 这个进程没有日志文件，马上就会退出，错误需要输出到控制台
+            logging::set_console(true);
+            error!(target: TRAY, "Failed to forward arguments to the running instance: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
+        .setup(move |app| {
+            // 日志文件写在应用数据目录下的 logs 目录
+            match app.path().app_data_dir() {
+                Ok(dir) => match logging::attach_file(&dir.join("logs")) {
+                    Ok(path) => info!(target: TRAY, "Writing log to {}", path.display()),
+                    Err(e) => warn!(target: TRAY, "{}", e),
+                },
+                Err(e) => warn!(target: TRAY, "Failed to get app data directory for log: {}", e),
+            }
+
+            // 从配置文件加载窗口配置
+            let window_config = load_window_config(app.handle());
+            match logging::parse_level(&window_config.log_level) {
+                Ok(level) => logging::set_level(level),
+                Err(e) => warn!(target: CONFIG, "{}", e),
+            }
+
+            // 创建带有加载配置的应用状态
+            let app_state = AppState {
+                is_pinned: false,
+                monitored_windows: window_config.monitored_windows,
+                auto_hide_delay: window_config.auto_hide_delay,
+                window_tracker: WindowTracker::default(),
+                switch_history: SwitchHistory::default(),
+                focus_switch: window_config.focus_switch.clone(),
+                process_rules: window_config.process_rules.clone(),
+                process_tracker: ProcessTracker::default(),
+                schedule_rules: window_config.schedule_rules.clone(),
+                scheduler: Scheduler::default(),
+                rules: window_config.rules.clone(),
+                rule_log: RuleLog::default(),
+                known_devices: None,
+                device_subscribers: 0,
+                monitoring_active: false,
+                dark_mode: window_config.dark_mode, // 从配置文件加载深色模式
+                http_api: window_config.http_api.clone(),
+                mqtt: window_config.mqtt.clone(),
+                osc: window_config.osc.clone(),
+            };
+
+            app.manage(SharedState::new(Mutex::new(app_state)));
+            app.manage::<SharedWindowSource>(default_window_source());
+            app.manage::<SharedProcessSource>(default_process_source());
+            app.manage::<SharedClock>(default_clock());
+            app.manage(AudioClient::spawn());
+            app.manage::<AutoHideSender>(tray::start_auto_hide_loop(app.handle().clone()));
+
+            // 事件历史保存在应用数据目录，取不到目录时只保存在内存中
+            let event_history = match app.path().app_data_dir() {
+                Ok(dir) => EventHistory::load(dir.join("event_history.jsonl")),
+                Err(e) => {
+                    warn!(
+                        target: CONFIG,
+                        "Failed to get app data directory for event history: {}",
+                        e
+                    );
+                    EventHistory::default()
+                }
+            };
+            app.manage::<SharedEventHistory>(Arc::new(Mutex::new(event_history)));
+
+            // 启动后台窗口监听，规则在后端匹配，不再依赖前端定时器
+            monitor::start_monitor_loop(app.handle().clone());
+            monitor::start_focus_switch_loop(app.handle().clone());
+            monitor::start_schedule_loop(app.handle().clone());
+
+            // 规则引擎的全局快捷键
+            app.handle().plugin(
+                tauri_plugin_global_shortcut::Builder::new()
+                    .with_handler(|app, shortcut, event| {
+                        if event.state() == ShortcutState::Pressed {
+                            monitor::handle_hotkey(app, shortcut);
+                        }
+                    })
+                    .build(),
+            )?;
+            monitor::register_rule_hotkeys(app.handle());
+
+            // 应用保存的主窗口位置
+            if let Some(main_window) = app.get_webview_window("main") {
+                if let Some(position) = window_config.main_window_position {
+                    let _ = main_window.set_position(tauri::Position::Physical(
+                        tauri::PhysicalPosition {
+                            x: position.x,
+                            y: position.y,
+                        },
+                    ));
+                    let _ = main_window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
+                        width: position.width,
+                        height: position.height,
+                    }));
+                    debug!(
+                        target: CONFIG,
+                        "Restored main window position: {}x{} at ({}, {})",
+                        position.width, position.height, position.x, position.y
+                    );
+                }
+            }
+
+            tray::build_tray(app)?;
+
+            if let Some(listener) = listener {
+                single_instance::start_listener(app.handle().clone(), listener);
+            }
+            rpc::start_server(app.handle().clone());
+            #[cfg(all(target_os = "linux", feature = "dbus"))]
+            dbus::start_service(app.handle().clone());
+            app.manage(HttpServer::default());
+            http_api::init(app.handle());
+            app.manage(MqttBridge::default());
+            mqtt::apply(app.handle(), &window_config.mqtt);
+            app.manage(OscServer::default());
+            osc::apply(app.handle(), &window_config.osc);
+            // 第一个实例启动时带的参数
+            let launch = single_instance::parse_args(&args)
+                .and_then(|actions| single_instance::execute(app.handle(), &actions));
+            if let Err(e) = launch {
+                warn!(target: TRAY, "Failed to apply launch arguments: {}", e);
+            }
+
+            // 主窗口默认为正常模式，用户可以通过界面切换到穿透模式
+            info!(target: TRAY, "Application initialized successfully");
+
+            Ok(())
+        })
+        .on_window_event(tray::on_window_event)
+        .plugin(tauri_plugin_fs::init()) // 初始化文件系统插件
+        .plugin(tauri_plugin_notification::init()) // 规则的通知动作
+        .invoke_handler(tauri::generate_handler![
+            commands::set_audio_device,
+            commands::revert_audio_device,
+            commands::get_switch_history,
+            commands::get_event_history,
+            commands::clear_event_history,
+            commands::set_log_level,
+            commands::get_log_level,
+            commands::get_log_tail,
+            commands::get_focus_switch_config,
+            commands::set_focus_switch_config,
+            commands::set_process_rules,
+            commands::get_process_rules,
+            commands::set_schedule_rules,
+            commands::get_schedule_rules,
+            commands::set_rules,
+            commands::get_rules,
+            commands::get_rule_log,
+            commands::test_rule,
+            commands::cancel_auto_hide,
+            commands::set_auto_hide_hover,
+            commands::test_auto_hide,
+            commands::get_current_audio_device,
+            commands::get_audio_output_devices,
+            commands::create_app_data_dir,
+            commands::test_drag_functionality,
+            commands::set_window_pinned,
+            commands::get_window_pinned,
+            commands::get_running_windows,
+            commands::get_running_window_info,
+            commands::set_monitored_windows,
+            commands::get_monitored_windows,
+            commands::set_auto_hide_delay,
+            commands::get_auto_hide_delay,
+            commands::start_window_monitoring,
+            commands::stop_window_monitoring,
+            commands::save_window_position,
+            commands::get_window_position,
+            commands::set_dark_mode,
+            commands::get_dark_mode,
+            commands::open_config_folder,
+            commands::get_config_file_path,
+            commands::get_http_api_config,
+            commands::set_http_api_config,
+            commands::get_mqtt_config,
+            commands::set_mqtt_config,
+            commands::get_osc_config,
+            commands::set_osc_config
+        ]) // 添加命令处理
         .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .expect("error while running tauri app");
 }
@@ -0,0 +1,355 @@
+// 规则执行：把事件交给匹配的规则，检查条件并执行动作；设备切换和恢复会写入切换历史和事件历史
+use crate::audio::{AudioBackend, AudioDevice, SwitchOutcome};
+use crate::auto_hide::AutoHideCommand;
+use crate::error::{AppError, AppResult};
+use crate::event_history::EventKind;
+use crate::logging::MONITOR;
+use crate::rules::{
+    ActionOutcome, Rule, RuleAction, RuleContext, RuleEvent, RuleFiring, RuleTrigger, legacy_rules,
+};
+use crate::schedule::SharedClock;
+use crate::state::{
+    AppState, SharedState, audio, main_window, record_event, window_error, with_state,
+};
+use crate::switch_history::{SwitchCause, SwitchRecord};
+use crate::tray::{send_auto_hide, show_switcher};
+use log::{info, warn};
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use tauri_plugin_notification::NotificationExt;
+
+// 把切换前的设备和原因记录到切换历史和事件历史，返回给前端的提示
+pub fn record_switch(
+    app_handle: &tauri::AppHandle,
+    outcome: SwitchOutcome,
+    cause: SwitchCause,
+) -> String {
+    let SwitchOutcome { previous, current } = outcome;
+    let previous_name = previous
+        .as_ref()
+        .map(|device| device.name.clone())
+        .unwrap_or_default();
+    record_event(
+        app_handle,
+        EventKind::Device,
+        format!(
+            "default device: {} -> {} ({})",
+            previous_name,
+            current.name,
+            cause.describe()
+        ),
+        serde_json::json!({ "device_id": &current.id, "cause": &cause }),
+    );
+    let _ = app_handle.emit(
+        "device-switched",
+        serde_json::json!({ "previous": &previous, "current": &current, "cause": &cause }),
+    );
+
+    if let Some(previous) = previous {
+        let _ = with_state(app_handle, |app_state| {
+            app_state
+                .switch_history
+                .push(previous.id, current.id.clone(), cause)
+        });
+    }
+
+    format!("成功切换到音频设备: {}", current.name)
+}
+
+// 规则的"恢复之前的设备"动作，在监听线程上同步执行
+fn revert_device_for_rule(app_handle: &tauri::AppHandle) -> AppResult<()> {
+    let audio = audio(app_handle)?;
+    let devices = audio.call_blocking(AudioBackend::devices)?;
+    let record = pop_revert_record(app_handle, &devices)?;
+
+    let device_id = record.previous_device_id.clone();
+    let outcome = audio.call_blocking(move |backend| backend.set_default(&device_id))?;
+    record_revert(app_handle, &record, &outcome);
+    Ok(())
+}
+
+// 从切换历史中取出最近一条之前设备仍然存在的记录
+pub fn pop_revert_record(
+    app_handle: &tauri::AppHandle,
+    devices: &[AudioDevice],
+) -> AppResult<SwitchRecord> {
+    let available_ids: Vec<String> = devices.iter().map(|device| device.id.clone()).collect();
+    with_state(app_handle, |app_state| {
+        app_state.switch_history.pop_available(&available_ids)
+    })?
+    .ok_or_else(|| AppError::not_found("No previous audio device to revert to"))
+}
+
+pub fn record_revert(
+    app_handle: &tauri::AppHandle,
+    record: &SwitchRecord,
+    outcome: &SwitchOutcome,
+) -> String {
+    record_event(
+        app_handle,
+        EventKind::Device,
+        format!("reverted default device: {}", outcome.current.name),
+        record,
+    );
+    let _ = app_handle.emit(
+        "device-switched",
+        serde_json::json!({
+            "previous": &outcome.previous,
+            "current": &outcome.current,
+            "cause": { "type": "revert" },
+        }),
+    );
+    let _ = app_handle.emit("audio-device-changed", ());
+    format!("成功切换到音频设备: {}", outcome.current.name)
+}
+
+// 规则触发记录在事件历史中的描述
+pub fn describe_firing(firing: &RuleFiring) -> String {
+    let name = if firing.dry_run {
+        format!("dry run of rule '{}'", firing.rule_name)
+    } else {
+        format!("rule '{}'", firing.rule_name)
+    };
+    match &firing.skipped_by {
+        Some(condition) => format!(
+            "{} skipped, condition not met: {} (trigger: {})",
+            name, condition, firing.trigger
+        ),
+        None => {
+            let failed = firing
+                .actions
+                .iter()
+                .filter(|outcome| outcome.error.is_some())
+                .count();
+            if failed > 0 {
+                format!(
+                    "{} fired by {}, {} of {} actions failed",
+                    name,
+                    firing.trigger,
+                    failed,
+                    firing.actions.len()
+                )
+            } else {
+                format!("{} fired by {}", name, firing.trigger)
+            }
+        }
+    }
+}
+
+// 当前生效的规则：窗口/进程/定时规则转换成的规则在前，用户规则在后，跳过已禁用的规则
+pub fn active_rules(app_state: &AppState) -> Vec<Rule> {
+    let mut rules = legacy_rules(
+        &app_state.monitored_windows,
+        &app_state.process_rules,
+        &app_state.schedule_rules,
+    );
+    rules.extend(app_state.rules.iter().cloned());
+    rules.retain(|rule| rule.enabled);
+    rules
+}
+
+// 把事件交给所有匹配的规则
+pub fn dispatch_rule_event(app_handle: &tauri::AppHandle, event: &RuleEvent) {
+    let Some(state) = app_handle.try_state::<SharedState>() else {
+        return;
+    };
+    let rules = match state.lock() {
+        Ok(app_state) => active_rules(&app_state),
+        Err(_) => return,
+    };
+
+    let trigger = event.describe();
+    for rule in rules.iter().filter(|rule| rule.trigger.matches(event)) {
+        fire_rule(app_handle, rule, &trigger);
+    }
+}
+
+// current_device 由调用方读取：监听线程同步读取，试运行命令异步读取
+pub fn rule_context(
+    app_handle: &tauri::AppHandle,
+    current_device: Option<AudioDevice>,
+) -> RuleContext {
+    let now = match app_handle.try_state::<SharedClock>() {
+        Some(clock) => clock.now(),
+        None => chrono::Local::now().naive_local(),
+    };
+    RuleContext {
+        current_device: current_device.map(|device| (device.id, device.name)),
+        now,
+    }
+}
+
+// 检查条件并按顺序执行规则的动作；每次触发都写入触发记录
+pub fn fire_rule(app_handle: &tauri::AppHandle, rule: &Rule, trigger: &str) {
+    let mut firing = RuleFiring::new(rule, trigger, false);
+
+    if !rule.conditions.is_empty() {
+        let current_device = audio(app_handle)
+            .and_then(|audio| audio.call_blocking(AudioBackend::current_device))
+            .ok();
+        firing.skipped_by = rule
+            .failed_condition(&rule_context(app_handle, current_device))
+            .map(|condition| condition.describe());
+    }
+
+    match &firing.skipped_by {
+        Some(condition) => info!(
+            target: MONITOR,
+            "Rule '{}' skipped, condition not met: {}",
+            rule.label(),
+            condition
+        ),
+        None => {
+            info!(target: MONITOR, "Rule '{}' fired by {}", rule.label(), trigger);
+            for action in &rule.actions {
+                let result = run_rule_action(app_handle, action, trigger);
+                if let Err(e) = &result {
+                    warn!(target: MONITOR, "Rule action '{}' failed: {}", action.describe(), e);
+                }
+                firing.actions.push(ActionOutcome {
+                    action: action.describe(),
+                    error: result.err().map(|e| e.to_string()),
+                });
+            }
+        }
+    }
+
+    let _ = app_handle.emit("rule-fired", &firing);
+    record_event(
+        app_handle,
+        EventKind::Rule,
+        describe_firing(&firing),
+        &firing,
+    );
+    if let Some(state) = app_handle.try_state::<SharedState>() {
+        if let Ok(mut app_state) = state.lock() {
+            app_state.rule_log.push(firing);
+        }
+    }
+}
+
+// 执行单个动作，trigger 是触发规则的窗口标题、进程名等
+fn run_rule_action(
+    app_handle: &tauri::AppHandle,
+    action: &RuleAction,
+    trigger: &str,
+) -> AppResult<()> {
+    match action {
+        RuleAction::SwitchDevice { device } => switch_device_for_rule(app_handle, device, trigger),
+        RuleAction::RevertDevice => revert_device_for_rule(app_handle),
+        RuleAction::SetVolume { level } => {
+            let level = f32::from(*level) / 100.0;
+            audio(app_handle)?.call_blocking(move |backend| backend.set_volume(level))
+        }
+        RuleAction::Mute { muted } => {
+            let muted = *muted;
+            audio(app_handle)?.call_blocking(move |backend| backend.set_mute(muted))
+        }
+        RuleAction::ShowSwitcher => show_switcher(app_handle, trigger),
+        RuleAction::HideSwitcher => {
+            send_auto_hide(app_handle, AutoHideCommand::Cancel);
+            main_window(app_handle)?.hide().map_err(window_error)
+        }
+        RuleAction::RunCommand { program, args } => {
+            // 不等待命令结束，避免阻塞监听线程
+            std::process::Command::new(program)
+                .args(args)
+                .spawn()
+                .map(|_| ())
+                .map_err(|e| {
+                    AppError::invalid_input(format!("Failed to run {}", program)).with_detail(e)
+                })
+        }
+        RuleAction::Notify { title, body } => app_handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+            .map_err(|e| AppError::backend("Failed to show notification").with_detail(e)),
+    }
+}
+
+// 当前规则里配置的全局快捷键
+fn rule_hotkeys(app_handle: &tauri::AppHandle) -> Vec<String> {
+    let Some(state) = app_handle.try_state::<SharedState>() else {
+        return Vec::new();
+    };
+    let Ok(app_state) = state.lock() else {
+        return Vec::new();
+    };
+
+    let mut shortcuts: Vec<String> = active_rules(&app_state)
+        .into_iter()
+        .filter_map(|rule| match rule.trigger {
+            RuleTrigger::Hotkey { shortcut } => Some(shortcut),
+            _ => None,
+        })
+        .collect();
+    shortcuts.sort();
+    shortcuts.dedup();
+    shortcuts
+}
+
+// 按当前规则重新注册全局快捷键
+pub fn register_rule_hotkeys(app_handle: &tauri::AppHandle) {
+    let global_shortcut = app_handle.global_shortcut();
+    if let Err(e) = global_shortcut.unregister_all() {
+        warn!(target: MONITOR, "Failed to unregister shortcuts: {}", e);
+    }
+    for shortcut in rule_hotkeys(app_handle) {
+        if let Err(e) = global_shortcut.register(shortcut.as_str()) {
+            warn!(target: MONITOR, "Failed to register shortcut {}: {}", shortcut, e);
+        }
+    }
+}
+
+// 全局快捷键按下时，找出对应的规则快捷键再交给规则引擎
+pub fn handle_hotkey(app_handle: &tauri::AppHandle, pressed: &Shortcut) {
+    // 同一个快捷键可能有多种写法（如 Ctrl+Alt+A 和 alt+ctrl+a），按解析后的结果比较
+    let configured = rule_hotkeys(app_handle).into_iter().find(|shortcut| {
+        shortcut
+            .parse::<Shortcut>()
+            .is_ok_and(|shortcut| shortcut.id() == pressed.id())
+    });
+    if let Some(shortcut) = configured {
+        dispatch_rule_event(app_handle, &RuleEvent::Hotkey(&shortcut));
+    }
+}
+
+// 规则切换设备：target 可以是设备 id 或设备名称
+fn switch_device_for_rule(
+    app_handle: &tauri::AppHandle,
+    target: &str,
+    trigger: &str,
+) -> AppResult<()> {
+    let cause = SwitchCause::Rule {
+        trigger: trigger.to_string(),
+    };
+    switch_device(app_handle, target, cause).map(|_| ())
+}
+
+// 按设备 id 或名称切换默认设备，目标已经是默认设备时不做任何事；返回切换后的默认设备
+pub fn switch_device(
+    app_handle: &tauri::AppHandle,
+    target: &str,
+    cause: SwitchCause,
+) -> AppResult<AudioDevice> {
+    let audio = audio(app_handle)?;
+    let device = audio
+        .call_blocking(AudioBackend::devices)?
+        .into_iter()
+        .find(|device| device.matches(target))
+        .ok_or_else(|| AppError::device_not_found(target))?;
+
+    if device.is_default {
+        return Ok(device);
+    }
+
+    let outcome = audio.call_blocking(move |backend| backend.set_default(&device.id))?;
+    let current = outcome.current.clone();
+    record_switch(app_handle, outcome, cause);
+
+    let _ = app_handle.emit("audio-device-changed", ());
+    Ok(current)
+}
@@ -0,0 +1,423 @@
+// 后台监听：窗口、进程、设备变化和定时规则的轮询线程，以及焦点跟随线程。
+// 检测到的事件交给 engine 中的规则引擎执行，窗口规则的匹配逻辑在 audiocat-core 中。
+mod engine;
+
+pub use audiocat_core::monitor::{MonitorRule, WindowEvent, WindowTracker};
+pub use engine::{
+    describe_firing, handle_hotkey, pop_revert_record, record_revert, record_switch,
+    register_rule_hotkeys, rule_context, switch_device,
+};
+
+use crate::audio::{AudioBackend, AudioDevice};
+use crate::error::{AppError, AppResult};
+use crate::event_history::EventKind;
+use crate::focus_switch::{FocusSwitchConfig, FocusSwitcher, watch_focus};
+use crate::logging::{AUDIO, MONITOR};
+use crate::process_monitor::{ProcessEvent, SharedProcessSource};
+use crate::rules::{DeviceTrigger, Rule, RuleEvent, RuleTrigger};
+use crate::schedule::{ScheduleEntry, SharedClock};
+use crate::state::{SharedState, audio, record_event, with_state};
+use crate::switch_history::SwitchCause;
+use crate::window_source::{SharedWindowSource, WindowInfo};
+use engine::{active_rules, dispatch_rule_event, fire_rule};
+use log::{info, warn};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+// 后台监听线程的轮询间隔
+const MONITOR_INTERVAL: Duration = Duration::from_secs(1);
+
+// 启动后台窗口监听线程，规则匹配和动作执行都在这里完成
+pub fn start_monitor_loop(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(MONITOR_INTERVAL);
+            check_window_changes(&app_handle);
+            check_process_changes(&app_handle);
+            check_device_changes(&app_handle);
+        }
+    });
+}
+
+// 检查窗口变化并执行触发的规则（由后台监听线程调用）
+fn check_window_changes(app_handle: &tauri::AppHandle) {
+    let Some(state) = app_handle.try_state::<SharedState>() else {
+        return;
+    };
+
+    let matchers: Vec<_> = {
+        let Ok(app_state) = state.lock() else {
+            return;
+        };
+        if !app_state.monitoring_active {
+            return;
+        }
+        active_rules(&app_state)
+            .into_iter()
+            .filter_map(|rule| match rule.trigger {
+                RuleTrigger::Window { matcher, .. } => Some(matcher),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // 枚举窗口可能很慢（X11 上每个窗口都要往返几次），不能在持有全局状态锁时进行
+    let current_windows = get_current_windows(app_handle);
+    let focused_window = get_focused_window(app_handle);
+
+    // 只有窗口实例真正消失才算关闭，标题变化单独上报
+    let events = match state.lock() {
+        Ok(mut app_state) => {
+            app_state
+                .window_tracker
+                .update(current_windows, focused_window, &matchers)
+        }
+        Err(_) => return,
+    };
+
+    for event in &events {
+        match event {
+            WindowEvent::Opened { window } => {
+                info!(target: MONITOR, "Detected window opened: {}", window.title)
+            }
+            WindowEvent::Closed { window } => {
+                info!(target: MONITOR, "Detected window closed: {}", window.title)
+            }
+            WindowEvent::Focused { window } => {
+                info!(target: MONITOR, "Detected window focused: {}", window.title)
+            }
+            WindowEvent::TitleChanged { window, old_title } => {
+                info!(
+                    target: MONITOR,
+                    "Detected window title changed: {} -> {}",
+                    old_title, window.title
+                );
+            }
+        }
+        let _ = app_handle.emit("window-event", event);
+        record_event(app_handle, EventKind::Window, event.describe(), event);
+        dispatch_rule_event(app_handle, &RuleEvent::Window(event));
+    }
+}
+
+// 检查进程启动/退出并执行触发的规则（由后台监听线程调用）
+fn check_process_changes(app_handle: &tauri::AppHandle) {
+    let Some(state) = app_handle.try_state::<SharedState>() else {
+        return;
+    };
+    let Some(source) = app_handle.try_state::<SharedProcessSource>() else {
+        return;
+    };
+
+    // 进程监听不依赖窗口监听的开关：没有窗口的后台程序正是它要覆盖的场景
+    let names: Vec<String> = {
+        let Ok(app_state) = state.lock() else {
+            return;
+        };
+        active_rules(&app_state)
+            .into_iter()
+            .filter_map(|rule| match rule.trigger {
+                RuleTrigger::Process { process, .. } => Some(process),
+                _ => None,
+            })
+            .collect()
+    };
+    // 没有进程规则时不扫描进程表
+    if names.is_empty() {
+        return;
+    }
+
+    // 扫描进程表不能在持有全局状态锁时进行
+    let processes = match source.list_processes() {
+        Ok(processes) => processes,
+        Err(e) => {
+            warn!(target: MONITOR, "Failed to enumerate processes: {}", e);
+            return;
+        }
+    };
+    let events = match state.lock() {
+        Ok(mut app_state) => app_state.process_tracker.update(processes, &names),
+        Err(_) => return,
+    };
+
+    for event in &events {
+        match event {
+            ProcessEvent::Started { process } => {
+                info!(
+                    target: MONITOR,
+                    "Detected process started: {} ({})",
+                    process.name, process.pid
+                );
+            }
+            ProcessEvent::Exited { process } => {
+                info!(
+                    target: MONITOR,
+                    "Detected process exited: {} ({})",
+                    process.name, process.pid
+                );
+            }
+        }
+        let _ = app_handle.emit("process-event", event);
+        record_event(app_handle, EventKind::Process, event.describe(), event);
+        dispatch_rule_event(app_handle, &RuleEvent::Process(event));
+    }
+}
+
+// 检查输出设备的连接、断开和默认设备的变化（由后台监听线程调用）
+fn check_device_changes(app_handle: &tauri::AppHandle) {
+    let Some(state) = app_handle.try_state::<SharedState>() else {
+        return;
+    };
+
+    // 没有设备触发的规则、也没有外部订阅者时不枚举设备
+    let should_check = match state.lock() {
+        Ok(app_state) => {
+            app_state.device_subscribers > 0
+                || active_rules(&app_state)
+                    .iter()
+                    .any(|rule| matches!(rule.trigger, RuleTrigger::Device { .. }))
+        }
+        Err(_) => return,
+    };
+    if !should_check {
+        if let Ok(mut app_state) = state.lock() {
+            app_state.known_devices = None;
+        }
+        return;
+    }
+
+    let current =
+        match audio(app_handle).and_then(|audio| audio.call_blocking(AudioBackend::devices)) {
+            Ok(devices) => devices,
+            Err(e) => {
+                // 枚举失败时不更新快照，避免把所有设备误判为断开
+                warn!(target: AUDIO, "Failed to enumerate audio devices: {}", e);
+                return;
+            }
+        };
+    let previous = match state.lock() {
+        Ok(mut app_state) => app_state.known_devices.replace(current.clone()),
+        Err(_) => return,
+    };
+    // 第一次检查只记录快照
+    let Some(previous) = previous else {
+        return;
+    };
+
+    // 设备增减或默认设备变化（包括在系统设置中切换）时通知前端和外部订阅者
+    let default_id = |devices: &[AudioDevice]| {
+        devices
+            .iter()
+            .find(|device| device.is_default)
+            .map(|device| device.id.clone())
+    };
+    let ids = |devices: &[AudioDevice]| -> Vec<String> {
+        devices.iter().map(|device| device.id.clone()).collect()
+    };
+    if ids(&current) != ids(&previous) || default_id(&current) != default_id(&previous) {
+        let _ = app_handle.emit("devices-changed", &current);
+    }
+
+    for device in current
+        .iter()
+        .filter(|device| !previous.iter().any(|p| p.id == device.id))
+    {
+        info!(target: AUDIO, "Detected audio device connected: {}", device.name);
+        record_event(
+            app_handle,
+            EventKind::Device,
+            format!("connected: {}", device.name),
+            device,
+        );
+        dispatch_rule_event(
+            app_handle,
+            &RuleEvent::Device {
+                event: DeviceTrigger::Connected,
+                id: &device.id,
+                name: &device.name,
+            },
+        );
+    }
+    for device in previous
+        .iter()
+        .filter(|device| !current.iter().any(|c| c.id == device.id))
+    {
+        info!(target: AUDIO, "Detected audio device disconnected: {}", device.name);
+        record_event(
+            app_handle,
+            EventKind::Device,
+            format!("disconnected: {}", device.name),
+            device,
+        );
+        dispatch_rule_event(
+            app_handle,
+            &RuleEvent::Device {
+                event: DeviceTrigger::Disconnected,
+                id: &device.id,
+                name: &device.name,
+            },
+        );
+    }
+}
+
+// 定时规则的检查间隔，规则精确到分钟
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(10);
+
+// 启动定时规则线程；睡眠唤醒后的第一次检查会发现时间跳变，按规则的 missed 策略补执行或跳过
+pub fn start_schedule_loop(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        loop {
+            check_schedule(&app_handle);
+            std::thread::sleep(SCHEDULE_INTERVAL);
+        }
+    });
+}
+
+fn check_schedule(app_handle: &tauri::AppHandle) {
+    let Some(state) = app_handle.try_state::<SharedState>() else {
+        return;
+    };
+    let Some(clock) = app_handle.try_state::<SharedClock>() else {
+        return;
+    };
+
+    let (rules, firings) = {
+        let Ok(mut app_state) = state.lock() else {
+            return;
+        };
+        let rules: Vec<Rule> = active_rules(&app_state)
+            .into_iter()
+            .filter(|rule| matches!(rule.trigger, RuleTrigger::Schedule { .. }))
+            .collect();
+        let entries: Vec<ScheduleEntry> = rules
+            .iter()
+            .filter_map(|rule| match &rule.trigger {
+                RuleTrigger::Schedule {
+                    schedule,
+                    edge,
+                    missed,
+                } => Some(ScheduleEntry {
+                    trigger: schedule,
+                    edge: *edge,
+                    missed: *missed,
+                }),
+                _ => None,
+            })
+            .collect();
+        let firings = app_state.scheduler.tick(clock.now(), &entries);
+        (rules, firings)
+    };
+
+    for firing in &firings {
+        let rule = &rules[firing.index];
+        if firing.catch_up {
+            info!(target: MONITOR, "Catching up missed schedule: {}", rule.label());
+        } else {
+            info!(target: MONITOR, "Schedule triggered: {}", rule.label());
+        }
+        fire_rule(app_handle, rule, &rule.label());
+    }
+}
+
+// 启动焦点跟随线程：前台窗口变化时按应用映射切换设备，焦点稳定一段时间后才真正切换
+pub fn start_focus_switch_loop(app_handle: tauri::AppHandle) {
+    let Some(source) = app_handle.try_state::<SharedWindowSource>() else {
+        return;
+    };
+    let source = source.inner().clone();
+    let focus_events = watch_focus(source.clone());
+
+    std::thread::spawn(move || {
+        let mut switcher = FocusSwitcher::new(Duration::from_millis(
+            FocusSwitchConfig::default().settle_ms,
+        ));
+
+        loop {
+            // 有待切换的目标时等到它的截止时间，否则一直等下一次焦点变化
+            let timeout = switcher
+                .next_deadline()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or(Duration::from_secs(60));
+
+            let event = focus_events.recv_timeout(timeout);
+            if let Err(RecvTimeoutError::Disconnected) = event {
+                break;
+            }
+
+            let config = with_state(&app_handle, |app_state| app_state.focus_switch.clone())
+                .unwrap_or_default();
+            if !config.enabled {
+                // 关闭后不能再执行之前排队的切换
+                switcher.cancel();
+                continue;
+            }
+            switcher.set_settle(Duration::from_millis(config.settle_ms));
+
+            if let Ok(window_id) = event {
+                let window = source
+                    .list_windows()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|window| window.id == window_id);
+                if let Some(window) = window {
+                    let current = audio(&app_handle)
+                        .and_then(|audio| audio.call_blocking(AudioBackend::current_device))
+                        .ok();
+                    switcher.on_focus(
+                        config.target_device(&window),
+                        current.as_ref(),
+                        Instant::now(),
+                    );
+                }
+            }
+
+            if let Some(target) = switcher.poll(Instant::now()) {
+                if let Err(e) = switch_device_for_focus(&app_handle, &target) {
+                    warn!(target: MONITOR, "Focus switch failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+// 焦点跟随切换设备，目标已经是默认设备时不做任何事
+fn switch_device_for_focus(app_handle: &tauri::AppHandle, target: &str) -> AppResult<()> {
+    let audio = audio(app_handle)?;
+    let device = audio
+        .call_blocking(AudioBackend::devices)?
+        .into_iter()
+        .find(|device| device.matches(target))
+        .ok_or_else(|| AppError::device_not_found(target))?;
+
+    if device.is_default {
+        return Ok(());
+    }
+
+    let cause = SwitchCause::Focus {
+        device: device.name.clone(),
+    };
+    let outcome = audio.call_blocking(move |backend| backend.set_default(&device.id))?;
+    record_switch(app_handle, outcome, cause);
+
+    let _ = app_handle.emit("audio-device-changed", ());
+    Ok(())
+}
+
+// 获取当前所有窗口的辅助函数
+pub fn get_current_windows(app_handle: &tauri::AppHandle) -> Vec<WindowInfo> {
+    match app_handle.try_state::<SharedWindowSource>() {
+        Some(source) => source.list_windows().unwrap_or_else(|e| {
+            warn!(target: MONITOR, "Failed to enumerate windows: {}", e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    }
+}
+
+// 获取当前前台窗口 id 的辅助函数
+pub fn get_focused_window(app_handle: &tauri::AppHandle) -> Option<u64> {
+    app_handle
+        .try_state::<SharedWindowSource>()
+        .and_then(|source| source.focused_window())
+}
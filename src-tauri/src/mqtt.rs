@@ -0,0 +1,517 @@
+// MQTT 桥接（默认关闭）：连接配置中的 broker，把当前设备、设备列表和音量发布为保留消息，
+// 订阅命令主题切换设备和显示切换器，并可以发布 Home Assistant 自动发现配置。主题前缀 P 默认
+// 是 audiocat/<主机名>：
+//   P/status       online / offline（遗嘱消息）
+//   P/device       当前默认设备名称        P/device/set  设备 id 或名称，切换默认设备
+//   P/devices      设备列表 JSON           P/show        显示切换器，内容忽略
+//   P/volume       默认设备音量 0-100
+use crate::audio::{AudioBackend, AudioDevice};
+use crate::config::MqttConfig;
+use crate::logging::API;
+use crate::monitor::switch_device;
+use crate::state::{audio, with_state};
+use crate::switch_history::SwitchCause;
+use crate::tray::show_switcher;
+use log::{debug, info, warn};
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{RecvTimeoutError, channel};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{EventId, Listener, Manager};
+
+// 音量没有变化事件，定期检查；外部程序切换设备也会在这时发现
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// 连不上 broker 时重试的间隔
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// 触发重新发布状态的应用事件
+const EVENTS: [&str; 2] = ["device-switched", "devices-changed"];
+
+// 正在运行的桥接
+#[derive(Default)]
+pub struct MqttBridge {
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    config: MqttConfig,
+    client: Client,
+    topics: Topics,
+    stop: Arc<AtomicBool>,
+    subscriptions: Vec<EventId>,
+}
+
+enum Update {
+    Connected, // 连接或重连成功，重新发布所有保留消息
+    Refresh,   // 设备变化，只发布有变化的消息
+}
+
+#[derive(Clone)]
+struct Topics {
+    prefix: String,
+    node_id: String, // Home Assistant 的 node_id 和 unique_id 前缀
+    discovery_prefix: Option<String>,
+}
+
+impl Topics {
+    fn new(config: &MqttConfig, hostname: &str) -> Self {
+        let node_id: String = hostname
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let prefix = match config.topic_prefix.trim().trim_end_matches('/') {
+            "" => format!("audiocat/{}", node_id),
+            prefix => prefix.to_string(),
+        };
+        Self {
+            prefix,
+            node_id,
+            discovery_prefix: config.discovery.then(|| {
+                config
+                    .discovery_prefix
+                    .trim()
+                    .trim_end_matches('/')
+                    .to_string()
+            }),
+        }
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.prefix, name)
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "audiocat".to_string())
+}
+
+// 按新配置启动、停止或重新连接；配置没有变化时不做任何事
+pub fn apply(app_handle: &tauri::AppHandle, config: &MqttConfig) {
+    let Some(bridge) = app_handle.try_state::<MqttBridge>() else {
+        return;
+    };
+    let Ok(mut running) = bridge.running.lock() else {
+        return;
+    };
+
+    if let Some(current) = running.as_ref() {
+        if config.enabled && current.config == *config {
+            return;
+        }
+    }
+    if let Some(current) = running.take() {
+        stop(app_handle, current);
+    }
+    if config.enabled {
+        *running = Some(start(app_handle, config));
+    }
+}
+
+fn stop(app_handle: &tauri::AppHandle, running: Running) {
+    running.stop.store(true, Ordering::SeqCst);
+    for id in running.subscriptions {
+        app_handle.unlisten(id);
+    }
+    // 正常断开时 broker 不发送遗嘱消息，先自己发布 offline
+    let status = running.topics.topic("status");
+    let _ = running
+        .client
+        .try_publish(status, QoS::AtLeastOnce, true, "offline");
+    let _ = running.client.try_disconnect();
+    let _ = with_state(app_handle, |app_state| {
+        app_state.device_subscribers = app_state.device_subscribers.saturating_sub(1)
+    });
+    info!(target: API, "MQTT bridge stopped");
+}
+
+fn start(app_handle: &tauri::AppHandle, config: &MqttConfig) -> Running {
+    let topics = Topics::new(config, &hostname());
+    let mut options = MqttOptions::new(
+        format!("audiocat-{}", topics.node_id),
+        config.host.trim(),
+        config.port,
+    );
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        topics.topic("status"),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if !config.username.is_empty() {
+        options.set_credentials(&config.username, &config.password);
+    }
+    let (client, mut connection) = Client::new(options, 64);
+    let stop = Arc::new(AtomicBool::new(false));
+    info!(
+        target: API,
+        "MQTT bridge connecting to {}:{} with prefix {}", config.host, config.port, topics.prefix
+    );
+
+    let (updates, receiver) = channel::<Update>();
+    let mut subscriptions = Vec::new();
+    for event in EVENTS {
+        let updates = updates.clone();
+        subscriptions.push(app_handle.listen_any(event, move |_| {
+            let _ = updates.send(Update::Refresh);
+        }));
+    }
+    // 有订阅者时监听线程即使没有设备规则也检查设备变化
+    let _ = with_state(app_handle, |app_state| app_state.device_subscribers += 1);
+
+    // 连接线程：驱动连接、断线重连并执行收到的命令
+    {
+        let app_handle = app_handle.clone();
+        let client = client.clone();
+        let topics = topics.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            for notification in connection.iter() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                match notification {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        info!(target: API, "MQTT bridge connected");
+                        // 在连接线程上不能用会阻塞的调用
+                        for name in ["device/set", "show"] {
+                            let _ = client.try_subscribe(topics.topic(name), QoS::AtLeastOnce);
+                        }
+                        let _ = updates.send(Update::Connected);
+                    }
+                    // 订阅时 broker 会转发命令主题上的保留消息，那是以前的命令，不能再执行一次
+                    Ok(Event::Incoming(Packet::Publish(publish))) if publish.retain => {
+                        debug!(target: API, "Ignoring retained MQTT message on {}", publish.topic);
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let payload = String::from_utf8_lossy(&publish.payload);
+                        run_command(&app_handle, &topics, &publish.topic, payload.trim());
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        warn!(target: API, "MQTT connection error: {}", e);
+                        std::thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        });
+    }
+
+    // 发布线程：只在内容变化时重新发布保留消息
+    {
+        let app_handle = app_handle.clone();
+        let client = client.clone();
+        let topics = topics.clone();
+        std::thread::spawn(move || {
+            let mut publisher = Publisher {
+                client,
+                published: HashMap::new(),
+            };
+            loop {
+                match receiver.recv_timeout(POLL_INTERVAL) {
+                    Ok(Update::Connected) => {
+                        publisher.published.clear();
+                        publisher.publish(topics.topic("status"), "online".to_string());
+                    }
+                    Ok(Update::Refresh) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                publish_state(&app_handle, &topics, &mut publisher);
+            }
+        });
+    }
+
+    Running {
+        config: config.clone(),
+        client,
+        topics,
+        stop,
+        subscriptions,
+    }
+}
+
+fn run_command(app_handle: &tauri::AppHandle, topics: &Topics, topic: &str, payload: &str) {
+    debug!(target: API, "MQTT command {}: {}", topic, payload);
+    let result = if topic == topics.topic("device/set") {
+        let cause = SwitchCause::Remote {
+            source: "mqtt".to_string(),
+        };
+        switch_device(app_handle, payload, cause).map(|_| ())
+    } else if topic == topics.topic("show") {
+        show_switcher(app_handle, "mqtt")
+    } else {
+        return;
+    };
+    if let Err(e) = result {
+        warn!(target: API, "MQTT command {} failed: {}", topic, e);
+    }
+}
+
+struct Publisher {
+    client: Client,
+    published: HashMap<String, String>, // 主题 -> 上次发布的内容
+}
+
+impl Publisher {
+    fn publish(&mut self, topic: String, payload: String) {
+        if self.published.get(&topic) == Some(&payload) {
+            return;
+        }
+        match self
+            .client
+            .try_publish(&topic, QoS::AtLeastOnce, true, payload.as_bytes())
+        {
+            Ok(()) => {
+                self.published.insert(topic, payload);
+            }
+            Err(e) => debug!(target: API, "Failed to publish {}: {}", topic, e),
+        }
+    }
+}
+
+// 后端不可用的部分（如没有音频后端的平台上的音量）跳过，不影响其他主题
+fn publish_state(app_handle: &tauri::AppHandle, topics: &Topics, publisher: &mut Publisher) {
+    let Ok(audio) = audio(app_handle) else {
+        return;
+    };
+    let devices = audio.call_blocking(AudioBackend::devices);
+    if let Ok(devices) = &devices {
+        if let Some(current) = devices.iter().find(|device| device.is_default) {
+            publisher.publish(topics.topic("device"), current.name.clone());
+        }
+        if let Ok(payload) = serde_json::to_string(devices) {
+            publisher.publish(topics.topic("devices"), payload);
+        }
+    }
+    if let Ok(volume) = audio.call_blocking(AudioBackend::volume) {
+        let percent = (volume * 100.0).round() as u8;
+        publisher.publish(topics.topic("volume"), percent.to_string());
+    }
+    if let (Some(discovery_prefix), Ok(devices)) = (&topics.discovery_prefix, &devices) {
+        for (topic, payload) in discovery(topics, discovery_prefix, devices) {
+            publisher.publish(topic, payload.to_string());
+        }
+    }
+}
+
+// Home Assistant 自动发现：设备选择、音量传感器和显示切换器按钮
+fn discovery(
+    topics: &Topics,
+    discovery_prefix: &str,
+    devices: &[AudioDevice],
+) -> Vec<(String, serde_json::Value)> {
+    let node_id = &topics.node_id;
+    let device = json!({
+        "identifiers": [format!("audiocat_{}", node_id)],
+        "name": format!("AudioCat {}", node_id),
+        "manufacturer": "AudioCat",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let entity = |component: &str, object_id: &str, name: &str| {
+        let topic = format!(
+            "{}/{}/{}/{}/config",
+            discovery_prefix, component, node_id, object_id
+        );
+        let config = json!({
+            "name": name,
+            "unique_id": format!("audiocat_{}_{}", node_id, object_id),
+            "availability_topic": topics.topic("status"),
+            "device": device,
+        });
+        (topic, config)
+    };
+
+    let (select_topic, mut select) = entity("select", "output_device", "Output device");
+    select["state_topic"] = json!(topics.topic("device"));
+    select["command_topic"] = json!(topics.topic("device/set"));
+    select["options"] = json!(
+        devices
+            .iter()
+            .map(|device| &device.name)
+            .collect::<Vec<_>>()
+    );
+    select["icon"] = json!("mdi:speaker");
+
+    let (volume_topic, mut volume) = entity("sensor", "volume", "Volume");
+    volume["state_topic"] = json!(topics.topic("volume"));
+    volume["unit_of_measurement"] = json!("%");
+    volume["icon"] = json!("mdi:volume-high");
+
+    let (show_topic, mut show) = entity("button", "show_switcher", "Show switcher");
+    show["command_topic"] = json!(topics.topic("show"));
+    show["payload_press"] = json!("show");
+
+    vec![
+        (select_topic, select),
+        (volume_topic, volume),
+        (show_topic, show),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rumqttc::Request;
+
+    fn config() -> MqttConfig {
+        MqttConfig {
+            discovery: true,
+            discovery_prefix: "homeassistant".to_string(),
+            ..MqttConfig::default()
+        }
+    }
+
+    fn device(id: &str, name: &str, is_default: bool) -> AudioDevice {
+        AudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            is_default,
+        }
+    }
+
+    // 不连接 broker，直接从请求通道读出发布的消息
+    fn publisher() -> (Publisher, flume::Receiver<Request>) {
+        let (sender, receiver) = flume::bounded(16);
+        let publisher = Publisher {
+            client: Client::from_sender(sender),
+            published: HashMap::new(),
+        };
+        (publisher, receiver)
+    }
+
+    fn sent(receiver: &flume::Receiver<Request>) -> Vec<(String, String, bool)> {
+        receiver
+            .try_iter()
+            .filter_map(|request| match request {
+                Request::Publish(publish) => Some((
+                    publish.topic,
+                    String::from_utf8_lossy(&publish.payload).to_string(),
+                    publish.retain,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_prefix_uses_sanitized_hostname() {
+        let topics = Topics::new(&config(), "studio pc.local");
+        assert_eq!(topics.node_id, "studio_pc_local");
+        assert_eq!(topics.prefix, "audiocat/studio_pc_local");
+        assert_eq!(
+            topics.topic("device/set"),
+            "audiocat/studio_pc_local/device/set"
+        );
+        assert_eq!(topics.discovery_prefix.as_deref(), Some("homeassistant"));
+    }
+
+    #[test]
+    fn custom_prefixes_are_trimmed() {
+        let config = MqttConfig {
+            topic_prefix: " home/office/ ".to_string(),
+            discovery_prefix: "ha/".to_string(),
+            ..config()
+        };
+        let topics = Topics::new(&config, "desk");
+        assert_eq!(topics.prefix, "home/office");
+        assert_eq!(topics.topic("status"), "home/office/status");
+        assert_eq!(topics.discovery_prefix.as_deref(), Some("ha"));
+
+        let config = MqttConfig {
+            discovery: false,
+            ..config
+        };
+        assert_eq!(Topics::new(&config, "desk").discovery_prefix, None);
+    }
+
+    #[test]
+    fn discovery_describes_select_sensor_and_button() {
+        let topics = Topics::new(&config(), "desk");
+        let devices = [
+            device("1", "Speakers", true),
+            device("2", "Headphones", false),
+        ];
+        let entities: HashMap<_, _> = discovery(&topics, "homeassistant", &devices)
+            .into_iter()
+            .collect();
+        assert_eq!(entities.len(), 3);
+
+        let select = &entities["homeassistant/select/desk/output_device/config"];
+        assert_eq!(select["unique_id"], "audiocat_desk_output_device");
+        assert_eq!(select["state_topic"], "audiocat/desk/device");
+        assert_eq!(select["command_topic"], "audiocat/desk/device/set");
+        assert_eq!(select["availability_topic"], "audiocat/desk/status");
+        assert_eq!(select["options"], json!(["Speakers", "Headphones"]));
+        assert_eq!(select["device"]["identifiers"], json!(["audiocat_desk"]));
+
+        let volume = &entities["homeassistant/sensor/desk/volume/config"];
+        assert_eq!(volume["state_topic"], "audiocat/desk/volume");
+        assert_eq!(volume["unit_of_measurement"], "%");
+
+        let show = &entities["homeassistant/button/desk/show_switcher/config"];
+        assert_eq!(show["command_topic"], "audiocat/desk/show");
+    }
+
+    #[test]
+    fn publisher_skips_unchanged_payloads() {
+        let (mut publisher, receiver) = publisher();
+        publisher.publish("a/device".to_string(), "Speakers".to_string());
+        publisher.publish("a/device".to_string(), "Speakers".to_string());
+        publisher.publish("a/volume".to_string(), "50".to_string());
+        publisher.publish("a/device".to_string(), "Headphones".to_string());
+        assert_eq!(
+            sent(&receiver),
+            vec![
+                ("a/device".to_string(), "Speakers".to_string(), true),
+                ("a/volume".to_string(), "50".to_string(), true),
+                ("a/device".to_string(), "Headphones".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn publisher_republishes_after_reconnect() {
+        let (mut publisher, receiver) = publisher();
+        publisher.publish("a/device".to_string(), "Speakers".to_string());
+        assert_eq!(sent(&receiver).len(), 1);
+
+        // 重连时清空记录，broker 可能已经丢失了保留消息
+        publisher.published.clear();
+        publisher.publish("a/device".to_string(), "Speakers".to_string());
+        assert_eq!(sent(&receiver).len(), 1);
+    }
+
+    #[test]
+    fn publisher_retries_after_failed_publish() {
+        let (sender, receiver) = flume::bounded(1);
+        let mut publisher = Publisher {
+            client: Client::from_sender(sender),
+            published: HashMap::new(),
+        };
+        publisher.publish("a/device".to_string(), "Speakers".to_string());
+        // 通道已满，发布失败，不记录为已发布
+        publisher.publish("a/volume".to_string(), "50".to_string());
+        assert!(!publisher.published.contains_key("a/volume"));
+        assert_eq!(sent(&receiver).len(), 1);
+
+        publisher.publish("a/volume".to_string(), "50".to_string());
+        assert_eq!(
+            sent(&receiver),
+            vec![("a/volume".to_string(), "50".to_string(), true)]
+        );
+    }
+}
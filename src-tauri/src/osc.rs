@@ -0,0 +1,487 @@
+// OSC 服务器（默认关闭）：在 UDP 端口上接收 TouchOSC、QLab 等调音台控制器的消息，切换设备时和
+// set_audio_device 一样设置默认设备并记录切换。支持的地址：
+//   /audiocat/device/set <设备 id 或名称>   /audiocat/device/index <序号，从 1 开始>
+//   /audiocat/show                          显示切换器
+//   /audiocat/register [回复端口]           注册接收反馈，省略端口时回复到发送端口
+//   /audiocat/unregister [回复端口]
+// 默认只监听本机；监听其他网卡时只接受本机和 allowed_clients 中的地址发来的消息。
+// 注册的客户端在默认设备或设备列表变化时收到 /audiocat/device/current <名称> <序号> 和
+// /audiocat/devices <名称...>
+use crate::audio::{AudioBackend, AudioDevice};
+use crate::config::OscConfig;
+use crate::error::{AppError, AppResult};
+use crate::logging::API;
+use crate::monitor::switch_device;
+use crate::state::{audio, with_state};
+use crate::switch_history::SwitchCause;
+use crate::tray::show_switcher;
+use log::{debug, info, warn};
+use rosc::{OscMessage, OscPacket, OscType};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{EventId, Listener, Manager};
+
+// 接收线程定期醒来检查是否需要停止
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+// 最多记住的客户端数，超出时丢弃最早注册的
+const MAX_CLIENTS: usize = 16;
+// 需要发送反馈的应用事件
+const EVENTS: [&str; 2] = ["device-switched", "devices-changed"];
+
+// 正在运行的服务器
+#[derive(Default)]
+pub struct OscServer {
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    config: OscConfig,
+    stop: Arc<AtomicBool>,
+    receiver: JoinHandle<()>,
+    subscriptions: Vec<EventId>,
+}
+
+// 发给反馈线程的请求；反馈需要读取设备列表，不能在事件回调所在的线程上调用音频后端
+enum Feedback {
+    All,                // 设备变化，发送给所有客户端
+    Client(SocketAddr), // 刚注册的客户端
+}
+
+// 按新配置启动、停止或换端口重启；配置没有变化时不做任何事
+pub fn apply(app_handle: &tauri::AppHandle, config: &OscConfig) {
+    let Some(server) = app_handle.try_state::<OscServer>() else {
+        return;
+    };
+    let Ok(mut running) = server.running.lock() else {
+        return;
+    };
+
+    if let Some(current) = running.as_ref() {
+        if config.enabled && current.config == *config {
+            return;
+        }
+    }
+    if let Some(current) = running.take() {
+        current.stop.store(true, Ordering::SeqCst);
+        for id in current.subscriptions {
+            app_handle.unlisten(id);
+        }
+        // 等接收线程释放端口，换端口或重新开启时才能立即绑定
+        let _ = current.receiver.join();
+        let _ = with_state(app_handle, |app_state| {
+            app_state.device_subscribers = app_state.device_subscribers.saturating_sub(1)
+        });
+        info!(target: API, "OSC server stopped");
+    }
+    if !config.enabled {
+        return;
+    }
+
+    match start(app_handle, config) {
+        Ok(started) => *running = Some(started),
+        Err(e) => warn!(target: API, "{}", e),
+    }
+}
+
+fn start(app_handle: &tauri::AppHandle, config: &OscConfig) -> AppResult<Running> {
+    let socket = UdpSocket::bind((config.address.as_str(), config.port))
+        .and_then(|socket| {
+            socket.set_read_timeout(Some(READ_TIMEOUT))?;
+            Ok(socket)
+        })
+        .map_err(|e| {
+            AppError::backend(format!(
+                "Failed to start OSC server on {}:{}",
+                config.address, config.port
+            ))
+            .with_detail(e)
+        })?;
+    let sender = socket
+        .try_clone()
+        .map_err(|e| AppError::internal("Failed to clone OSC socket").with_detail(e))?;
+    info!(target: API, "OSC server listening on {}:{}", config.address, config.port);
+
+    // 配置保存前已经校验过
+    let allowed: Vec<IpAddr> = config
+        .allowed_clients
+        .iter()
+        .filter_map(|client| client.parse().ok())
+        .collect();
+    // 旧配置监听所有网卡但没有允许的客户端时，其他设备上的控制器会失效，提示用户
+    let local_only = config
+        .address
+        .parse::<IpAddr>()
+        .is_ok_and(|address| address.is_loopback());
+    if !local_only && allowed.is_empty() {
+        warn!(target: API, "OSC server only accepts local clients until allowed_clients is set");
+    }
+    let clients: Arc<Mutex<Vec<SocketAddr>>> = Arc::default();
+    let stop = Arc::new(AtomicBool::new(false));
+    let (feedback, requests) = channel::<Feedback>();
+
+    let mut subscriptions = Vec::new();
+    for event in EVENTS {
+        let feedback = feedback.clone();
+        subscriptions.push(app_handle.listen_any(event, move |_| {
+            let _ = feedback.send(Feedback::All);
+        }));
+    }
+    // 有订阅者时监听线程即使没有设备规则也检查设备变化，外部切换设备时也能发送反馈
+    let _ = with_state(app_handle, |app_state| app_state.device_subscribers += 1);
+
+    // 反馈线程：在所有发送端（事件回调和接收线程）丢弃后退出
+    {
+        let app_handle = app_handle.clone();
+        let clients = clients.clone();
+        std::thread::spawn(move || {
+            for request in requests {
+                let targets = match request {
+                    Feedback::All => clients.lock().map(|c| c.clone()).unwrap_or_default(),
+                    Feedback::Client(addr) => vec![addr],
+                };
+                if targets.is_empty() {
+                    continue;
+                }
+                match feedback_messages(&app_handle) {
+                    Ok(messages) => {
+                        for message in messages {
+                            send(&sender, &targets, message);
+                        }
+                    }
+                    Err(e) => debug!(target: API, "Failed to build OSC feedback: {}", e),
+                }
+            }
+        });
+    }
+
+    // 接收线程
+    let receiver = {
+        let app_handle = app_handle.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; rosc::decoder::MTU];
+            let mut handler = Handler {
+                app_handle,
+                clients,
+                feedback,
+            };
+            while !stop.load(Ordering::SeqCst) {
+                let (size, from) = match socket.recv_from(&mut buffer) {
+                    Ok(received) => received,
+                    // 超时或 Windows 上对方端口不可达时返回的错误，继续等待
+                    Err(_) => continue,
+                };
+                // 解码前丢弃不允许的来源，伪造来源地址的注册也不会让我们向第三方发送反馈
+                if !is_allowed(&allowed, from.ip()) {
+                    debug!(target: API, "Ignoring OSC packet from {}", from);
+                    continue;
+                }
+                match rosc::decoder::decode_udp(&buffer[..size]) {
+                    Ok((_, packet)) => handler.handle_packet(packet, from),
+                    Err(e) => debug!(target: API, "Invalid OSC packet from {}: {:?}", from, e),
+                }
+            }
+        })
+    };
+
+    Ok(Running {
+        config: config.clone(),
+        stop,
+        receiver,
+        subscriptions,
+    })
+}
+
+struct Handler {
+    app_handle: tauri::AppHandle,
+    clients: Arc<Mutex<Vec<SocketAddr>>>,
+    feedback: Sender<Feedback>,
+}
+
+impl Handler {
+    fn handle_packet(&mut self, packet: OscPacket, from: SocketAddr) {
+        match packet {
+            OscPacket::Message(message) => {
+                debug!(target: API, "OSC {} {:?} from {}", message.addr, message.args, from);
+                if let Err(e) = self.handle_message(&message, from) {
+                    warn!(target: API, "OSC {} failed: {}", message.addr, e);
+                }
+            }
+            OscPacket::Bundle(bundle) => {
+                for packet in bundle.content {
+                    self.handle_packet(packet, from);
+                }
+            }
+        }
+    }
+
+    fn handle_message(&mut self, message: &OscMessage, from: SocketAddr) -> AppResult<()> {
+        let cause = || SwitchCause::Remote {
+            source: "osc".to_string(),
+        };
+        match parse_command(message, from)? {
+            Some(Command::SetDevice(target)) => {
+                switch_device(&self.app_handle, &target, cause())?;
+            }
+            Some(Command::DeviceIndex(index)) => {
+                let devices = audio(&self.app_handle)?.call_blocking(AudioBackend::devices)?;
+                let device = devices
+                    .get(index - 1)
+                    .ok_or_else(|| AppError::device_not_found(&format!("index {}", index)))?;
+                switch_device(&self.app_handle, &device.id, cause())?;
+            }
+            Some(Command::Show) => show_switcher(&self.app_handle, "osc")?,
+            Some(Command::Register(addr)) => {
+                if let Ok(mut clients) = self.clients.lock() {
+                    clients.retain(|client| *client != addr);
+                    if clients.len() >= MAX_CLIENTS {
+                        clients.remove(0);
+                    }
+                    clients.push(addr);
+                }
+                info!(target: API, "OSC client {} registered", addr);
+                let _ = self.feedback.send(Feedback::Client(addr));
+            }
+            Some(Command::Unregister(addr)) => {
+                if let Ok(mut clients) = self.clients.lock() {
+                    clients.retain(|client| *client != addr);
+                }
+                info!(target: API, "OSC client {} unregistered", addr);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+// 收到的消息对应的操作
+#[derive(Debug, PartialEq)]
+enum Command {
+    SetDevice(String),
+    DeviceIndex(usize), // 从 1 开始
+    Show,
+    Register(SocketAddr),
+    Unregister(SocketAddr),
+}
+
+// 解析地址和参数；不支持的地址和按钮松开返回 None
+fn parse_command(message: &OscMessage, from: SocketAddr) -> AppResult<Option<Command>> {
+    let first = message.args.first();
+    let command = match message.addr.as_str() {
+        "/audiocat/device/set" => match first {
+            Some(OscType::String(target)) => Command::SetDevice(target.clone()),
+            _ => return Err(AppError::invalid_input("Expected a device id or name")),
+        },
+        "/audiocat/device/index" => {
+            let index = first
+                .and_then(number)
+                .filter(|index| *index >= 1.0)
+                .ok_or_else(|| AppError::invalid_input("Expected a device index from 1"))?;
+            Command::DeviceIndex(index as usize)
+        }
+        // 控制器的按钮松开时发送 0，只在按下时显示
+        "/audiocat/show" if first.and_then(number) == Some(0.0) => return Ok(None),
+        "/audiocat/show" => Command::Show,
+        "/audiocat/register" => Command::Register(reply_address(from, first)?),
+        "/audiocat/unregister" => Command::Unregister(reply_address(from, first)?),
+        other => {
+            debug!(target: API, "Ignoring OSC address {}", other);
+            return Ok(None);
+        }
+    };
+    Ok(Some(command))
+}
+
+// 本机回环地址总是允许，其他地址需要在 allowed_clients 中；双栈套接字收到的 IPv4 地址是映射形式
+fn is_allowed(allowed: &[IpAddr], ip: IpAddr) -> bool {
+    let ip = ip.to_canonical();
+    ip.is_loopback() || allowed.iter().any(|client| client.to_canonical() == ip)
+}
+
+// 控制器的推子和按钮通常发送浮点数，也接受整数和数字字符串
+fn number(arg: &OscType) -> Option<f64> {
+    match arg {
+        OscType::Int(value) => Some(f64::from(*value)),
+        OscType::Long(value) => Some(*value as f64),
+        OscType::Float(value) => Some(f64::from(*value)),
+        OscType::Double(value) => Some(*value),
+        OscType::String(value) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// 客户端可以指定接收反馈的端口，发送端口和接收端口不同时需要
+fn reply_address(from: SocketAddr, port: Option<&OscType>) -> AppResult<SocketAddr> {
+    match port.and_then(number) {
+        None => Ok(from),
+        Some(port) if (1.0..=65535.0).contains(&port) => {
+            Ok(SocketAddr::new(from.ip(), port as u16))
+        }
+        Some(port) => Err(AppError::invalid_input(format!(
+            "Invalid reply port: {}",
+            port
+        ))),
+    }
+}
+
+// 当前设备（名称和从 1 开始的序号，没有默认设备时序号为 0）和设备名称列表
+fn feedback_messages(app_handle: &tauri::AppHandle) -> AppResult<Vec<OscMessage>> {
+    let devices: Vec<AudioDevice> = audio(app_handle)?.call_blocking(AudioBackend::devices)?;
+    let current = devices.iter().position(|device| device.is_default);
+    let name = current
+        .map(|index| devices[index].name.clone())
+        .unwrap_or_default();
+    let index = current.map_or(0, |index| index as i32 + 1);
+    Ok(vec![
+        OscMessage {
+            addr: "/audiocat/device/current".to_string(),
+            args: vec![OscType::String(name), OscType::Int(index)],
+        },
+        OscMessage {
+            addr: "/audiocat/devices".to_string(),
+            args: devices
+                .into_iter()
+                .map(|device| OscType::String(device.name))
+                .collect(),
+        },
+    ])
+}
+
+fn send(socket: &UdpSocket, targets: &[SocketAddr], message: OscMessage) {
+    let packet = match rosc::encoder::encode(&OscPacket::Message(message)) {
+        Ok(packet) => packet,
+        Err(e) => {
+            warn!(target: API, "Failed to encode OSC message: {:?}", e);
+            return;
+        }
+    };
+    for target in targets {
+        if let Err(e) = socket.send_to(&packet, target) {
+            debug!(target: API, "Failed to send OSC feedback to {}: {}", target, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn from() -> SocketAddr {
+        "192.168.1.20:53000".parse().unwrap()
+    }
+
+    fn message(addr: &str, args: Vec<OscType>) -> OscMessage {
+        OscMessage {
+            addr: addr.to_string(),
+            args,
+        }
+    }
+
+    fn parse(addr: &str, args: Vec<OscType>) -> AppResult<Option<Command>> {
+        parse_command(&message(addr, args), from())
+    }
+
+    #[test]
+    fn number_accepts_numeric_types_and_strings() {
+        assert_eq!(number(&OscType::Int(3)), Some(3.0));
+        assert_eq!(number(&OscType::Long(4)), Some(4.0));
+        assert_eq!(number(&OscType::Float(0.5)), Some(0.5));
+        assert_eq!(number(&OscType::Double(2.5)), Some(2.5));
+        assert_eq!(number(&OscType::String(" 7 ".to_string())), Some(7.0));
+        assert_eq!(number(&OscType::String("abc".to_string())), None);
+        assert_eq!(number(&OscType::Bool(true)), None);
+    }
+
+    #[test]
+    fn reply_address_defaults_to_sender() {
+        assert_eq!(reply_address(from(), None).unwrap(), from());
+        assert_eq!(
+            reply_address(from(), Some(&OscType::Int(9001))).unwrap(),
+            "192.168.1.20:9001".parse().unwrap()
+        );
+        assert_eq!(
+            reply_address(from(), Some(&OscType::Float(9002.0))).unwrap(),
+            "192.168.1.20:9002".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn reply_address_rejects_invalid_ports() {
+        for port in [0, 65536, -1] {
+            let error = reply_address(from(), Some(&OscType::Int(port))).unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn parses_device_commands() {
+        assert_eq!(
+            parse(
+                "/audiocat/device/set",
+                vec![OscType::String("Headphones".to_string())]
+            )
+            .unwrap(),
+            Some(Command::SetDevice("Headphones".to_string()))
+        );
+        assert!(parse("/audiocat/device/set", vec![OscType::Int(1)]).is_err());
+        assert!(parse("/audiocat/device/set", vec![]).is_err());
+
+        assert_eq!(
+            parse("/audiocat/device/index", vec![OscType::Float(2.0)]).unwrap(),
+            Some(Command::DeviceIndex(2))
+        );
+        assert!(parse("/audiocat/device/index", vec![OscType::Int(0)]).is_err());
+        assert!(parse("/audiocat/device/index", vec![]).is_err());
+    }
+
+    #[test]
+    fn show_ignores_button_release() {
+        assert_eq!(
+            parse("/audiocat/show", vec![]).unwrap(),
+            Some(Command::Show)
+        );
+        assert_eq!(
+            parse("/audiocat/show", vec![OscType::Float(1.0)]).unwrap(),
+            Some(Command::Show)
+        );
+        assert_eq!(
+            parse("/audiocat/show", vec![OscType::Float(0.0)]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_registration() {
+        assert_eq!(
+            parse("/audiocat/register", vec![]).unwrap(),
+            Some(Command::Register(from()))
+        );
+        assert_eq!(
+            parse("/audiocat/unregister", vec![OscType::Int(9001)]).unwrap(),
+            Some(Command::Unregister("192.168.1.20:9001".parse().unwrap()))
+        );
+        assert!(parse("/audiocat/register", vec![OscType::Int(0)]).is_err());
+    }
+
+    #[test]
+    fn ignores_unknown_addresses() {
+        assert_eq!(parse("/audiocat/unknown", vec![]).unwrap(), None);
+        assert_eq!(parse("/other/device/set", vec![]).unwrap(), None);
+    }
+
+    #[test]
+    fn allows_loopback_and_listed_clients() {
+        let allowed: Vec<IpAddr> = vec!["192.168.1.20".parse().unwrap()];
+        assert!(is_allowed(&[], "127.0.0.1".parse().unwrap()));
+        assert!(is_allowed(&[], "::1".parse().unwrap()));
+        assert!(!is_allowed(&[], "192.168.1.20".parse().unwrap()));
+        assert!(is_allowed(&allowed, "192.168.1.20".parse().unwrap()));
+        assert!(is_allowed(&allowed, "::ffff:192.168.1.20".parse().unwrap()));
+        assert!(!is_allowed(&allowed, "192.168.1.21".parse().unwrap()));
+    }
+}
@@ -0,0 +1,733 @@
+// 本地控制接口：在每个用户自己的本地套接字上提供 JSON-RPC 2.0，每行一个消息，
+// 供 Stream Deck、脚本和其他工具控制正在运行的实例，不需要启动新进程。方法和 Tauri 命令对应：
+//   devices.list、devices.current、devices.set { device }（设备 id 或名称）
+//   monitored_windows.get、monitored_windows.set { windows }
+//   auto_hide_delay.get、auto_hide_delay.set { delay }
+//   config.set { key, value }：修改一个配置项，和配置文件中的格式相同，返回保存后的值
+//   subscribe：之后用 event 通知推送设备变化（devices-changed）和设备切换（device-switched）
+use crate::audio::{AudioBackend, AudioDevice};
+use crate::commands;
+use crate::config::{HttpApiConfig, MqttConfig, OscConfig, WindowConfig, read_window_config};
+use crate::error::{AppError, AppResult};
+use crate::focus_switch::FocusSwitchConfig;
+use crate::local_socket;
+use crate::logging::API;
+use crate::monitor::{MonitorRule, switch_device};
+use crate::process_monitor::ProcessRule;
+use crate::rules::Rule;
+use crate::schedule::ScheduleRule;
+use crate::state::{audio, with_state};
+use crate::switch_history::SwitchCause;
+use interprocess::local_socket::{Stream, prelude::*};
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::mpsc::{Sender, channel};
+use tauri::{EventId, Listener};
+
+const SOCKET_KIND: &str = "rpc";
+
+// subscribe 之后推送的事件，和发给前端的事件同名
+const EVENTS: [&str; 2] = ["devices-changed", "device-switched"];
+
+// JSON-RPC 2.0 的标准错误码；应用自身的错误用 APP_ERROR，data 中是 AppError
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const APP_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(error: AppError) -> Self {
+        Self {
+            code: APP_ERROR,
+            message: error.to_string(),
+            data: serde_json::to_value(&error).ok(),
+        }
+    }
+}
+
+// 一个连接：回复和事件通知都交给写线程发送，事件推送不会被读得慢的客户端阻塞
+struct Connection {
+    outgoing: Sender<Value>,
+    subscriptions: Vec<EventId>,
+}
+
+// config.set 修改的配置项，值已经按配置文件中的格式解析
+enum ConfigUpdate {
+    MonitoredWindows(Vec<MonitorRule>),
+    AutoHideDelay(u64),
+    DarkMode(bool),
+    FocusSwitch(FocusSwitchConfig),
+    ProcessRules(Vec<ProcessRule>),
+    ScheduleRules(Vec<ScheduleRule>),
+    Rules(Vec<Rule>),
+    LogLevel(String),
+    HttpApi(HttpApiConfig),
+    Mqtt(MqttConfig),
+    Osc(OscConfig),
+}
+
+// 接口背后的应用操作；测试时换成不依赖 Tauri 的实现
+trait Host {
+    fn devices(&self) -> AppResult<Vec<AudioDevice>>;
+    fn current_device(&self) -> AppResult<AudioDevice>;
+    fn set_default(&self, device: &str) -> AppResult<AudioDevice>;
+    fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>>;
+    fn set_monitored_windows(&self, windows: Vec<MonitorRule>) -> AppResult<String>;
+    fn auto_hide_delay(&self) -> AppResult<u64>;
+    fn set_auto_hide_delay(&self, delay: u64) -> AppResult<String>;
+    fn set_config(&self, update: ConfigUpdate) -> AppResult<()>;
+    fn config(&self) -> AppResult<WindowConfig>;
+    // 收到事件时用事件的 payload 调用 handler
+    fn listen(&self, event: &'static str, handler: Box<dyn Fn(Value) + Send>) -> EventId;
+    fn unlisten(&self, id: EventId);
+    // 有订阅者时监听线程即使没有设备规则也检查设备变化
+    fn set_device_subscriber(&self, subscribed: bool) -> AppResult<()>;
+}
+
+impl Host for tauri::AppHandle {
+    fn devices(&self) -> AppResult<Vec<AudioDevice>> {
+        audio(self)?.call_blocking(AudioBackend::devices)
+    }
+
+    fn current_device(&self) -> AppResult<AudioDevice> {
+        audio(self)?.call_blocking(AudioBackend::current_device)
+    }
+
+    fn set_default(&self, device: &str) -> AppResult<AudioDevice> {
+        let cause = SwitchCause::Remote {
+            source: "rpc".to_string(),
+        };
+        switch_device(self, device, cause)
+    }
+
+    fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>> {
+        commands::get_monitored_windows(self.clone())
+    }
+
+    fn set_monitored_windows(&self, windows: Vec<MonitorRule>) -> AppResult<String> {
+        commands::set_monitored_windows(self.clone(), windows)
+    }
+
+    fn auto_hide_delay(&self) -> AppResult<u64> {
+        commands::get_auto_hide_delay(self.clone())
+    }
+
+    fn set_auto_hide_delay(&self, delay: u64) -> AppResult<String> {
+        commands::set_auto_hide_delay(self.clone(), delay)
+    }
+
+    // 交给偏好设置使用的命令，内存中的状态和运行中的服务随之更新
+    fn set_config(&self, update: ConfigUpdate) -> AppResult<()> {
+        let app = self.clone();
+        match update {
+            ConfigUpdate::MonitoredWindows(windows) => {
+                commands::set_monitored_windows(app, windows).map(|_| ())
+            }
+            ConfigUpdate::AutoHideDelay(delay) => {
+                commands::set_auto_hide_delay(app, delay).map(|_| ())
+            }
+            ConfigUpdate::DarkMode(dark_mode) => {
+                commands::set_dark_mode(app, dark_mode).map(|_| ())
+            }
+            ConfigUpdate::FocusSwitch(config) => {
+                commands::set_focus_switch_config(app, config).map(|_| ())
+            }
+            ConfigUpdate::ProcessRules(rules) => {
+                commands::set_process_rules(app, rules).map(|_| ())
+            }
+            ConfigUpdate::ScheduleRules(rules) => {
+                commands::set_schedule_rules(app, rules).map(|_| ())
+            }
+            ConfigUpdate::Rules(rules) => commands::set_rules(app, rules).map(|_| ()),
+            ConfigUpdate::LogLevel(level) => commands::set_log_level(app, level).map(|_| ()),
+            ConfigUpdate::HttpApi(config) => commands::set_http_api_config(app, config).map(|_| ()),
+            ConfigUpdate::Mqtt(config) => commands::set_mqtt_config(app, config).map(|_| ()),
+            ConfigUpdate::Osc(config) => commands::set_osc_config(app, config).map(|_| ()),
+        }
+    }
+
+    fn config(&self) -> AppResult<WindowConfig> {
+        read_window_config(self)
+    }
+
+    fn listen(&self, event: &'static str, handler: Box<dyn Fn(Value) + Send>) -> EventId {
+        self.listen_any(event, move |message| {
+            handler(serde_json::from_str(message.payload()).unwrap_or(Value::Null))
+        })
+    }
+
+    fn unlisten(&self, id: EventId) {
+        Listener::unlisten(self, id);
+    }
+
+    fn set_device_subscriber(&self, subscribed: bool) -> AppResult<()> {
+        with_state(self, |app_state| {
+            app_state.device_subscribers = if subscribed {
+                app_state.device_subscribers + 1
+            } else {
+                app_state.device_subscribers.saturating_sub(1)
+            }
+        })
+    }
+}
+
+pub fn start_server(app_handle: tauri::AppHandle) {
+    let listener = match local_socket::listen(SOCKET_KIND) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!(target: API, "Failed to start local control API: {}", e);
+            return;
+        }
+    };
+    info!(target: API, "Local control API listening");
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    // 订阅会一直占用连接，每个连接一个线程
+                    let app_handle = app_handle.clone();
+                    std::thread::spawn(move || serve(&app_handle, stream));
+                }
+                Err(e) => warn!(target: API, "Failed to accept control connection: {}", e),
+            }
+        }
+    });
+}
+
+fn serve(app_handle: &tauri::AppHandle, stream: Stream) {
+    let (receiver, mut sender) = stream.split();
+    let (outgoing, messages) = channel::<Value>();
+    std::thread::spawn(move || {
+        for message in messages {
+            if writeln!(sender, "{}", message).is_err() {
+                break;
+            }
+        }
+    });
+    read_requests(app_handle, BufReader::new(receiver), outgoing);
+}
+
+// 逐行处理请求直到连接断开
+fn read_requests(host: &dyn Host, receiver: impl BufRead, outgoing: Sender<Value>) {
+    let mut connection = Connection {
+        outgoing,
+        subscriptions: Vec::new(),
+    };
+    for line in receiver.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(reply) = handle_message(host, &mut connection, &line) {
+            let _ = connection.outgoing.send(reply);
+        }
+    }
+
+    // 连接断开后取消订阅，写线程在所有发送端丢弃后退出
+    if !connection.subscriptions.is_empty() {
+        for id in connection.subscriptions {
+            host.unlisten(id);
+        }
+        let _ = host.set_device_subscriber(false);
+    }
+}
+
+// 返回 None 表示这是一条通知（没有 id），不需要回复
+fn handle_message(host: &dyn Host, connection: &mut Connection, line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("Parse error: {}", e));
+            return Some(error_reply(Value::Null, error));
+        }
+    };
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        let error = RpcError::new(INVALID_REQUEST, "Invalid request");
+        return Some(error_reply(id.unwrap_or(Value::Null), error));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    debug!(target: API, "Control API request: {}", method);
+    let result = call(host, connection, method, &params);
+    if let Err(error) = &result {
+        warn!(target: API, "Control API {} failed: {}", method, error.message);
+    }
+
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_reply(id, error),
+    })
+}
+
+fn error_reply(id: Value, error: RpcError) -> Value {
+    let mut body = json!({ "code": error.code, "message": error.message });
+    if let Some(data) = error.data {
+        body["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": body })
+}
+
+fn call(
+    host: &dyn Host,
+    connection: &mut Connection,
+    method: &str,
+    params: &Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "devices.list" => to_result(host.devices()?),
+        "devices.current" => to_result(host.current_device()?),
+        "devices.set" => {
+            let target: String = param(params, "device")?;
+            to_result(host.set_default(&target)?)
+        }
+        "monitored_windows.get" => to_result(host.monitored_windows()?),
+        "monitored_windows.set" => {
+            to_result(host.set_monitored_windows(param(params, "windows")?)?)
+        }
+        "auto_hide_delay.get" => to_result(host.auto_hide_delay()?),
+        "auto_hide_delay.set" => to_result(host.set_auto_hide_delay(param(params, "delay")?)?),
+        "config.set" => {
+            let key: String = param(params, "key")?;
+            to_result(set_config(host, &key, param(params, "value")?)?)
+        }
+        "subscribe" => {
+            subscribe(host, connection)?;
+            to_result(EVENTS)
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+// 修改一个配置项，返回保存后的值；窗口位置由窗口自己保存
+fn set_config(host: &dyn Host, key: &str, value: Value) -> AppResult<Value> {
+    host.set_config(config_update(key, value)?)?;
+    let mut config = serde_json::to_value(host.config()?)
+        .map_err(|e| AppError::internal("Failed to serialize config").with_detail(e))?;
+    Ok(config[key].take())
+}
+
+fn config_update(key: &str, value: Value) -> AppResult<ConfigUpdate> {
+    Ok(match key {
+        "monitored_windows" => ConfigUpdate::MonitoredWindows(config_value(key, value)?),
+        "auto_hide_delay" => ConfigUpdate::AutoHideDelay(config_value(key, value)?),
+        "dark_mode" => ConfigUpdate::DarkMode(config_value(key, value)?),
+        "focus_switch" => ConfigUpdate::FocusSwitch(config_value(key, value)?),
+        "process_rules" => ConfigUpdate::ProcessRules(config_value(key, value)?),
+        "schedule_rules" => ConfigUpdate::ScheduleRules(config_value(key, value)?),
+        "rules" => ConfigUpdate::Rules(config_value(key, value)?),
+        "log_level" => ConfigUpdate::LogLevel(config_value(key, value)?),
+        "http_api" => ConfigUpdate::HttpApi(config_value(key, value)?),
+        "mqtt" => ConfigUpdate::Mqtt(config_value(key, value)?),
+        "osc" => ConfigUpdate::Osc(config_value(key, value)?),
+        "main_window_position" | "preference_window_position" => {
+            return Err(AppError::invalid_input(format!(
+                "{} can't be changed while AudioCat is running",
+                key
+            )));
+        }
+        _ => {
+            return Err(AppError::invalid_input(format!(
+                "Unknown config key: {}",
+                key
+            )));
+        }
+    })
+}
+
+fn config_value<T: DeserializeOwned>(key: &str, value: Value) -> AppResult<T> {
+    serde_json::from_value(value)
+        .map_err(|e| AppError::invalid_input(format!("Invalid value for {}", key)).with_detail(e))
+}
+
+// 重复订阅时不做任何事
+fn subscribe(host: &dyn Host, connection: &mut Connection) -> Result<(), RpcError> {
+    if !connection.subscriptions.is_empty() {
+        return Ok(());
+    }
+
+    for event in EVENTS {
+        let outgoing = connection.outgoing.clone();
+        let id = host.listen(
+            event,
+            Box::new(move |payload| {
+                let _ = outgoing.send(json!({
+                    "jsonrpc": "2.0",
+                    "method": "event",
+                    "params": { "event": event, "payload": payload },
+                }));
+            }),
+        );
+        connection.subscriptions.push(id);
+    }
+    host.set_device_subscriber(true)?;
+    Ok(())
+}
+
+fn param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, RpcError> {
+    let value = params
+        .get(name)
+        .cloned()
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Missing parameter: {}", name)))?;
+    serde_json::from_value(value)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid parameter {}: {}", name, e)))
+}
+
+fn to_result(value: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| {
+        RpcError::from(AppError::internal("Failed to serialize result").with_detail(e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::Mutex;
+    use std::sync::mpsc::Receiver;
+
+    type Handler = Box<dyn Fn(Value) + Send>;
+
+    #[derive(Default)]
+    struct FakeHost {
+        devices: Vec<AudioDevice>,
+        switched: Mutex<Vec<String>>,
+        config: Mutex<WindowConfig>,
+        handlers: Mutex<Vec<(EventId, &'static str, Handler)>>,
+        unlistened: Mutex<Vec<EventId>>,
+        subscribers: Mutex<usize>,
+    }
+
+    impl FakeHost {
+        fn new() -> Self {
+            Self {
+                devices: vec![
+                    device("1", "Speakers", true),
+                    device("2", "Headphones", false),
+                ],
+                ..Self::default()
+            }
+        }
+
+        fn emit(&self, event: &str, payload: Value) {
+            for (_, name, handler) in self.handlers.lock().unwrap().iter() {
+                if *name == event {
+                    handler(payload.clone());
+                }
+            }
+        }
+    }
+
+    impl Host for FakeHost {
+        fn devices(&self) -> AppResult<Vec<AudioDevice>> {
+            Ok(self.devices.clone())
+        }
+
+        fn current_device(&self) -> AppResult<AudioDevice> {
+            Err(AppError::backend("No audio backend"))
+        }
+
+        fn set_default(&self, device: &str) -> AppResult<AudioDevice> {
+            let found = self
+                .devices
+                .iter()
+                .find(|candidate| candidate.matches(device))
+                .cloned()
+                .ok_or_else(|| AppError::device_not_found(device))?;
+            self.switched.lock().unwrap().push(found.id.clone());
+            Ok(found)
+        }
+
+        fn monitored_windows(&self) -> AppResult<Vec<MonitorRule>> {
+            Ok(self.config.lock().unwrap().monitored_windows.clone())
+        }
+
+        fn set_monitored_windows(&self, windows: Vec<MonitorRule>) -> AppResult<String> {
+            self.config.lock().unwrap().monitored_windows = windows;
+            Ok("Monitored windows saved".to_string())
+        }
+
+        fn auto_hide_delay(&self) -> AppResult<u64> {
+            Ok(self.config.lock().unwrap().auto_hide_delay)
+        }
+
+        fn set_auto_hide_delay(&self, delay: u64) -> AppResult<String> {
+            self.config.lock().unwrap().auto_hide_delay = delay;
+            Ok("Auto hide delay saved".to_string())
+        }
+
+        fn set_config(&self, update: ConfigUpdate) -> AppResult<()> {
+            let mut config = self.config.lock().unwrap();
+            match update {
+                ConfigUpdate::MonitoredWindows(windows) => config.monitored_windows = windows,
+                ConfigUpdate::AutoHideDelay(delay) => config.auto_hide_delay = delay,
+                ConfigUpdate::DarkMode(dark_mode) => config.dark_mode = dark_mode,
+                ConfigUpdate::FocusSwitch(focus_switch) => config.focus_switch = focus_switch,
+                ConfigUpdate::ProcessRules(rules) => config.process_rules = rules,
+                ConfigUpdate::ScheduleRules(rules) => config.schedule_rules = rules,
+                ConfigUpdate::Rules(rules) => config.rules = rules,
+                ConfigUpdate::LogLevel(level) => config.log_level = level,
+                ConfigUpdate::HttpApi(http_api) => config.http_api = http_api,
+                ConfigUpdate::Mqtt(mqtt) => config.mqtt = mqtt,
+                ConfigUpdate::Osc(osc) => config.osc = osc,
+            }
+            Ok(())
+        }
+
+        fn config(&self) -> AppResult<WindowConfig> {
+            Ok(self.config.lock().unwrap().clone())
+        }
+
+        fn listen(&self, event: &'static str, handler: Handler) -> EventId {
+            let mut handlers = self.handlers.lock().unwrap();
+            let id = handlers.len() as EventId;
+            handlers.push((id, event, handler));
+            id
+        }
+
+        fn unlisten(&self, id: EventId) {
+            self.handlers
+                .lock()
+                .unwrap()
+                .retain(|(handler, _, _)| *handler != id);
+            self.unlistened.lock().unwrap().push(id);
+        }
+
+        fn set_device_subscriber(&self, subscribed: bool) -> AppResult<()> {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            *subscribers = if subscribed {
+                *subscribers + 1
+            } else {
+                subscribers.saturating_sub(1)
+            };
+            Ok(())
+        }
+    }
+
+    fn device(id: &str, name: &str, is_default: bool) -> AudioDevice {
+        AudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            is_default,
+        }
+    }
+
+    fn connection() -> (Connection, Receiver<Value>) {
+        let (outgoing, messages) = channel();
+        let connection = Connection {
+            outgoing,
+            subscriptions: Vec::new(),
+        };
+        (connection, messages)
+    }
+
+    // 发送一个带 id 的请求，返回回复
+    fn request(host: &FakeHost, connection: &mut Connection, method: &str, params: Value) -> Value {
+        let line = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        handle_message(host, connection, &line.to_string()).unwrap()
+    }
+
+    #[test]
+    fn malformed_requests_get_standard_errors() {
+        let host = FakeHost::new();
+        let (mut connection, _) = connection();
+
+        let reply = handle_message(&host, &mut connection, "{ not json").unwrap();
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+
+        let reply = handle_message(&host, &mut connection, r#"{"id":7}"#).unwrap();
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["error"]["code"], INVALID_REQUEST);
+
+        let reply = request(&host, &mut connection, "devices.remove", Value::Null);
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(
+            reply["error"]["message"],
+            "Method not found: devices.remove"
+        );
+    }
+
+    #[test]
+    fn notifications_are_not_answered() {
+        let host = FakeHost::new();
+        let (mut connection, _) = connection();
+
+        let line = r#"{"jsonrpc":"2.0","method":"devices.set","params":{"device":"Headphones"}}"#;
+        assert!(handle_message(&host, &mut connection, line).is_none());
+        assert_eq!(*host.switched.lock().unwrap(), ["2"]);
+    }
+
+    #[test]
+    fn params_are_checked() {
+        let host = FakeHost::new();
+        let (mut connection, _) = connection();
+
+        let reply = request(&host, &mut connection, "devices.set", json!({}));
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+        assert_eq!(reply["error"]["message"], "Missing parameter: device");
+
+        let reply = request(
+            &host,
+            &mut connection,
+            "auto_hide_delay.set",
+            json!({ "delay": "soon" }),
+        );
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+        assert!(
+            reply["error"]["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid parameter delay")
+        );
+
+        let reply = request(
+            &host,
+            &mut connection,
+            "auto_hide_delay.set",
+            json!({ "delay": 5 }),
+        );
+        assert_eq!(reply["result"], "Auto hide delay saved");
+        let reply = request(&host, &mut connection, "auto_hide_delay.get", Value::Null);
+        assert_eq!(reply["result"], 5);
+    }
+
+    #[test]
+    fn app_errors_carry_the_error_code() {
+        let host = FakeHost::new();
+        let (mut connection, _) = connection();
+
+        let reply = request(
+            &host,
+            &mut connection,
+            "devices.set",
+            json!({ "device": "Headphones" }),
+        );
+        assert_eq!(reply["result"]["id"], "2");
+
+        let reply = request(
+            &host,
+            &mut connection,
+            "devices.set",
+            json!({ "device": "Missing" }),
+        );
+        assert_eq!(reply["error"]["code"], APP_ERROR);
+        assert_eq!(reply["error"]["data"]["code"], "device_not_found");
+
+        let reply = request(&host, &mut connection, "devices.current", Value::Null);
+        assert_eq!(reply["error"]["data"]["code"], "backend_unavailable");
+    }
+
+    #[test]
+    fn config_set_returns_the_saved_value() {
+        let host = FakeHost::new();
+        let (mut connection, _) = connection();
+        let set = |connection: &mut Connection, key: &str, value: Value| {
+            request(
+                &host,
+                connection,
+                "config.set",
+                json!({ "key": key, "value": value }),
+            )
+        };
+
+        let reply = set(&mut connection, "auto_hide_delay", json!(10));
+        assert_eq!(reply["result"], 10);
+        let reply = set(
+            &mut connection,
+            "monitored_windows",
+            json!(["Zoom Meeting"]),
+        );
+        assert_eq!(reply["result"][0]["matcher"]["value"], "Zoom Meeting");
+        assert_eq!(host.config.lock().unwrap().monitored_windows.len(), 1);
+
+        let reply = set(&mut connection, "dark_mode", json!("yes"));
+        assert_eq!(reply["error"]["data"]["code"], "invalid_input");
+        assert_eq!(
+            reply["error"]["data"]["message"],
+            "Invalid value for dark_mode"
+        );
+        let reply = set(&mut connection, "main_window_position", Value::Null);
+        assert_eq!(reply["error"]["data"]["code"], "invalid_input");
+        let reply = set(&mut connection, "volume", json!(10));
+        assert_eq!(reply["error"]["message"], "Unknown config key: volume");
+        // 失败时不修改配置
+        assert!(!host.config.lock().unwrap().dark_mode);
+
+        let reply = request(
+            &host,
+            &mut connection,
+            "config.set",
+            json!({ "key": "dark_mode" }),
+        );
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn subscribe_pushes_events_once() {
+        let host = FakeHost::new();
+        let (mut connection, messages) = connection();
+
+        let reply = request(&host, &mut connection, "subscribe", Value::Null);
+        assert_eq!(reply["result"], json!(EVENTS));
+        // 重复订阅不会重复推送，也不会重复计数
+        request(&host, &mut connection, "subscribe", Value::Null);
+        assert_eq!(host.handlers.lock().unwrap().len(), EVENTS.len());
+        assert_eq!(*host.subscribers.lock().unwrap(), 1);
+
+        host.emit("device-switched", json!({ "id": "2" }));
+        let notification = messages.try_recv().unwrap();
+        assert_eq!(notification["method"], "event");
+        assert_eq!(notification["params"]["event"], "device-switched");
+        assert_eq!(notification["params"]["payload"], json!({ "id": "2" }));
+        assert!(messages.try_recv().is_err());
+    }
+
+    #[test]
+    fn disconnecting_removes_the_subscription() {
+        let host = FakeHost::new();
+        let (outgoing, messages) = channel();
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"subscribe"}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"devices.list"}"#,
+            "\n",
+        );
+
+        read_requests(&host, Cursor::new(input), outgoing);
+        let replies: Vec<Value> = messages.iter().collect();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[1]["result"][0]["name"], "Speakers");
+
+        assert!(host.handlers.lock().unwrap().is_empty());
+        assert_eq!(host.unlistened.lock().unwrap().len(), EVENTS.len());
+        assert_eq!(*host.subscribers.lock().unwrap(), 0);
+    }
+}
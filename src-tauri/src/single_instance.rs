@@ -0,0 +1,208 @@
+// 单实例：每个用户只运行一个托盘应用。第一个启动的进程在本地套接字上监听，之后启动的进程
+// 把命令行参数转发给它执行后退出，避免出现两个托盘图标、两个进程同时写 window_config.json。
+// 支持的参数：--show 显示主窗口，--switch <设备 id 或名称> 切换默认输出设备。
+use crate::error::{AppError, AppResult};
+use crate::local_socket;
+use crate::logging::TRAY;
+use crate::monitor::switch_device;
+use crate::state::{main_window, window_error};
+use crate::switch_history::SwitchCause;
+use interprocess::local_socket::{Listener, Stream, prelude::*};
+use log::{info, warn};
+use std::io::{self, BufRead, BufReader, Write};
+use std::time::Duration;
+
+const SOCKET_KIND: &str = "instance";
+// 转发的参数只有一行，连接后这么久还没发完就放弃
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+// 转发后等待正在运行的实例回复，它卡住时新启动的进程不会一直等下去；切换设备需要一点时间
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchAction {
+    Show,
+    Switch(String), // 设备 id 或设备名称
+}
+
+pub enum Instance {
+    Primary(Option<Listener>),     // 无法监听时为 None，此时不做单实例限制
+    Secondary(Result<(), String>), // 参数已转发给正在运行的实例，Err 是它返回的错误
+}
+
+// 连接已运行的实例并转发参数，没有实例在运行时开始监听
+pub fn claim(args: &[String]) -> Instance {
+    if let Ok(stream) = local_socket::connect(SOCKET_KIND) {
+        return Instance::Secondary(forward(stream, args));
+    }
+
+    match local_socket::listen(SOCKET_KIND) {
+        Ok(listener) => Instance::Primary(Some(listener)),
+        // 两个进程同时启动时，另一个进程可能刚刚开始监听
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            match local_socket::connect(SOCKET_KIND) {
+                Ok(stream) => Instance::Secondary(forward(stream, args)),
+                Err(e) => {
+                    warn!(target: TRAY, "Failed to reach running instance: {}", e);
+                    Instance::Primary(None)
+                }
+            }
+        }
+        Err(e) => {
+            warn!(target: TRAY, "Failed to listen for other instances: {}", e);
+            Instance::Primary(None)
+        }
+    }
+}
+
+// 请求和回复各占一行：请求是参数的 JSON 数组，回复是 null 或错误信息
+fn forward(stream: Stream, args: &[String]) -> Result<(), String> {
+    let request = serde_json::to_string(args).map_err(|e| e.to_string())?;
+    stream
+        .set_recv_timeout(Some(REPLY_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream);
+    writeln!(reader.get_mut(), "{}", request).map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    reader.read_line(&mut reply).map_err(|e| e.to_string())?;
+    match serde_json::from_str::<Option<String>>(&reply) {
+        Ok(None) => Ok(()),
+        Ok(Some(error)) => Err(error),
+        Err(e) => Err(format!("Invalid reply from running instance: {}", e)),
+    }
+}
+
+// 在后台线程上接受其他实例的连接，每个连接在自己的线程上处理，
+// 连上后不发送参数的进程不会挡住后面的连接
+pub fn start_listener(app_handle: tauri::AppHandle, listener: Listener) {
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let app_handle = app_handle.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle_connection(&app_handle, stream) {
+                            warn!(target: TRAY, "Failed to handle forwarded arguments: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!(target: TRAY, "Failed to accept forwarded arguments: {}", e),
+            }
+        }
+    });
+}
+
+fn handle_connection(app_handle: &tauri::AppHandle, stream: Stream) -> io::Result<()> {
+    stream.set_recv_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+
+    let result = serde_json::from_str::<Vec<String>>(&request)
+        .map_err(|e| AppError::invalid_input("Invalid forwarded arguments").with_detail(e))
+        .and_then(|args| {
+            info!(target: TRAY, "Received arguments from another instance: {:?}", args);
+            let mut actions = parse_args(&args)?;
+            // 不带参数再次启动时显示主窗口
+            if actions.is_empty() {
+                actions.push(LaunchAction::Show);
+            }
+            execute(app_handle, &actions)
+        });
+    if let Err(e) = &result {
+        warn!(target: TRAY, "Forwarded arguments failed: {}", e);
+    }
+
+    let reply = serde_json::to_string(&result.err().map(|e| e.to_string())).unwrap_or_default();
+    writeln!(reader.get_mut(), "{}", reply)
+}
+
+// 解析命令行参数，不认识的参数记录警告后忽略
+pub fn parse_args(args: &[String]) -> AppResult<Vec<LaunchAction>> {
+    let mut actions = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--show" => actions.push(LaunchAction::Show),
+            "--switch" => {
+                let target = args.next().ok_or_else(|| {
+                    AppError::invalid_input("--switch requires a device id or name")
+                })?;
+                actions.push(LaunchAction::Switch(target.clone()));
+            }
+            other => match other.strip_prefix("--switch=") {
+                Some(target) => actions.push(LaunchAction::Switch(target.to_string())),
+                None => warn!(target: TRAY, "Ignoring unknown argument: {}", other),
+            },
+        }
+    }
+    Ok(actions)
+}
+
+pub fn execute(app_handle: &tauri::AppHandle, actions: &[LaunchAction]) -> AppResult<()> {
+    for action in actions {
+        match action {
+            LaunchAction::Show => {
+                let window = main_window(app_handle)?;
+                window.show().map_err(window_error)?;
+                let _ = window.set_focus();
+            }
+            LaunchAction::Switch(target) => {
+                let cause = SwitchCause::Remote {
+                    source: "command line".to_string(),
+                };
+                switch_device(app_handle, target, cause)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn parse(args: &[&str]) -> AppResult<Vec<LaunchAction>> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_args(&args)
+    }
+
+    fn switch(target: &str) -> LaunchAction {
+        LaunchAction::Switch(target.to_string())
+    }
+
+    #[test]
+    fn parses_show_and_switch() {
+        assert_eq!(parse(&[]).unwrap(), []);
+        assert_eq!(parse(&["--show"]).unwrap(), [LaunchAction::Show]);
+        assert_eq!(
+            parse(&["--switch", "Headphones"]).unwrap(),
+            [switch("Headphones")]
+        );
+        assert_eq!(
+            parse(&["--switch=USB Audio (2)"]).unwrap(),
+            [switch("USB Audio (2)")]
+        );
+        // 按出现的顺序执行
+        assert_eq!(
+            parse(&["--switch", "{0.0.0.00000000}", "--show"]).unwrap(),
+            [switch("{0.0.0.00000000}"), LaunchAction::Show]
+        );
+    }
+
+    #[test]
+    fn switch_needs_a_value() {
+        let error = parse(&["--show", "--switch"]).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidInput);
+        assert_eq!(error.message, "--switch requires a device id or name");
+    }
+
+    #[test]
+    fn unknown_arguments_are_ignored() {
+        assert_eq!(
+            parse(&["--minimized", "-v", "--show"]).unwrap(),
+            [LaunchAction::Show]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+// 应用状态：托管在 Tauri 中的共享状态，以及取用各个托管对象的辅助函数
+use crate::audio::{AudioClient, AudioDevice};
+use crate::config::{HttpApiConfig, MqttConfig, OscConfig};
+use crate::error::{AppError, AppResult};
+use crate::event_history::{EventKind, SharedEventHistory};
+use crate::focus_switch::FocusSwitchConfig;
+use crate::monitor::{MonitorRule, WindowTracker};
+use crate::process_monitor::{ProcessRule, ProcessTracker};
+use crate::rules::{Rule, RuleLog};
+use crate::schedule::{ScheduleRule, Scheduler};
+use crate::switch_history::SwitchHistory;
+use crate::window_source::SharedWindowSource;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+
+// 全局状态管理
+#[derive(Default)]
+pub struct AppState {
+    pub is_pinned: bool,
+    pub monitored_windows: Vec<MonitorRule>,
+    pub auto_hide_delay: u64,                    // 秒
+    pub window_tracker: WindowTracker,           // 按句柄 + pid 记录上次检查时存在的窗口
+    pub switch_history: SwitchHistory,           // 设备切换历史，用于恢复之前的设备
+    pub focus_switch: FocusSwitchConfig,         // 焦点跟随切换设备的配置
+    pub process_rules: Vec<ProcessRule>,         // 进程启动/退出规则
+    pub process_tracker: ProcessTracker,         // 按 pid 记录上次看到的相关进程
+    pub schedule_rules: Vec<ScheduleRule>,       // 定时规则
+    pub scheduler: Scheduler,                    // 记录上次检查定时规则的时间
+    pub rules: Vec<Rule>,                        // 用户定义的引擎规则
+    pub rule_log: RuleLog,                       // 规则触发记录
+    pub known_devices: Option<Vec<AudioDevice>>, // 上次检查时的输出设备，用于设备连接/断开触发
+    pub device_subscribers: usize,               // 订阅设备事件的外部连接数
+    pub monitoring_active: bool,                 // 是否正在监听窗口
+    pub dark_mode: bool,                         // 深色模式
+    pub http_api: HttpApiConfig,                 // 本地 HTTP 接口
+    pub mqtt: MqttConfig,                        // MQTT 桥接
+    pub osc: OscConfig,                          // OSC 服务器
+}
+
+pub type SharedState = Arc<Mutex<AppState>>;
+
+// 在锁内读写应用状态，状态未初始化或锁已中毒时返回错误
+pub fn with_state<T>(
+    app_handle: &tauri::AppHandle,
+    f: impl FnOnce(&mut AppState) -> T,
+) -> AppResult<T> {
+    let state = app_handle
+        .try_state::<SharedState>()
+        .ok_or_else(AppError::state)?;
+    let mut app_state = state.lock().map_err(|_| AppError::state())?;
+    Ok(f(&mut app_state))
+}
+
+pub fn audio(app_handle: &tauri::AppHandle) -> AppResult<AudioClient> {
+    app_handle
+        .try_state::<AudioClient>()
+        .map(|audio| audio.inner().clone())
+        .ok_or_else(|| AppError::backend("Audio worker not initialized"))
+}
+
+// 写入事件历史，detail 是原始事件
+pub fn record_event(
+    app_handle: &tauri::AppHandle,
+    kind: EventKind,
+    summary: String,
+    detail: impl Serialize,
+) {
+    if let Some(history) = app_handle.try_state::<SharedEventHistory>() {
+        if let Ok(mut history) = history.lock() {
+            let detail = serde_json::to_value(detail).unwrap_or_default();
+            history.record(kind, summary, detail);
+        }
+    }
+}
+
+pub fn event_history(app_handle: &tauri::AppHandle) -> AppResult<SharedEventHistory> {
+    app_handle
+        .try_state::<SharedEventHistory>()
+        .map(|history| history.inner().clone())
+        .ok_or_else(|| AppError::internal("Event history not initialized"))
+}
+
+// 窗口操作（显示、隐藏、置顶）的错误
+pub fn window_error(error: tauri::Error) -> AppError {
+    AppError::internal("Window operation failed").with_detail(error)
+}
+
+pub fn main_window(app_handle: &tauri::AppHandle) -> AppResult<tauri::WebviewWindow> {
+    app_handle
+        .get_webview_window("main")
+        .ok_or_else(|| AppError::not_found("Main window not found"))
+}
+
+pub fn window_source(app_handle: &tauri::AppHandle) -> AppResult<SharedWindowSource> {
+    app_handle
+        .try_state::<SharedWindowSource>()
+        .map(|source| source.inner().clone())
+        .ok_or_else(|| AppError::backend("Window source not initialized"))
+}
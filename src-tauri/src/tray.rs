@@ -0,0 +1,329 @@
+// 托盘图标和窗口管理：托盘菜单、窗口位置的保存与恢复，以及切换器的自动隐藏倒计时
+use crate::auto_hide::{AutoHideCommand, AutoHideSender, AutoHideStatus, AutoHideTimer};
+use crate::commands::save_window_position;
+use crate::config::load_window_config;
+use crate::error::{AppError, AppResult};
+use crate::logging::{CONFIG, MONITOR, TRAY};
+use crate::state::{SharedState, main_window, window_error, with_state};
+use log::{debug, info};
+use std::sync::mpsc::{RecvTimeoutError, channel};
+use std::time::{Duration, Instant};
+use tauri::{
+    Emitter, Manager, WebviewWindowBuilder,
+    menu::{Menu, MenuItem},
+    tray::TrayIconBuilder,
+};
+
+// 置顶显示切换器并开始自动隐藏倒计时（规则的"显示切换器"动作）
+pub fn show_switcher(app_handle: &tauri::AppHandle, trigger: &str) -> AppResult<()> {
+    let window = main_window(app_handle)?;
+    window.show().map_err(window_error)?;
+    let _ = window.set_focus();
+    window
+        .set_always_on_top(true)
+        .map_err(|e| AppError::internal("Failed to set window always on top").with_detail(e))?;
+
+    let _ = app_handle.emit("monitor-triggered", trigger);
+
+    // 延迟为 0 表示立即隐藏
+    let delay = with_state(app_handle, |app_state| app_state.auto_hide_delay).unwrap_or(5);
+    send_auto_hide(
+        app_handle,
+        AutoHideCommand::Start(Duration::from_secs(delay)),
+    );
+    Ok(())
+}
+
+pub fn send_auto_hide(app_handle: &tauri::AppHandle, command: AutoHideCommand) {
+    if let Some(sender) = app_handle.try_state::<AutoHideSender>() {
+        let _ = sender.send(command);
+    }
+}
+
+// 启动自动隐藏倒计时线程，每秒发送 auto-hide-tick 事件
+pub fn start_auto_hide_loop(app_handle: tauri::AppHandle) -> AutoHideSender {
+    let (sender, commands) = channel();
+
+    std::thread::spawn(move || {
+        let mut timer = AutoHideTimer::default();
+        let mut last_status: Option<AutoHideStatus> = None;
+
+        loop {
+            // 倒计时进行中时等到剩余秒数变化，否则一直等下一条命令
+            let timeout = timer
+                .next_tick(Instant::now())
+                .unwrap_or(Duration::from_secs(60));
+
+            match commands.recv_timeout(timeout) {
+                Ok(AutoHideCommand::Start(delay)) => timer.start(delay, Instant::now()),
+                Ok(AutoHideCommand::Cancel) => {
+                    if timer.is_active() {
+                        timer.cancel();
+                        info!(target: MONITOR, "Auto hide cancelled");
+                        restore_always_on_top(&app_handle);
+                    }
+                }
+                Ok(AutoHideCommand::Hover(hovered)) => timer.set_hovered(hovered, Instant::now()),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let now = Instant::now();
+            if timer.is_expired(now) {
+                timer.cancel();
+                hide_switcher_after_countdown(&app_handle);
+            }
+
+            let status = timer.status(now);
+            if last_status != Some(status) {
+                let _ = app_handle.emit("auto-hide-tick", status);
+                last_status = Some(status);
+            }
+        }
+    });
+
+    sender
+}
+
+// 倒计时结束：用户已经手动置顶时保持显示，否则取消置顶并隐藏
+fn hide_switcher_after_countdown(app_handle: &tauri::AppHandle) {
+    if with_state(app_handle, |app_state| app_state.is_pinned).unwrap_or(false) {
+        info!(target: MONITOR, "Auto hide skipped, window is pinned");
+        return;
+    }
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.set_always_on_top(false);
+        let _ = window.hide();
+        info!(target: MONITOR, "Auto hid switcher after countdown");
+    }
+}
+
+// 倒计时被取消后恢复为用户设置的置顶状态
+fn restore_always_on_top(app_handle: &tauri::AppHandle) {
+    let pinned = with_state(app_handle, |app_state| app_state.is_pinned).unwrap_or(false);
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.set_always_on_top(pinned);
+    }
+}
+
+// 创建托盘图标和菜单：显示/隐藏窗口、置顶、偏好设置、退出
+pub fn build_tray(app: &tauri::App) -> tauri::Result<()> {
+    // 创建菜单项
+    let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+    let toggle_item = MenuItem::with_id(app, "toggle", "显示/隐藏窗口", true, None::<&str>)?;
+    let pin_item = MenuItem::with_id(app, "pin", "置顶显示", true, None::<&str>)?;
+    let config_item = MenuItem::with_id(app, "preference", "偏好设置", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&toggle_item, &pin_item, &config_item, &quit_item])?;
+
+    // 构建托盘
+    TrayIconBuilder::new()
+        .icon(app.default_window_icon().unwrap().clone())
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.0.as_str() {
+            "quit" => {
+                app.exit(0);
+            }
+            "toggle" => {
+                let window = app.get_webview_window("main").unwrap();
+                if window.is_visible().unwrap_or(false) {
+                    window.hide().unwrap();
+                } else {
+                    window.show().unwrap();
+                    window.set_focus().unwrap();
+                }
+            }
+            "pin" => {
+                // 切换置顶显示 - 获取当前状态并切换
+                let current_pinned = if let Some(state) = app.try_state::<SharedState>() {
+                    if let Ok(app_state) = state.lock() {
+                        app_state.is_pinned
+                    } else {
+                        false
+                    }
+                } else {
+                    false
+                };
+
+                let new_pinned = !current_pinned;
+
+                // 更新状态
+                if let Some(state) = app.try_state::<SharedState>() {
+                    if let Ok(mut app_state) = state.lock() {
+                        app_state.is_pinned = new_pinned;
+                    }
+                }
+
+                // 设置窗口置顶状态
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.set_always_on_top(new_pinned);
+                    let _ = window.emit("pin-mode-changed", new_pinned);
+                }
+
+                info!(
+                    target: TRAY,
+                    "Pin mode {} via tray menu",
+                    if new_pinned { "enabled" } else { "disabled" }
+                );
+            }
+
+            "preference" => {
+                let preference_window = app.get_webview_window("preference");
+                if let Some(window) = preference_window {
+                    window.show().unwrap();
+                    window.set_focus().unwrap();
+                } else {
+                    let mut builder = WebviewWindowBuilder::new(
+                        app,
+                        "preference",
+                        tauri::WebviewUrl::App("preference.html".into()),
+                    )
+                    .title("偏好设置")
+                    .min_inner_size(800.0, 600.0);
+
+                    // 尝试加载保存的窗口位置
+                    if let Some(position) = load_window_config(app).preference_window_position {
+                        builder = builder
+                            .position(position.x as f64, position.y as f64)
+                            .inner_size(position.width as f64, position.height as f64);
+                        debug!(
+                            target: CONFIG,
+                            "Restored preference window position: {}x{} at ({}, {})",
+                            position.width, position.height, position.x, position.y
+                        );
+                    } else {
+                        debug!(
+                            target: CONFIG,
+                            "No saved preference window position found, using default"
+                        );
+                    }
+
+                    builder.build().unwrap();
+                }
+            }
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let tauri::tray::TrayIconEvent::Click {
+                button: tauri::tray::MouseButton::Left,
+                button_state: tauri::tray::MouseButtonState::Up,
+                ..
+            } = event
+            {
+                // 左键点击显示/隐藏主窗口
+                let app = tray.app_handle();
+                if let Some(window) = app.get_webview_window("main") {
+                    if window.is_visible().unwrap_or(false) {
+                        let _ = window.hide();
+                    } else {
+                        let _ = window.show();
+                        let _ = window.set_focus();
+                    }
+                }
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
+
+// 关闭主窗口时改为隐藏；主窗口和偏好设置窗口移动、调整大小或关闭时保存位置
+pub fn on_window_event(window: &tauri::Window, event: &tauri::WindowEvent) {
+    match event {
+        tauri::WindowEvent::CloseRequested { api, .. } => {
+            // 只对主窗口阻止关闭，其他窗口（如偏好设置）允许正常关闭
+            if window.label() == "main" {
+                // 主窗口关闭前保存位置
+                if let Ok(position) = window.outer_position() {
+                    if let Ok(size) = window.outer_size() {
+                        let _ = save_window_position(
+                            window.app_handle().clone(),
+                            "main".to_string(),
+                            position.x,
+                            position.y,
+                            size.width,
+                            size.height,
+                        );
+                    }
+                }
+                // 主窗口没有关闭按钮，如果触发关闭事件就隐藏窗口
+                api.prevent_close();
+                let _ = window.hide();
+            } else if window.label() == "preference" {
+                // 偏好设置窗口关闭前保存位置
+                if let Ok(position) = window.outer_position() {
+                    if let Ok(size) = window.inner_size() {
+                        let _ = save_window_position(
+                            window.app_handle().clone(),
+                            "preference".to_string(),
+                            position.x,
+                            position.y,
+                            size.width,
+                            size.height,
+                        );
+                        debug!(
+                            target: CONFIG,
+                            "Saved preference window position: {}x{} at ({}, {})",
+                            size.width, size.height, position.x, position.y
+                        );
+                    }
+                }
+            }
+        }
+        tauri::WindowEvent::Moved(position) => {
+            // 窗口移动时保存位置
+            if window.label() == "main" {
+                if let Ok(size) = window.outer_size() {
+                    let _ = save_window_position(
+                        window.app_handle().clone(),
+                        window.label().to_string(),
+                        position.x,
+                        position.y,
+                        size.width,
+                        size.height,
+                    );
+                }
+            } else if window.label() == "preference" {
+                if let Ok(size) = window.inner_size() {
+                    let _ = save_window_position(
+                        window.app_handle().clone(),
+                        window.label().to_string(),
+                        position.x,
+                        position.y,
+                        size.width,
+                        size.height,
+                    );
+                }
+            }
+        }
+        tauri::WindowEvent::Resized(size) => {
+            // 窗口调整大小时保存位置
+            if window.label() == "main" {
+                if let Ok(position) = window.outer_position() {
+                    let _ = save_window_position(
+                        window.app_handle().clone(),
+                        window.label().to_string(),
+                        position.x,
+                        position.y,
+                        size.width,
+                        size.height,
+                    );
+                }
+            } else if window.label() == "preference" {
+                if let Ok(position) = window.outer_position() {
+                    // 对于偏好设置窗口，size 参数已经是 inner_size
+                    let _ = save_window_position(
+                        window.app_handle().clone(),
+                        window.label().to_string(),
+                        position.x,
+                        position.y,
+                        size.width,
+                        size.height,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}